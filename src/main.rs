@@ -1,10 +1,840 @@
-use std::collections::HashMap;
-use std::io::{self, Write};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+// 单局对局记录
+#[derive(Clone, Serialize, Deserialize)]
+struct MatchRecord {
+    round: u32,
+    timestamp: u64,
+    winner_id: PlayerId,
+    // 本局用时，仅在开启计时时记录
+    duration: Option<Duration>,
+    // 胜者因连胜额外获得的奖励分，单独记录以便核对
+    #[serde(default)]
+    bonus: i32,
+    // 参与本局的玩家（包括胜者），按序号排列
+    #[serde(default)]
+    participants: Vec<PlayerId>,
+    // 本局结束后所有玩家的积分快照，用于绘制积分走势
+    #[serde(default)]
+    scores_after: HashMap<PlayerId, i32>,
+    // 本局权重，扣分和胜者所得都乘以该值
+    #[serde(default = "default_round_weight")]
+    weight: u32,
+}
+
+fn default_round_weight() -> u32 {
+    1
+}
+
+// 玩家序号。与局数、人数等普通整数区分开，避免误传；存档中仍保存为数字
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+struct PlayerId(usize);
+
+impl PlayerId {
+    fn get(self) -> usize {
+        self.0
+    }
+}
+
+impl From<usize> for PlayerId {
+    fn from(id: usize) -> Self {
+        PlayerId(id)
+    }
+}
+
+impl From<PlayerId> for usize {
+    fn from(id: PlayerId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+// 可暂停的单局计时器，暂停期间的时间不计入本局用时
+#[derive(Clone)]
+struct RoundTimer {
+    running_since: Option<Instant>,
+    accumulated: Duration,
+}
+
+impl RoundTimer {
+    fn start() -> Self {
+        RoundTimer {
+            running_since: Some(Instant::now()),
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.accumulated += since.elapsed();
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.accumulated + self.running_since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    // 结束本局计时并返回用时，随后立即开始下一局的计时
+    fn lap(&mut self) -> Duration {
+        let elapsed = self.elapsed();
+        *self = RoundTimer::start();
+        elapsed
+    }
+}
+
+// 用于展示的对局摘要，胜者名称在展示时根据序号解析
+struct RoundSummary {
+    round: u32,
+    timestamp: u64,
+    winner_id: PlayerId,
+    winner_name: Option<String>,
+    bonus: i32,
+    weight: u32,
+}
+
+// 一名玩家的完整战绩，供界面一次取得所需的全部信息
+#[derive(Clone, PartialEq, Debug)]
+struct PlayerRecord {
+    name: String,
+    score: i32,
+    rank: usize,
+    wins: u32,
+    // 参赛但未胜出的局数；未记录参赛玩家的旧对局和已清空的对局不计入
+    losses: u32,
+    current_streak: u32,
+    best_streak: u32,
+    withdrawn: bool,
+}
+
+// 积分规则：每局未胜出的玩家按名次扣分，胜者获得所有扣分之和
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct ScoringRule {
+    // 每位未胜出玩家默认扣除的分数
+    loser_delta: i32,
+    // 按名次加重扣分：第 i 个元素为本局开始前排第 i+1 名的玩家未胜出时扣除的分数，
+    // 未列出的名次扣 loser_delta。为空时即标准规则。同分并列的玩家按并列名次扣分。
+    rank_penalties: Vec<i32>,
+}
+
+impl Default for ScoringRule {
+    fn default() -> Self {
+        ScoringRule {
+            loser_delta: 1,
+            rank_penalties: Vec::new(),
+        }
+    }
+}
+
+// 单名玩家单局扣分的上限，避免多局累计后积分溢出
+const MAX_PENALTY: i32 = 1000;
+
+// 加权对局的权重上限，与 MAX_PENALTY 一起限制单局的积分变化
+const MAX_WEIGHT: u32 = 100;
+
+impl ScoringRule {
+    // 检查规则是否合理：各项扣分都必须在 0 到 MAX_PENALTY 之间（扣分为负时胜者反而失分），
+    // 且至少有一项大于 0（全部为 0 时每局积分都不会变化）。
+    // 允许 loser_delta 为 0 而只对部分名次扣分，如 --loser-delta 0 --rank-penalties 1 只扣领先者。
+    fn validate(&self) -> Result<(), String> {
+        let penalties = std::iter::once(self.loser_delta).chain(self.rank_penalties.iter().copied());
+        for penalty in penalties.clone() {
+            if penalty < 0 {
+                return Err(format!("扣分不能为负数（{}），否则胜者会失分", penalty));
+            }
+            if penalty > MAX_PENALTY {
+                return Err(format!("扣分 {} 过大，单局最多扣 {} 分", penalty, MAX_PENALTY));
+            }
+        }
+        if penalties.into_iter().all(|penalty| penalty == 0) {
+            return Err("所有扣分都为 0，每局积分都不会变化".to_string());
+        }
+        Ok(())
+    }
+
+    // 本局开始前排名为 rank 的玩家未胜出时扣除的分数
+    fn penalty_for_rank(&self, rank: usize) -> i32 {
+        rank.checked_sub(1)
+            .and_then(|index| self.rank_penalties.get(index))
+            .copied()
+            .unwrap_or(self.loser_delta)
+    }
+
+    // 单名玩家单局可能被扣除的最大分数
+    fn max_penalty(&self) -> i32 {
+        self.rank_penalties
+            .iter()
+            .copied()
+            .chain(std::iter::once(self.loser_delta))
+            .max()
+            .unwrap_or(self.loser_delta)
+    }
+}
+
+// 计分方式
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ScoringMode {
+    // 所有未胜出玩家扣除相同分数
+    Standard,
+    // 按本局开始前的名次扣除不同分数
+    RankScaled,
+}
+
+// 当前生效的计分配置（只读汇总），供帮助、报告等处展示
+#[derive(Clone, PartialEq, Debug)]
+struct ScoringConfig {
+    mode: ScoringMode,
+    loser_delta: i32,
+    rank_penalties: Vec<i32>,
+    min_score: Option<i32>,
+    streak_bonus: Option<StreakBonus>,
+    tie_break: TieBreak,
+    // 描述中的分数按积分的显示精度和舍入方式格式化
+    score_precision: usize,
+    rounding_mode: RoundingMode,
+}
+
+impl ScoringConfig {
+    // 以中文逐行描述计分配置
+    fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match self.mode {
+            ScoringMode::Standard => lines.push(format!(
+                "其他玩家各 -{} 分，胜出玩家获得所有扣分之和",
+                self.loser_delta
+            )),
+            ScoringMode::RankScaled => {
+                let penalties: Vec<String> = self
+                    .rank_penalties
+                    .iter()
+                    .enumerate()
+                    .map(|(index, penalty)| format!("第{}名 -{}", index + 1, penalty))
+                    .collect();
+                lines.push(format!(
+                    "按名次扣分: {}，其余名次 -{}（同分并列按并列名次扣分），胜出玩家获得所有扣分之和",
+                    penalties.join("，"),
+                    self.loser_delta
+                ));
+            }
+        }
+        lines.push(match self.min_score {
+            Some(floor) => format!("积分下限: {}", floor),
+            None => "积分下限: 无".to_string(),
+        });
+        lines.push(match self.streak_bonus {
+            Some(rule) => format!(
+                "连胜奖励: 每连胜 {} 局额外 {} 分",
+                rule.milestone,
+                format_delta(rule.bonus, self.score_precision, self.rounding_mode)
+            ),
+            None => "连胜奖励: 无".to_string(),
+        });
+        lines.push(
+            match self.tie_break {
+                TieBreak::None => "同分排名: 并列（按序号显示）",
+                TieBreak::Wins => "同分排名: 胜场多者靠前，仍相同则并列（按序号显示）",
+            }
+            .to_string(),
+        );
+        lines
+    }
+}
+
+// 连胜奖励：连胜局数每达到 milestone 的倍数时，胜者额外获得 bonus 分
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+struct StreakBonus {
+    milestone: u32,
+    bonus: i32,
+}
+
+// 一局的结算结果（尚未写入积分板）：每位未胜出玩家的扣分，以及胜者结算前后的积分
+struct Settlement {
+    losers: Vec<SettledLoser>,
+    winner_before: i32,
+    // 含连胜奖励
+    winner_after: i32,
+    // 其他玩家的扣分之和，不含连胜奖励
+    gained: i32,
+}
+
+struct SettledLoser {
+    id: PlayerId,
+    penalty: i32,
+    before: i32,
+    // 扣分后尚未按积分下限截断的积分
+    unclamped: i32,
+}
+
+// 未指定名称时副积分列的名称
+const DEFAULT_SECONDARY_LABEL: &str = "副积分";
+
+// 副积分（如奖励筹码）：与主积分分开记录，只用于显示和导出，不影响排名。
+// 每局胜者自动获得 per_win 分，也可以单独调整。
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct SecondaryScore {
+    label: String,
+    per_win: i32,
+}
+
+// 比赛报告中"进步最快"奖项统计的局数
+const MOST_IMPROVED_WINDOW: usize = 5;
+
+// 会话统计：开始时间、已进行的时长和对局节奏
+struct SessionStats {
+    started_at: u64,
+    elapsed: Duration,
+    rounds: u32,
+    // 每分钟局数，会话时长为 0 时无法计算
+    rounds_per_minute: Option<f64>,
+}
+
+// 批量导入或批量记录的结果：成功的数量，以及被跳过的行或条目（从 1 开始的编号, 原因）
+struct ImportSummary {
+    added: usize,
+    skipped: Vec<(usize, String)>,
+}
+
+// 简单的可设定种子的伪随机数生成器（SplitMix64），同一种子总是得到相同的序列
+struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // 返回 [0, bound) 范围内的随机数
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+// 未指定种子时使用当前时间生成种子
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// 某一时刻冻结的积分快照，用于与之后的实时积分对比
+#[derive(Clone, Serialize, Deserialize)]
+struct FrozenSnapshot {
+    label: String,
+    timestamp: u64,
+    // 冻结时已进行的局数
+    round: u32,
+    scores: HashMap<PlayerId, i32>,
+}
+
+// 清空对局记录时保留的汇总，使局数、胜场、连胜和积分校验在清空后保持不变
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct ClearedHistory {
+    rounds: u32,
+    // 按权重计的局数，用于积分校验
+    weighted_rounds: u64,
+    total_bonus: i64,
+    wins: HashMap<PlayerId, u32>,
+    // 清空时最后一局的胜者及其连胜局数
+    streak: Option<(PlayerId, u32)>,
+    // 导入积分板时各玩家的初始积分，积分校验以此为起点
+    #[serde(default)]
+    baseline: HashMap<PlayerId, i32>,
+}
+
+// 冻结对比中的一行：（序号, 名称, 冻结时积分, 当前积分）
+type FrozenRow<'a> = (PlayerId, &'a str, Option<i32>, i32);
+
+// 淘汰赛中的一场对阵：两个位置上的（序号, 名称），None 表示轮空
+type BracketMatch<'a> = (Option<(PlayerId, &'a str)>, Option<(PlayerId, &'a str)>);
+
+// 两个积分板之间单个玩家的变化：before 为 None 表示新增，after 为 None 表示移除
+#[derive(Debug, PartialEq)]
+struct ScoreDiff {
+    name: String,
+    // 按序号匹配且名称不同时记录原名称
+    previous_name: Option<String>,
+    before: Option<i32>,
+    after: Option<i32>,
+}
+
+// 显示积分时舍入到指定小数位数的方式，只影响显示，不改变内部数值
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+enum RoundingMode {
+    // 向下取整
+    Floor,
+    // 向上取整
+    Ceil,
+    // 四舍五入，0.5 远离 0 进位
+    #[default]
+    Round,
+    // 直接截去多余位数（向 0 取整）
+    Truncate,
+}
+
+impl RoundingMode {
+    fn apply(self, value: f64, precision: usize) -> f64 {
+        let scale = 10f64.powi(precision.min(15) as i32);
+        let scaled = value * scale;
+        let rounded = match self {
+            RoundingMode::Floor => scaled.floor(),
+            RoundingMode::Ceil => scaled.ceil(),
+            RoundingMode::Round => scaled.round(),
+            RoundingMode::Truncate => scaled.trunc(),
+        };
+        rounded / scale
+    }
+}
+
+// 是否在正数积分前显示 "+" 号，用于强调得分；只影响显示和导出文本
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+enum PositiveSign {
+    // 不显示（默认）
+    #[default]
+    Off,
+    // 正数显示 "+"，0 不显示
+    NonZero,
+    // 正数和 0 都显示 "+"
+    All,
+}
+
+impl PositiveSign {
+    // 给已格式化的积分文本加上符号；负数自带 "-"，不做处理
+    fn apply(self, text: String) -> String {
+        let show = match self {
+            PositiveSign::Off => false,
+            PositiveSign::NonZero => text != "0",
+            PositiveSign::All => true,
+        };
+        if show && !text.starts_with('-') {
+            format!("+{}", text)
+        } else {
+            text
+        }
+    }
+}
+
+// 选择已退出的玩家作为胜者时的处理方式
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+enum WithdrawnWinnerPolicy {
+    // 报错，本局不记录（默认）
+    #[default]
+    Reject,
+    // 自动恢复该玩家并记录本局，同时给出提示
+    Reinstate,
+}
+
+// 积分榜表格的边框样式
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+enum TableStyle {
+    // 仅在表头下方画一条横线
+    #[default]
+    Plain,
+    // 使用 + - | 画边框
+    Ascii,
+    // 使用 ┌─┐ 等制表符画边框
+    Unicode,
+}
+
+// 带边框的表格所用字符
+struct BoxChars {
+    horizontal: char,
+    vertical: char,
+    // 每行依次为：左端、列间、右端；分为顶部、表头下方、底部三行
+    top: [char; 3],
+    middle: [char; 3],
+    bottom: [char; 3],
+}
+
+impl TableStyle {
+    fn box_chars(self) -> Option<BoxChars> {
+        match self {
+            TableStyle::Plain => None,
+            TableStyle::Ascii => Some(BoxChars {
+                horizontal: '-',
+                vertical: '|',
+                top: ['+', '+', '+'],
+                middle: ['+', '+', '+'],
+                bottom: ['+', '+', '+'],
+            }),
+            TableStyle::Unicode => Some(BoxChars {
+                horizontal: '─',
+                vertical: '│',
+                top: ['┌', '┬', '┐'],
+                middle: ['├', '┼', '┤'],
+                bottom: ['└', '┴', '┘'],
+            }),
+        }
+    }
+}
+
+// 玩家的显示颜色，用于终端表格和 SVG 图表
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+// 相互之间容易区分的调色板，玩家按序号循环取色
+const PLAYER_COLORS: [Color; 8] = [
+    Color { r: 0xe6, g: 0x19, b: 0x4b },
+    Color { r: 0x3c, g: 0xb4, b: 0x4b },
+    Color { r: 0x43, g: 0x63, b: 0xd8 },
+    Color { r: 0xf5, g: 0x82, b: 0x31 },
+    Color { r: 0x91, g: 0x1e, b: 0xb4 },
+    Color { r: 0x42, g: 0xd4, b: 0xf4 },
+    Color { r: 0xf0, g: 0x32, b: 0xe6 },
+    Color { r: 0x9a, g: 0x63, b: 0x24 },
+];
+
+impl Color {
+    // SVG/HTML 使用的 #rrggbb 形式
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    // 用 24 位 ANSI 转义序列给终端文本上色
+    fn paint(self, text: &str) -> String {
+        format!("\x1b[38;2;{};{};{}m{}\x1b[0m", self.r, self.g, self.b, text)
+    }
+}
+
+// 何时在终端输出颜色
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+enum ColorChoice {
+    // 标准输出是终端且未设置 NO_COLOR 环境变量时上色
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self, no_color_env: bool, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => !no_color_env && is_terminal,
+        }
+    }
+}
+
+// 去掉文本中的 ANSI 颜色转义序列，用于计算实际显示宽度
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // 跳过 ESC [ ... m
+            for c in chars.by_ref() {
+                if c == 'm' {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+// 文本在终端中的显示宽度（中日韩字符占两列，颜色转义序列不占宽度）
+fn visible_width(text: &str) -> usize {
+    strip_ansi(text).width()
+}
+
+// 按终端显示宽度在右侧补空格
+fn pad_display(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(visible_width(text));
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+// 渲染带边框的表格，列宽按显示宽度计算，保证中文内容也能对齐
+fn render_box_table(chars: &BoxChars, headers: &[&str], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| visible_width(cell))
+                .chain(std::iter::once(header.width()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let border = |[left, join, right]: [char; 3]| {
+        let segments: Vec<String> = widths
+            .iter()
+            .map(|width| chars.horizontal.to_string().repeat(width + 2))
+            .collect();
+        format!("{}{}{}\n", left, segments.join(&join.to_string()), right)
+    };
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!(" {} ", pad_display(cell, *width)))
+            .collect();
+        let vertical = chars.vertical.to_string();
+        format!("{}{}{}\n", vertical, padded.join(&vertical), vertical)
+    };
+
+    let mut table = border(chars.top);
+    table.push_str(&line(headers.to_vec()));
+    table.push_str(&border(chars.middle));
+    for row in rows {
+        table.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    table.push_str(&border(chars.bottom));
+    table
+}
+
+// 复制到剪贴板的积分榜格式：纯文本或 Markdown 表格
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum StandingsFormat {
+    Plain,
+    Markdown,
+}
+
+impl StandingsFormat {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "plain" | "text" => Ok(StandingsFormat::Plain),
+            "markdown" | "md" => Ok(StandingsFormat::Markdown),
+            _ => Err(format!("无效的积分榜格式: {}（可选 plain、markdown）", value)),
+        }
+    }
+}
+
+// 玩家列表的排列方式
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PlayerSort {
+    Id,
+    // 按名称的 Unicode 码位排列（英文字母不区分大小写）；中文名称不按拼音或笔画，而是按汉字在 Unicode 中的编码顺序
+    Name,
+    // 按当前排名排列
+    Score,
+}
+
+impl PlayerSort {
+    // 写在导出文件中的排序说明
+    fn describe(self) -> &'static str {
+        match self {
+            PlayerSort::Id => "按序号",
+            PlayerSort::Name => "按名称",
+            PlayerSort::Score => "按名次（名次相同时按序号）",
+        }
+    }
+}
+
+// 积分相同时的排名依据；依据也相同时始终再按序号排列，保证顺序稳定
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+enum TieBreak {
+    // 不区分，积分相同的玩家名次并列
+    #[default]
+    None,
+    // 胜场多者排名靠前
+    Wins,
+}
+
+// 玩家名称校验策略
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct NamePolicy {
+    // 拒绝全部由 ASCII 数字组成的名称，避免与序号输入混淆
+    reject_numeric: bool,
+    // 查重时忽略重音符号和大小写（如 "José" 与 "jose" 视为重名），名称仍按原样保存
+    #[serde(default)]
+    accent_insensitive: bool,
+}
+
+impl NamePolicy {
+    // 用于查重比较的名称形式
+    fn comparison_key(&self, name: &str) -> String {
+        if self.accent_insensitive {
+            name.nfkd()
+                .filter(|c| !is_combining_mark(*c))
+                .collect::<String>()
+                .to_lowercase()
+        } else {
+            name.to_string()
+        }
+    }
+
+    // 查找与 name 冲突的已有名称
+    fn find_conflict<'a, I>(&self, name: &str, existing: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let key = self.comparison_key(name);
+        existing
+            .into_iter()
+            .find(|other| self.comparison_key(other) == key)
+            .map(String::as_str)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct Scoreboard {
-    players: HashMap<usize, String>,
-    scores: HashMap<usize, i32>,
+    players: HashMap<PlayerId, String>,
+    scores: HashMap<PlayerId, i32>,
     next_id: usize,
+    history: Vec<MatchRecord>,
+    // 积分下限，设置后任何玩家的积分都不会低于该值
+    min_score: Option<i32>,
+    name_policy: NamePolicy,
+    // 单局计时器，为 None 时表示未开启计时；计时状态不保存到文件
+    #[serde(skip)]
+    round_timer: Option<RoundTimer>,
+    // 仅影响显示顺序的手动优先级：正数置顶，负数置底
+    pins: HashMap<PlayerId, i32>,
+    // 显示在名称前的装饰标记（如卫冕冠军的 👑），不影响积分和名次
+    #[serde(default)]
+    tags: HashMap<PlayerId, String>,
+    #[serde(default)]
+    tie_break: TieBreak,
+    // 已退出比赛的玩家：保留最终积分，之后的对局不再扣分
+    #[serde(default)]
+    withdrawn: HashSet<PlayerId>,
+    // 练习模式下的临时积分板，仅用于在标题中标注
+    #[serde(skip)]
+    practice: bool,
+    // 会话开始时间（Unix 时间戳），从文件加载时保留原值
+    #[serde(default = "current_timestamp")]
+    started_at: u64,
+    #[serde(default)]
+    streak_bonus: Option<StreakBonus>,
+    #[serde(default)]
+    scoring: ScoringRule,
+    // 严格模式：把近似重名、积分被下限截断、积分校验警告视为错误
+    #[serde(default)]
+    strict: bool,
+    // 显示和导出积分时最多保留的小数位数（整数不显示多余的 0）
+    #[serde(default = "default_score_precision")]
+    score_precision: usize,
+    #[serde(default)]
+    rounding_mode: RoundingMode,
+    #[serde(default)]
+    show_positive_sign: PositiveSign,
+    // 开启后，第一局记录完成即锁定玩家名单，不再允许添加玩家
+    #[serde(default)]
+    lock_roster_after_first_round: bool,
+    // 手动解锁名单后，即使开启了锁定也允许添加玩家
+    #[serde(default)]
+    roster_unlocked: bool,
+    // 最近一次冻结的积分快照
+    #[serde(default)]
+    frozen: Option<FrozenSnapshot>,
+    #[serde(default)]
+    table_style: TableStyle,
+    // 抢先达到目标积分（first to N）的玩家获胜
+    #[serde(default)]
+    target_score: Option<i32>,
+    // 有玩家达到目标积分后不再接受新的对局
+    #[serde(default)]
+    stop_at_target: bool,
+    // 逐轮淘汰：每局结束后排名最后的玩家退出，直到只剩一人
+    #[serde(default)]
+    elimination: bool,
+    // 已清空的对局记录的汇总
+    #[serde(default)]
+    cleared: ClearedHistory,
+    // 积分榜中是否额外显示每位玩家相对平均积分的差值
+    #[serde(default)]
+    show_relative_to_mean: bool,
+    #[serde(default)]
+    withdrawn_winner: WithdrawnWinnerPolicy,
+    // 每位玩家在会话中达到过的（最低分, 最高分），清空对局记录后仍保留
+    #[serde(default)]
+    score_extremes: HashMap<PlayerId, (i32, i32)>,
+    // 比赛标题（如 "周五联赛"），显示在积分榜标题中，并写入报告和导出文件
+    #[serde(default)]
+    title: Option<String>,
+    // 未启用副积分时为 None，积分榜和导出与只有主积分时完全相同
+    #[serde(default)]
+    secondary: Option<SecondaryScore>,
+    #[serde(default)]
+    secondary_scores: HashMap<PlayerId, i32>,
+    // 上次显示积分榜时各玩家的名次，用于显示名次变化，不保存到文件
+    #[serde(skip)]
+    last_displayed_ranks: RefCell<HashMap<PlayerId, usize>>,
+    // 操作过程中产生、尚未显示给用户的提示，不保存到文件
+    #[serde(skip)]
+    pending_notices: Vec<String>,
+    // 最近一局中刚达到目标积分、尚未宣布的玩家，不保存到文件
+    #[serde(skip)]
+    pending_target_winners: Vec<PlayerId>,
+    // 同一玩家在该秒数内再次被记为胜者时需要确认，防止误按两次；由 --repeat-window 设置，不保存到文件
+    #[serde(skip)]
+    repeat_window: Option<u64>,
+    // 导出文件中是否注明行的排列方式；由 --sort-note 设置，不保存到文件
+    #[serde(skip)]
+    sort_note: bool,
+    // 限时赛的结束时间（Unix 时间戳），由 --end-at 设置，不保存到文件
+    #[serde(skip)]
+    session_deadline: Option<u64>,
+    // 限时赛时间已到，不再接受新的对局
+    #[serde(skip)]
+    session_over: bool,
+    // 限时赛刚结束、尚未宣布最终排名
+    #[serde(skip)]
+    pending_session_end: bool,
+    // 记录胜局等修改操作后是否自动显示积分榜，由 --auto-display / --no-auto-display 和输入是否为终端决定，不保存到文件
+    #[serde(skip, default = "default_auto_display")]
+    auto_display: bool,
+    // 记录胜局前是否显示胜者名称并请求确认，由 --confirm-winner / --no-confirm-winner 和输入是否为终端决定，不保存到文件
+    #[serde(skip)]
+    confirm_winner: bool,
+    // 是否在终端中用颜色区分玩家，由 --color 和 NO_COLOR 决定，不保存到文件
+    #[serde(skip)]
+    use_color: bool,
+}
+
+fn default_score_precision() -> usize {
+    2
+}
+
+fn default_auto_display() -> bool {
+    true
 }
 
 impl Scoreboard {
@@ -13,11 +843,72 @@ impl Scoreboard {
             players: HashMap::new(),
             scores: HashMap::new(),
             next_id: 1,
+            history: Vec::new(),
+            min_score: None,
+            name_policy: NamePolicy::default(),
+            round_timer: None,
+            pins: HashMap::new(),
+            tags: HashMap::new(),
+            tie_break: TieBreak::None,
+            withdrawn: HashSet::new(),
+            practice: false,
+            started_at: current_timestamp(),
+            streak_bonus: None,
+            scoring: ScoringRule::default(),
+            strict: false,
+            score_precision: default_score_precision(),
+            rounding_mode: RoundingMode::default(),
+            show_positive_sign: PositiveSign::default(),
+            lock_roster_after_first_round: false,
+            roster_unlocked: false,
+            frozen: None,
+            table_style: TableStyle::default(),
+            target_score: None,
+            stop_at_target: false,
+            elimination: false,
+            cleared: ClearedHistory::default(),
+            show_relative_to_mean: false,
+            withdrawn_winner: WithdrawnWinnerPolicy::default(),
+            score_extremes: HashMap::new(),
+            title: None,
+            secondary: None,
+            secondary_scores: HashMap::new(),
+            last_displayed_ranks: RefCell::new(HashMap::new()),
+            pending_notices: Vec::new(),
+            pending_target_winners: Vec::new(),
+            repeat_window: None,
+            sort_note: false,
+            session_deadline: None,
+            session_over: false,
+            pending_session_end: false,
+            auto_display: default_auto_display(),
+            confirm_winner: false,
+            use_color: false,
+        }
+    }
+
+    fn add_player(&mut self, name: String) -> Result<PlayerId, String> {
+        if self.roster_locked() {
+            return Err("比赛已开始，玩家名单已锁定，无法添加玩家（可先解锁名单）".to_string());
         }
+
+        self.validate_name(&name, None)?;
+
+        let id = PlayerId(self.next_id);
+        self.players.insert(id, name);
+        self.scores.insert(id, 0);
+        self.next_id += 1;
+        Ok(id)
     }
 
-    fn add_player(&mut self, name: String) -> Result<usize, String> {
-        // 验证玩家名称
+    // 验证玩家名称是否合法且不与其他玩家重名；except 为改名时被排除在查重之外的玩家
+    fn validate_name(&self, name: &str, except: Option<PlayerId>) -> Result<(), String> {
+        let others = self
+            .players
+            .iter()
+            .filter(|(id, _)| Some(**id) != except)
+            .map(|(_, other)| other);
+
         if name.is_empty() {
             return Err("玩家名称不能为空".to_string());
         }
@@ -30,263 +921,9303 @@ impl Scoreboard {
         if name.chars().any(|c| c.is_control() || c == '\t' || c == '\n' || c == '\r') {
             return Err("玩家名称不能包含控制字符".to_string());
         }
-        
-        // 检查是否已存在同名玩家
-        if self.players.values().any(|existing_name| existing_name == &name) {
-            return Err(format!("玩家名称 '{}' 已存在，请使用不同的名称", name));
-        }
-        
-        let id = self.next_id;
-        self.players.insert(id, name);
-        self.scores.insert(id, 0);
-        self.next_id += 1;
-        Ok(id)
-    }
 
-    fn update_scores(&mut self, winner_id: usize) -> Result<(), String> {
-        if !self.players.contains_key(&winner_id) {
-            return Err(format!("玩家序号 {} 不存在", winner_id));
+        if self.name_policy.reject_numeric && name.chars().all(|c| c.is_ascii_digit()) {
+            return Err("玩家名称不能全部为数字，以免与玩家序号混淆".to_string());
         }
-
-        // 计算其他玩家数量
-        let other_players_count = self.players.len() - 1;
         
-        // 胜出玩家获得其他玩家数量的分数（通杀机制）
-        *self.scores.get_mut(&winner_id).unwrap() += other_players_count as i32;
+        // 检查是否已存在同名玩家
+        if let Some(existing) = self.name_policy.find_conflict(name, others.clone()) {
+            if existing == name {
+                return Err(format!("玩家名称 '{}' 已存在，请使用不同的名称", name));
+            }
+            return Err(format!(
+                "玩家名称 '{}' 与已存在的 '{}' 视为重名，请使用不同的名称",
+                name, existing
+            ));
+        }
 
-        // 其他玩家 -1 分
-        for (id, score) in self.scores.iter_mut() {
-            if *id != winner_id {
-                *score -= 1;
+        // 严格模式下，即使未开启忽略重音的查重，也拒绝近似重名
+        if self.strict {
+            let relaxed = NamePolicy {
+                accent_insensitive: true,
+                ..self.name_policy.clone()
+            };
+            if let Some(existing) = relaxed.find_conflict(name, others) {
+                return Err(format!(
+                    "严格模式: 玩家名称 '{}' 与已存在的 '{}' 近似重名",
+                    name, existing
+                ));
             }
         }
 
         Ok(())
     }
 
-    fn display_scoreboard(&self) {
-        println!("\n=== 积分榜 ===");
-        println!("{:<4} {:<15} {:<6}", "序号", "玩家名称", "积分");
-        println!("{}", "-".repeat(30));
-        
-        let mut sorted_players: Vec<_> = self.players.iter().collect();
-        sorted_players.sort_by_key(|(id, _)| *id);
-        
-        for (id, name) in sorted_players {
-            let score = self.scores.get(id).unwrap_or(&0);
-            println!("{:<4} {:<15} {:<6}", id, name, score);
+    // 当前存在的玩家序号（升序）。手动编辑存档等情况下序号可能不连续，如 1, 2, 5
+    fn existing_ids(&self) -> Vec<PlayerId> {
+        let mut ids: Vec<PlayerId> = self.players.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    // 序号到名称的对应关系，按序号排列，供外部工具把存档或导出文件中的序号换算为名称
+    fn id_name_map(&self) -> BTreeMap<PlayerId, String> {
+        self.players.iter().map(|(id, name)| (*id, name.clone())).collect()
+    }
+
+    // 把玩家序号重新编为连续的 1..=N（保持原有先后顺序），同步更新积分、对局记录、置顶、
+    // 退出名单、冻结快照等所有引用序号的数据，返回 旧序号 -> 新序号 的对应关系。
+    // 对局记录中可能引用已删除的玩家，这些序号排在现有玩家之后，避免与新序号冲突。
+    fn compact_ids(&mut self) -> HashMap<PlayerId, PlayerId> {
+        let mut mapping: HashMap<PlayerId, PlayerId> = self
+            .existing_ids()
+            .into_iter()
+            .enumerate()
+            .map(|(index, id)| (id, PlayerId(index + 1)))
+            .collect();
+
+        let mut removed: Vec<PlayerId> = self
+            .history
+            .iter()
+            .flat_map(|record| {
+                std::iter::once(record.winner_id)
+                    .chain(record.participants.iter().copied())
+                    .chain(record.scores_after.keys().copied())
+            })
+            .chain(self.scores.keys().copied())
+            .chain(self.pins.keys().copied())
+            .chain(self.tags.keys().copied())
+            .chain(self.secondary_scores.keys().copied())
+            .chain(self.score_extremes.keys().copied())
+            .chain(self.withdrawn.iter().copied())
+            .chain(self.frozen.iter().flat_map(|frozen| frozen.scores.keys().copied()))
+            .chain(self.cleared.wins.keys().copied())
+            .chain(self.cleared.streak.map(|(id, _)| id))
+            .chain(self.cleared.baseline.keys().copied())
+            .filter(|id| !mapping.contains_key(id))
+            .collect();
+        removed.sort();
+        removed.dedup();
+        for id in removed {
+            let new_id = PlayerId(mapping.len() + 1);
+            mapping.insert(id, new_id);
         }
-        println!();
+
+        let remap = |id: &PlayerId| mapping[id];
+        fn remap_keys<V>(map: &mut HashMap<PlayerId, V>, remap: impl Fn(&PlayerId) -> PlayerId) {
+            *map = map.drain().map(|(id, value)| (remap(&id), value)).collect();
+        }
+        remap_keys(&mut self.players, remap);
+        remap_keys(&mut self.scores, remap);
+        remap_keys(&mut self.pins, remap);
+        remap_keys(&mut self.tags, remap);
+        remap_keys(&mut self.secondary_scores, remap);
+        remap_keys(&mut self.score_extremes, remap);
+        self.withdrawn = self.withdrawn.iter().map(remap).collect();
+        for record in &mut self.history {
+            record.winner_id = remap(&record.winner_id);
+            record.participants = record.participants.iter().map(remap).collect();
+            remap_keys(&mut record.scores_after, remap);
+        }
+        if let Some(frozen) = self.frozen.as_mut() {
+            remap_keys(&mut frozen.scores, remap);
+        }
+        remap_keys(&mut self.cleared.wins, remap);
+        remap_keys(&mut self.cleared.baseline, remap);
+        self.cleared.streak = self.cleared.streak.map(|(id, streak)| (remap(&id), streak));
+        remap_keys(&mut self.last_displayed_ranks.borrow_mut(), remap);
+        self.pending_target_winners = self.pending_target_winners.iter().map(remap).collect();
+
+        self.next_id = mapping.len() + 1;
+        mapping
     }
 
-    fn list_players(&self) {
-        println!("\n=== 玩家列表 ===");
-        let mut sorted_players: Vec<_> = self.players.iter().collect();
-        sorted_players.sort_by_key(|(id, _)| *id);
-        
-        for (id, name) in sorted_players {
-            println!("{}: {}", id, name);
+    // 玩家序号不存在时的错误信息，列出实际存在的序号，避免让人误以为中间缺的序号可用
+    fn unknown_player_error(&self, id: PlayerId) -> String {
+        let ids = self.existing_ids();
+        if ids.is_empty() {
+            return format!("玩家序号 {} 不存在，当前没有玩家", id);
         }
-        println!();
+        format!("玩家序号 {} 不存在（现有序号: {}）", id, join_ids(&ids))
     }
-}
 
-fn get_input(prompt: &str) -> Result<String, String> {
-    print!("{}", prompt);
-    if let Err(_) = io::stdout().flush() {
-        return Err("输出缓冲区刷新失败".to_string());
+    // 修改玩家名称，新名称需满足与添加玩家相同的规则
+    fn rename_player(&mut self, id: PlayerId, new_name: String) -> Result<(), String> {
+        if !self.players.contains_key(&id) {
+            return Err(self.unknown_player_error(id));
+        }
+        self.validate_name(&new_name, Some(id))?;
+        self.players.insert(id, new_name);
+        Ok(())
     }
-    
-    let mut input = String::new();
-    match io::stdin().read_line(&mut input) {
-        Ok(0) => Err("输入流已结束 (EOF)".to_string()),
-        Ok(_) => {
-            let trimmed = input.trim().to_string();
-            // 检查输入长度
-            if trimmed.len() > 50 {
-                Err("输入内容过长，请限制在50个字符以内".to_string())
-            } else {
-                Ok(trimmed)
+
+    // 按名称（不区分大小写）查找玩家；仅大小写不同的多名玩家同时匹配时，
+    // 大小写完全一致的那名玩家优先，否则返回列出候选的错误
+    fn find_by_name(&self, name: &str) -> Result<PlayerId, String> {
+        let key = name.to_lowercase();
+        let mut matches: Vec<PlayerId> = self
+            .players
+            .iter()
+            .filter(|(_, other)| other.to_lowercase() == key)
+            .map(|(id, _)| *id)
+            .collect();
+        matches.sort();
+        if matches.len() > 1 {
+            let exact: Vec<PlayerId> = matches.iter().copied().filter(|id| self.players[id] == name).collect();
+            if exact.len() == 1 {
+                matches = exact;
+            }
+        }
+        match matches.as_slice() {
+            [] => Err(format!("找不到名为 '{}' 的玩家", name)),
+            [id] => Ok(*id),
+            _ => {
+                let candidates: Vec<String> = matches
+                    .iter()
+                    .map(|id| format!("{}({})", self.players[id], id))
+                    .collect();
+                Err(format!(
+                    "名称 '{}' 匹配到多名玩家: {}，请输入大小写完全一致的名称",
+                    name,
+                    candidates.join("、")
+                ))
             }
         }
-        Err(_) => Err("读取输入失败，可能是由于输入流错误或中断".to_string()),
     }
-}
 
-fn get_input_safe(prompt: &str) -> String {
-    loop {
-        match get_input(prompt) {
-            Ok(input) => return input,
-            Err(e) => {
-                eprintln!("输入错误: {}", e);
-                eprintln!("请重试或按 Ctrl+C 退出程序。");
-                // 如果是EOF或严重错误，退出程序
-                if e.contains("EOF") || e.contains("输入流") {
-                    eprintln!("程序将退出。");
-                    std::process::exit(1);
+    // 按名称查找玩家并改名，找不到或有多名玩家匹配时返回错误
+    fn rename_by_name(&mut self, old: &str, new_name: String) -> Result<(), String> {
+        let id = self.find_by_name(old)?;
+        self.rename_player(id, new_name)
+    }
+
+    // 按名称查询玩家的当前积分，找不到或无法确定是哪名玩家时返回 None
+    fn score_of_name(&self, name: &str) -> Option<i32> {
+        let id = self.find_by_name(name).ok()?;
+        Some(*self.scores.get(&id).unwrap_or(&0))
+    }
+
+    // 从每行一个名称的文本中批量导入玩家，空行和以 '#' 开头的行会被忽略
+    fn import_players<R: BufRead>(&mut self, reader: R) -> Result<ImportSummary, String> {
+        self.import_players_with_progress(reader, |_| {})
+    }
+
+    // 与 import_players 相同，但每处理完一行会以已处理行数调用 on_line，便于调用方显示进度
+    fn import_players_with_progress<R: BufRead, F: FnMut(usize)>(
+        &mut self,
+        reader: R,
+        mut on_line: F,
+    ) -> Result<ImportSummary, String> {
+        let mut summary = ImportSummary {
+            added: 0,
+            skipped: Vec::new(),
+        };
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.map_err(|e| format!("读取第 {} 行失败: {}", line_no, e))?;
+            let name = line.trim();
+
+            if !name.is_empty() && !name.starts_with('#') {
+                match self.add_player(name.to_string()) {
+                    Ok(_) => summary.added += 1,
+                    Err(e) => summary.skipped.push((line_no, e)),
                 }
             }
+            on_line(line_no);
         }
+
+        Ok(summary)
     }
-}
 
-#[cfg(test)]
+    // 修复 players 与 scores 不一致的数据（通常来自手动编辑的存档），返回所做修复的说明：
+    // 缺少积分的玩家积分记为 0，没有对应玩家的积分被丢弃，next_id 不大于已有序号时调大。
+    fn reconcile(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let Err(e) = self.scoring.validate() {
+            warnings.push(format!("积分规则不合理: {}", e));
+        }
+
+        // 旧存档没有保存最高分和最低分，从对局记录中的积分快照恢复
+        for record in &self.history {
+            fold_extremes(&mut self.score_extremes, &record.scores_after);
+        }
+
+        let mut missing: Vec<PlayerId> = self
+            .players
+            .keys()
+            .filter(|id| !self.scores.contains_key(*id))
+            .copied()
+            .collect();
+        missing.sort();
+        for id in missing {
+            self.scores.insert(id, 0);
+            warnings.push(format!("玩家 '{}'（序号 {}）缺少积分，已记为 0", self.players[&id], id));
+        }
+
+        let mut orphans: Vec<(PlayerId, i32)> = self
+            .scores
+            .iter()
+            .filter(|(id, _)| !self.players.contains_key(*id))
+            .map(|(id, score)| (*id, *score))
+            .collect();
+        orphans.sort();
+        for (id, score) in orphans {
+            self.scores.remove(&id);
+            warnings.push(format!("序号 {} 的积分 {} 没有对应的玩家，已丢弃", id, score));
+        }
+
+        if let Some(max_id) = self.players.keys().max().map(|id| id.get())
+            && self.next_id <= max_id
+        {
+            warnings.push(format!(
+                "下一个玩家序号 {} 不大于已有的最大序号 {}，已调整为 {}",
+                self.next_id,
+                max_id,
+                max_id + 1
+            ));
+            self.next_id = max_id + 1;
+        }
+
+        warnings
+    }
+
+    // 是否已锁定玩家名单：开启锁定、已记录过对局且未手动解锁
+    fn roster_locked(&self) -> bool {
+        self.lock_roster_after_first_round && self.rounds_played() > 0 && !self.roster_unlocked
+    }
+
+    // 手动解锁玩家名单，之后可以继续添加玩家
+    fn unlock_roster(&mut self) {
+        self.roster_unlocked = true;
+    }
+
+    // 严格模式下执行修改操作：若操作产生新的积分校验警告，则撤销操作并返回错误
+    fn guarded<T, F>(&mut self, operation: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut Scoreboard) -> Result<T, String>,
+    {
+        if !self.strict {
+            return operation(self);
+        }
+
+        // 警告内容含有局数和积分，会随操作变化，因此按警告数量判断是否新增
+        let before = self.anomalies().len();
+        let backup = self.clone();
+        let result = operation(self)?;
+        let warnings = self.anomalies();
+        if warnings.len() > before {
+            verbose_log(|| format!("严格模式: 出现新的校验警告，恢复操作前的积分板（{}）", warnings.join("；")));
+            *self = backup;
+            return Err(format!("严格模式: 操作已撤销，{}", warnings.join("；")));
+        }
+        Ok(result)
+    }
+
+    fn update_scores(&mut self, winner_id: PlayerId) -> Result<(), String> {
+        self.update_scores_weighted(winner_id, 1)
+    }
+
+    // 记录一局加权对局（如决赛），本局所有扣分和胜者所得都乘以 weight；连胜奖励不受权重影响
+    fn update_scores_weighted(&mut self, winner_id: PlayerId, weight: u32) -> Result<(), String> {
+        self.guarded(|board| board.update_scores_unchecked(winner_id, weight))
+    }
+
+    fn update_scores_unchecked(&mut self, winner_id: PlayerId, weight: u32) -> Result<(), String> {
+        if self.is_empty() {
+            return Err("当前没有玩家，请先添加玩家".to_string());
+        }
+        if !self.players.contains_key(&winner_id) {
+            return Err(self.unknown_player_error(winner_id));
+        }
+        if weight == 0 {
+            return Err("本局权重必须大于0".to_string());
+        }
+        if weight > MAX_WEIGHT {
+            return Err(format!("本局权重过大，最多为 {}", MAX_WEIGHT));
+        }
+        self.admit_withdrawn_winner(winner_id)?;
+
+        // 所有仍在比赛中的玩家都参与本局，已退出的玩家不再扣分
+        let mut participants: Vec<PlayerId> = self
+            .players
+            .keys()
+            .copied()
+            .filter(|id| *id == winner_id || !self.withdrawn.contains(id))
+            .collect();
+        participants.sort();
+        self.apply_round(winner_id, participants, weight)
+    }
+
+    // 胜者已退出时按设置处理：拒绝记录，或自动恢复该玩家并留下提示
+    fn admit_withdrawn_winner(&mut self, winner_id: PlayerId) -> Result<(), String> {
+        if !self.is_withdrawn(winner_id) {
+            return Ok(());
+        }
+        let name = self.players[&winner_id].clone();
+        match self.withdrawn_winner {
+            WithdrawnWinnerPolicy::Reject => Err(format!("玩家 '{}' 已退出，不能作为胜出玩家（可先恢复该玩家）", name)),
+            WithdrawnWinnerPolicy::Reinstate => {
+                self.withdrawn.remove(&winner_id);
+                self.pending_notices
+                    .push(format!("玩家 '{}' 已退出，记录本局胜利时已自动恢复该玩家", name));
+                Ok(())
+            }
+        }
+    }
+
+    // 取出尚未显示的提示
+    fn take_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_notices)
+    }
+
+    // 记录只在部分玩家之间进行的一局（如两人对战），只有参赛玩家的积分会变化
+    fn update_scores_among(&mut self, winner_id: PlayerId, participants: &[PlayerId]) -> Result<(), String> {
+        self.guarded(|board| board.update_scores_among_unchecked(winner_id, participants))
+    }
+
+    fn update_scores_among_unchecked(
+        &mut self,
+        winner_id: PlayerId,
+        participants: &[PlayerId],
+    ) -> Result<(), String> {
+        if self.is_empty() {
+            return Err("当前没有玩家，请先添加玩家".to_string());
+        }
+        let mut unique: Vec<PlayerId> = participants.to_vec();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != participants.len() {
+            return Err("参赛玩家中有重复的序号".to_string());
+        }
+        if unique.len() < 2 {
+            return Err("至少需要两名参赛玩家".to_string());
+        }
+        for id in &unique {
+            let name = self.players.get(id).ok_or_else(|| self.unknown_player_error(*id))?;
+            if *id != winner_id && self.withdrawn.contains(id) {
+                return Err(format!("玩家 '{}' 已退出", name));
+            }
+        }
+        if !unique.contains(&winner_id) {
+            return Err(format!("胜出玩家 {} 不在参赛玩家中", winner_id));
+        }
+        self.admit_withdrawn_winner(winner_id)?;
+
+        self.apply_round(winner_id, unique, 1)
+    }
+
+    // 计算一局的结算结果而不修改积分：其他参赛玩家按本局开始前的名次 ranks 扣分，
+    // 胜者获得扣分之和及连胜奖励 bonus。任何积分超出 i32 范围时返回错误；
+    // 只在权重为 1 时不会超出的情况下才提示权重过大。
+    fn plan_settlement(
+        &self,
+        winner_id: PlayerId,
+        participants: &[PlayerId],
+        ranks: &HashMap<PlayerId, usize>,
+        weight: i32,
+        bonus: i32,
+    ) -> Result<Settlement, String> {
+        let plan = |weight: i32| -> Option<Settlement> {
+            let mut gained: i32 = 0;
+            let mut losers = Vec::new();
+            for id in participants.iter().filter(|id| **id != winner_id) {
+                let penalty = self.scoring.penalty_for_rank(ranks[id]).checked_mul(weight)?;
+                gained = gained.checked_add(penalty)?;
+                let before = *self.scores.get(id).unwrap_or(&0);
+                losers.push(SettledLoser { id: *id, penalty, before, unclamped: before.checked_sub(penalty)? });
+            }
+            let winner_before = *self.scores.get(&winner_id).unwrap_or(&0);
+            let winner_after = winner_before.checked_add(gained)?.checked_add(bonus)?;
+            Some(Settlement { losers, winner_before, winner_after, gained })
+        };
+        plan(weight).ok_or_else(|| {
+            if weight > 1 && plan(1).is_some() {
+                "本局权重过大，积分超出可记录的范围".to_string()
+            } else {
+                "积分超出可记录的范围".to_string()
+            }
+        })
+    }
+
+    // 写入结算结果：其他参赛玩家扣分（不低于积分下限），胜者获得扣分之和（通杀机制）及连胜奖励
+    fn apply_settlement(&mut self, settlement: &Settlement, winner_id: PlayerId, ranks: &HashMap<PlayerId, usize>) {
+        for loser in &settlement.losers {
+            let after = self.min_score.map_or(loser.unclamped, |floor| loser.unclamped.max(floor));
+            self.scores.insert(loser.id, after);
+            verbose_log(|| {
+                let mut message = format!(
+                    "玩家 '{}'（赛前第 {} 名）扣 {} 分: {} -> {}",
+                    self.players[&loser.id], ranks[&loser.id], loser.penalty, loser.before, after
+                );
+                if after != loser.unclamped {
+                    message.push_str(&format!("（{} 被积分下限截断）", loser.unclamped));
+                }
+                message
+            });
+        }
+        self.scores.insert(winner_id, settlement.winner_after);
+    }
+
+    // 把所有积分恢复为导入时的初始积分（没有导入过则为 0）后按对局记录逐局重新结算，
+    // 用于修复因程序错误或手动编辑而与记录不符的积分。返回与重算前相比积分发生变化的玩家。
+    // 连胜奖励和权重取自记录本身；不在对局记录中的积分变化（如拆分玩家转移的积分）会被清除。
+    fn recompute_scores(&mut self) -> Result<Vec<ScoreDiff>, String> {
+        if self.cleared.rounds > 0 {
+            return Err("对局记录已被清空过，只凭剩余记录无法重算积分".to_string());
+        }
+        for record in &self.history {
+            if record.participants.is_empty() {
+                return Err(format!("第 {} 局没有保存参赛玩家，无法重算积分", record.round));
+            }
+            if i32::try_from(record.weight).is_err() {
+                return Err(format!("第 {} 局的权重过大", record.round));
+            }
+            for id in record.participants.iter().chain(std::iter::once(&record.winner_id)) {
+                if !self.players.contains_key(id) {
+                    return Err(format!("第 {} 局的玩家序号 {} 已不存在", record.round, id));
+                }
+            }
+        }
+
+        let before = self.clone();
+        let records = std::mem::take(&mut self.history);
+        self.scores = self
+            .players
+            .keys()
+            .map(|id| (*id, self.cleared.baseline.get(id).copied().unwrap_or(0)))
+            .collect();
+        // 最高分和最低分也按重算后的积分变化重新统计
+        self.score_extremes.clear();
+        self.track_extremes();
+        for mut record in records {
+            let ranks = self.ranks();
+            let settled = self.plan_settlement(
+                record.winner_id,
+                &record.participants,
+                &ranks,
+                record.weight as i32,
+                record.bonus,
+            );
+            match settled {
+                Ok(settlement) => self.apply_settlement(&settlement, record.winner_id, &ranks),
+                Err(e) => {
+                    let round = record.round;
+                    *self = before;
+                    return Err(format!("第 {} 局: {}", round, e));
+                }
+            }
+            record.scores_after = self.scores.clone();
+            self.history.push(record);
+            self.track_extremes();
+        }
+        Ok(before.diff(self))
+    }
+
+    // 按积分规则结算一局：参赛的其他玩家按名次扣分（乘以本局权重），胜者获得扣分之和及连胜奖励
+    fn apply_round(
+        &mut self,
+        winner_id: PlayerId,
+        participants: Vec<PlayerId>,
+        weight: u32,
+    ) -> Result<(), String> {
+        let weight = i32::try_from(weight).map_err(|_| "本局权重过大".to_string())?;
+        // 只有胜者一人参赛（如其他玩家都已退出）时没有人扣分，这一局没有意义
+        if !participants.iter().any(|id| *id != winner_id) {
+            return Err("本局除胜者外没有其他参赛玩家，无人失利，不能记录".to_string());
+        }
+        if self.session_over {
+            return Err("比赛时间已到，本场已结束，不再记录新的对局".to_string());
+        }
+        let reached_before = self.players_at_target();
+        if self.stop_at_target && !reached_before.is_empty() {
+            return Err(format!(
+                "比赛已结束: {} 已达到目标积分 {}，不再记录新的对局",
+                self.names_of(&reached_before).join(" / "),
+                self.target_score.unwrap_or_default()
+            ));
+        }
+        // 按本局开始前的名次计算扣分
+        let ranks = self.ranks();
+        verbose_log(|| {
+            format!(
+                "第 {} 局: 计分方式 {}，权重 x{}，参赛玩家 {} 人",
+                self.rounds_played() + 1,
+                match self.scoring_config().mode {
+                    ScoringMode::Standard => "标准",
+                    ScoringMode::RankScaled => "按名次扣分",
+                },
+                weight,
+                participants.len()
+            )
+        });
+
+        // 连胜达到奖励节点时额外加分
+        let streak = self.current_streak(winner_id) + 1;
+        let bonus = match self.streak_bonus {
+            Some(rule) if rule.milestone > 0 && streak.is_multiple_of(rule.milestone) => rule.bonus,
+            _ => 0,
+        };
+        // 先算出本局所有积分和副积分的变化，全部有效时才修改积分板
+        let settlement = self.plan_settlement(winner_id, &participants, &ranks, weight, bonus)?;
+
+        // 严格模式下不允许积分被下限截断
+        if self.strict
+            && let Some(floor) = self.min_score
+            && let Some(loser) = settlement.losers.iter().find(|loser| loser.unclamped < floor)
+        {
+            return Err(format!(
+                "严格模式: 玩家 '{}' 的积分将低于下限 {}",
+                self.players[&loser.id], floor
+            ));
+        }
+
+        let secondary_after = match &self.secondary {
+            Some(secondary) if secondary.per_win != 0 => Some(
+                self.secondary_scores
+                    .get(&winner_id)
+                    .unwrap_or(&0)
+                    .checked_add(secondary.per_win)
+                    .ok_or_else(|| format!("{}超出可记录的范围", secondary.label))?,
+            ),
+            _ => None,
+        };
+
+        // 记下本局之前的积分（如刚加入玩家的 0 分或导入的初始积分），之后再记下本局之后的积分
+        self.track_extremes();
+        self.apply_settlement(&settlement, winner_id, &ranks);
+        if let Some(secondary_after) = secondary_after {
+            self.secondary_scores.insert(winner_id, secondary_after);
+        }
+        let Settlement { winner_before, gained, .. } = settlement;
+        verbose_log(|| {
+            let mut message = format!(
+                "胜出玩家 '{}' 获得扣分之和 {} 分: {} -> {}",
+                self.players[&winner_id], gained, winner_before, self.scores[&winner_id]
+            );
+            if bonus != 0 {
+                message.push_str(&format!("（另加连胜 {} 局奖励 {} 分）", streak, self.delta_text(bonus)));
+            }
+            message
+        });
+
+        self.history.push(MatchRecord {
+            round: self.rounds_played() + 1,
+            timestamp: current_timestamp(),
+            winner_id,
+            duration: self.round_timer.as_mut().map(RoundTimer::lap),
+            bonus,
+            participants,
+            scores_after: self.scores.clone(),
+            weight: weight as u32,
+        });
+        self.track_extremes();
+
+        // 本局之后才达到目标积分的玩家（可能同时有多人），留待宣布
+        self.pending_target_winners = self
+            .players_at_target()
+            .into_iter()
+            .filter(|id| !reached_before.contains(id))
+            .collect();
+
+        // 超过结束时间后记录的这一局为最后一局
+        if self.session_deadline.is_some_and(|deadline| current_timestamp() >= deadline) {
+            self.session_over = true;
+            self.pending_session_end = true;
+        }
+        Ok(())
+    }
+
+    // 仍在比赛中的玩家（未退出），按序号排列
+    fn active_ids(&self) -> Vec<PlayerId> {
+        self.existing_ids().into_iter().filter(|id| !self.is_withdrawn(*id)).collect()
+    }
+
+    // 依次把每个胜者序号记录为一局（如补录线下对局），序号之间用空白或逗号分隔。
+    // 每个序号先单独校验，无效或无法记录的条目跳过并说明原因，不影响其余条目。
+    fn record_wins_batch(&mut self, input: &str) -> ImportSummary {
+        let mut summary = ImportSummary { added: 0, skipped: Vec::new() };
+        let entries = input
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '，')
+            .filter(|part| !part.is_empty());
+        for (index, entry) in entries.enumerate() {
+            let result = parse_player_id(entry).and_then(|id| {
+                if !self.players.contains_key(&id) {
+                    Err(self.unknown_player_error(id))
+                } else if self.elimination {
+                    match self.elimination_round(id)? {
+                        Some(_) => Ok(()),
+                        None => Err(self.elimination_over_error()),
+                    }
+                } else {
+                    self.update_scores(id)
+                }
+            });
+            match result {
+                Ok(()) => summary.added += 1,
+                Err(e) => summary.skipped.push((index + 1, format!("'{}': {}", entry, e))),
+            }
+        }
+        summary
+    }
+
+    // 逐轮淘汰只剩一名玩家时的最终胜者
+    fn elimination_survivor(&self) -> Option<PlayerId> {
+        match self.active_ids().as_slice() {
+            [survivor] if self.elimination => Some(*survivor),
+            _ => None,
+        }
+    }
+
+    // 逐轮淘汰的一局：记录胜者后，除胜者外排名最后的玩家被淘汰（标记为退出），返回被淘汰的玩家。
+    // 积分相同时按排名依据（--tie-break）决定名次，仍相同时序号大的玩家被淘汰。
+    // 已只剩一名玩家（最终胜者）时不再记录对局，返回 None。
+    fn elimination_round(&mut self, winner_id: PlayerId) -> Result<Option<PlayerId>, String> {
+        if self.elimination_survivor().is_some() {
+            return Ok(None);
+        }
+        self.update_scores(winner_id)?;
+        let eliminated = self
+            .ranked()
+            .into_iter()
+            .rev()
+            .map(|(id, _, _)| id)
+            .find(|id| *id != winner_id && !self.is_withdrawn(*id));
+        if let Some(id) = eliminated {
+            self.withdraw_player(id)?;
+        }
+        Ok(eliminated)
+    }
+
+    // 逐轮淘汰已结束时的提示
+    fn elimination_over_error(&self) -> String {
+        let survivor = self.elimination_survivor().map_or("", |id| self.players[&id].as_str());
+        format!("逐轮淘汰已结束，最终胜者: {}", survivor)
+    }
+
+    // 积分达到或超过目标积分的玩家，按排名排列；未设置目标积分时为空
+    fn players_at_target(&self) -> Vec<PlayerId> {
+        let Some(target) = self.target_score else {
+            return Vec::new();
+        };
+        self.ranked()
+            .into_iter()
+            .filter(|(_, _, score)| *score >= target)
+            .map(|(id, _, _)| id)
+            .collect()
+    }
+
+    fn names_of(&self, ids: &[PlayerId]) -> Vec<String> {
+        ids.iter()
+            .map(|id| self.players.get(id).cloned().unwrap_or_else(|| format!("#{}", id)))
+            .collect()
+    }
+
+    // 取出最近一局刚达到目标积分的玩家的获胜公告，每次达到只宣布一次
+    fn take_target_announcement(&mut self) -> Option<String> {
+        if self.pending_target_winners.is_empty() {
+            return None;
+        }
+        let winners = std::mem::take(&mut self.pending_target_winners);
+        let mut message = format!(
+            "恭喜！{} 率先达到目标积分 {}，获得胜利！",
+            self.names_of(&winners).join(" / "),
+            self.target_score.unwrap_or_default()
+        );
+        if self.stop_at_target {
+            message.push_str("比赛结束，不再记录新的对局。");
+        }
+        Some(message)
+    }
+
+    // 取出限时赛结束的公告，只在时间到后的第一局之后宣布一次
+    fn take_session_end_announcement(&mut self) -> Option<String> {
+        if !std::mem::take(&mut self.pending_session_end) {
+            return None;
+        }
+        let deadline = self.session_deadline.unwrap_or_default();
+        Some(format!(
+            "比赛时间已到（结束时间 {}），本场比赛结束，共进行 {} 局。最终排名如下:",
+            format_timestamp(deadline),
+            self.rounds_played()
+        ))
+    }
+
+    // 上一局的胜者刚在 repeat_window 秒内记录过，很可能是误按了两次
+    fn is_quick_repeat(&self, winner_id: PlayerId, now: u64) -> bool {
+        let (Some(window), Some(last)) = (self.repeat_window, self.history.last()) else {
+            return false;
+        };
+        last.winner_id == winner_id && now.saturating_sub(last.timestamp) <= window
+    }
+
+    // 最近一局的参赛玩家，尚无对局记录（或记录中未保存参赛玩家）时返回 None
+    fn last_participants(&self) -> Option<Vec<PlayerId>> {
+        self.history
+            .last()
+            .map(|record| record.participants.clone())
+            .filter(|participants| !participants.is_empty())
+    }
+
+    // 玩家截至最近一局的连胜局数，最近一局不是该玩家胜出时为 0
+    fn current_streak(&self, id: PlayerId) -> u32 {
+        let streak = self
+            .history
+            .iter()
+            .rev()
+            .take_while(|record| record.winner_id == id)
+            .count() as u32;
+        // 连胜从清空前延续下来时加上清空前的局数
+        match self.cleared.streak {
+            Some((winner, cleared)) if winner == id && streak as usize == self.history.len() => {
+                streak + cleared
+            }
+            _ => streak,
+        }
+    }
+
+    // 玩家人数（包括已退出的玩家）
+    fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    // 积分板上是否还没有玩家，没有玩家时无法记录对局
+    fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    // 已进行的总局数，包括已清空的对局记录
+    fn rounds_played(&self) -> u32 {
+        self.cleared.rounds + self.history.len() as u32
+    }
+
+    // 清空对局记录，保留当前积分、玩家和统计。返回清空的局数。
+    // 清空后无法再查看或导出之前的对局，积分走势图等也只从清空后开始。
+    fn clear_history(&mut self) -> usize {
+        let cleared_count = self.history.len();
+        if let Some(last) = self.history.last() {
+            self.cleared.streak = Some((last.winner_id, self.current_streak(last.winner_id)));
+        }
+        for record in self.history.drain(..) {
+            self.cleared.rounds += 1;
+            self.cleared.weighted_rounds += record.weight as u64;
+            self.cleared.total_bonus += record.bonus as i64;
+            *self.cleared.wins.entry(record.winner_id).or_insert(0) += 1;
+        }
+        cleared_count
+    }
+
+    // 将一名玩家拆分为两人：以 new_name 创建新玩家，并从原玩家处转移 transfer_score 分
+    fn split_player(
+        &mut self,
+        id: PlayerId,
+        new_name: String,
+        transfer_score: i32,
+    ) -> Result<PlayerId, String> {
+        self.guarded(|board| board.split_player_unchecked(id, new_name, transfer_score))
+    }
+
+    fn split_player_unchecked(
+        &mut self,
+        id: PlayerId,
+        new_name: String,
+        transfer_score: i32,
+    ) -> Result<PlayerId, String> {
+        let source_score = match self.scores.get(&id) {
+            Some(score) if self.players.contains_key(&id) => *score,
+            _ => return Err(self.unknown_player_error(id)),
+        };
+
+        if transfer_score < 0 {
+            return Err("转移的积分不能为负数".to_string());
+        }
+        let remaining = source_score.checked_sub(transfer_score).ok_or_else(|| {
+            format!(
+                "转移 {} 分后原玩家积分超出可记录的范围（当前积分 {}）",
+                transfer_score, source_score
+            )
+        })?;
+
+        if let Some(floor) = self.min_score
+            && remaining < floor
+        {
+            return Err(format!(
+                "转移 {} 分后原玩家积分将低于下限 {}（当前积分 {}）",
+                transfer_score, floor, source_score
+            ));
+        }
+
+        self.track_extremes();
+        let new_id = self.add_player(new_name)?;
+        // 新玩家从 0 分开始，转移的积分不为负数，不会溢出
+        self.scores.insert(id, remaining);
+        self.scores.insert(new_id, transfer_score);
+        self.track_extremes();
+        Ok(new_id)
+    }
+
+    // 开启单局计时，从现在起开始计算第一局的用时
+    fn start_round_timer(&mut self) {
+        self.round_timer = Some(RoundTimer::start());
+    }
+
+    // 已计时各局的平均用时，没有计时记录时返回 None
+    fn average_round_duration(&self) -> Option<Duration> {
+        let durations: Vec<Duration> = self.history.iter().filter_map(|r| r.duration).collect();
+        if durations.is_empty() {
+            return None;
+        }
+        Some(durations.iter().sum::<Duration>() / durations.len() as u32)
+    }
+
+    fn session_stats(&self) -> SessionStats {
+        let elapsed = Duration::from_secs(current_timestamp().saturating_sub(self.started_at));
+        let rounds = self.rounds_played();
+        let minutes = elapsed.as_secs_f64() / 60.0;
+        SessionStats {
+            started_at: self.started_at,
+            elapsed,
+            rounds,
+            rounds_per_minute: (minutes > 0.0).then(|| rounds as f64 / minutes),
+        }
+    }
+
+    // 最关键的一局：最终领先者名次提升最多的一局（提升相同取较早的一局）。
+    // 依据每局结束后的积分快照计算，快照少于两局或领先者名次从未提升时返回 None。
+    fn most_pivotal_round(&self) -> Option<u32> {
+        let (leader, _, _) = *self.ranked().first()?;
+        let rank_in = |snapshot: &HashMap<PlayerId, i32>| {
+            let score = snapshot.get(&leader)?;
+            Some(1 + snapshot.values().filter(|other| *other > score).count())
+        };
+
+        let snapshots: Vec<(u32, usize)> = self
+            .history
+            .iter()
+            .filter_map(|record| rank_in(&record.scores_after).map(|rank| (record.round, rank)))
+            .collect();
+        if snapshots.len() < 2 {
+            return None;
+        }
+
+        let mut best: Option<(u32, usize)> = None;
+        for pair in snapshots.windows(2) {
+            let ((_, before), (round, after)) = (pair[0], pair[1]);
+            let gain = before.saturating_sub(after);
+            if gain > 0 && best.is_none_or(|(_, best_gain)| gain > best_gain) {
+                best = Some((round, gain));
+            }
+        }
+        best.map(|(round, _)| round)
+    }
+
+    // 把当前积分计入每位玩家的最高分和最低分，每次积分变化前后调用
+    fn track_extremes(&mut self) {
+        fold_extremes(&mut self.score_extremes, &self.scores);
+    }
+
+    // 玩家在会话中达到过的（最低分, 最高分），包括当前积分；玩家不存在时返回 None
+    fn score_extremes(&self, id: PlayerId) -> Option<(i32, i32)> {
+        if !self.players.contains_key(&id) {
+            return None;
+        }
+        let score = *self.scores.get(&id).unwrap_or(&0);
+        let (low, high) = self.score_extremes.get(&id).copied().unwrap_or((score, score));
+        Some((low.min(score), high.max(score)))
+    }
+
+    // 最近 window 局中积分增加最多的玩家及其增加的分数，用于"进步最快"奖项。
+    // 按每局结束后的积分快照计算；window 超过对局记录长度时使用全部记录，第一局之前的积分视为 0
+    // （清空过对局记录时第一局之前的积分未知，从清空后第一局结束时算起）。
+    // 增加最多的有多人时取当前排名靠前者；没有人增加积分时返回 None。
+    fn most_improved(&self, window: usize) -> Option<(PlayerId, &str, i32)> {
+        let mut snapshots: Vec<&HashMap<PlayerId, i32>> = self
+            .history
+            .iter()
+            .map(|record| &record.scores_after)
+            .filter(|snapshot| !snapshot.is_empty())
+            .collect();
+        let initial = HashMap::new();
+        if self.cleared.rounds == 0 {
+            snapshots.insert(0, &initial);
+        }
+        let end = *snapshots.last()?;
+        let start = snapshots[snapshots.len().saturating_sub(window + 1)];
+
+        let mut best: Option<(PlayerId, &str, i32)> = None;
+        for (id, name, _) in self.ranked() {
+            let Some(score) = end.get(&id) else {
+                continue;
+            };
+            let gain = score - start.get(&id).unwrap_or(&0);
+            if gain > 0 && best.is_none_or(|(_, _, best_gain)| gain > best_gain) {
+                best = Some((id, name, gain));
+            }
+        }
+        best
+    }
+
+    // 修改积分规则，不合理的规则会被拒绝
+    fn set_scoring(&mut self, rule: ScoringRule) -> Result<(), String> {
+        rule.validate()?;
+        self.scoring = rule;
+        Ok(())
+    }
+
+    // 当前生效的计分配置
+    fn scoring_config(&self) -> ScoringConfig {
+        ScoringConfig {
+            mode: if self.scoring.rank_penalties.is_empty() {
+                ScoringMode::Standard
+            } else {
+                ScoringMode::RankScaled
+            },
+            loser_delta: self.scoring.loser_delta,
+            rank_penalties: self.scoring.rank_penalties.clone(),
+            min_score: self.min_score,
+            streak_bonus: self.streak_bonus,
+            tie_break: self.tie_break,
+            score_precision: self.score_precision,
+            rounding_mode: self.rounding_mode,
+        }
+    }
+
+    // 以中文逐行描述积分板当前的各项模式设置，供帮助展示；新增模式时在这里补充一行
+    fn mode_summary(&self) -> Vec<String> {
+        let on_off = |enabled: bool| if enabled { "开启" } else { "关闭" };
+        let mut lines = vec![
+            format!(
+                "严格模式: {}",
+                if self.strict { "开启（近似重名、积分被下限截断、积分校验警告均视为错误）" } else { "关闭" }
+            ),
+            format!("禁止纯数字名称: {}", on_off(self.name_policy.reject_numeric)),
+            format!("重名检查忽略重音和大小写: {}", on_off(self.name_policy.accent_insensitive)),
+            format!(
+                "单局计时: {}",
+                match &self.round_timer {
+                    Some(timer) if timer.is_paused() => "已暂停",
+                    Some(_) => "计时中",
+                    None => "关闭",
+                }
+            ),
+        ];
+        lines.push(match self.target_score {
+            Some(target) if self.stop_at_target => format!("目标积分: {}（有玩家达到后不再记录对局）", target),
+            Some(target) => format!("目标积分: {}（达到后继续比赛）", target),
+            None => "目标积分: 无".to_string(),
+        });
+        lines.push(match (self.elimination, self.elimination_survivor()) {
+            (false, _) => "逐轮淘汰: 关闭".to_string(),
+            (true, Some(survivor)) => format!("逐轮淘汰: 已结束（最终胜者 {}）", self.players[&survivor]),
+            (true, None) => format!("逐轮淘汰: 开启（剩余 {} 人）", self.active_ids().len()),
+        });
+        lines.push(match self.session_deadline {
+            Some(_) if self.session_over => "限时赛: 已结束".to_string(),
+            Some(deadline) => format!("限时赛: 结束时间 {}", format_timestamp(deadline)),
+            None => "限时赛: 关闭".to_string(),
+        });
+        lines.push(format!("记录前确认胜者: {}", on_off(self.confirm_winner)));
+        lines
+    }
+
+    fn generate_report(&self) -> String {
+        let stats = self.session_stats();
+        let mut report = String::from("=== 比赛报告 ===\n");
+        if let Some(title) = &self.title {
+            report.push_str(&format!("比赛: {}\n", title));
+        }
+        report.push_str(&format!("玩家人数: {}\n", self.player_count()));
+        report.push_str(&format!("总局数: {}\n", stats.rounds));
+        report.push_str(&format!("开始时间: {}\n", format_timestamp(stats.started_at)));
+        report.push_str(&format!("总时长: {}\n", format_duration(stats.elapsed)));
+        if let Some(rate) = stats.rounds_per_minute {
+            report.push_str(&format!("每分钟局数: {:.2}\n", rate));
+        }
+        if let Some(average) = self.average_round_duration() {
+            report.push_str(&format!("平均每局用时: {}\n", format_duration(average)));
+        }
+        if let Some(round) = self.most_pivotal_round() {
+            report.push_str(&format!("关键一局: 第 {} 局（最终领先者名次提升最多）\n", round));
+        }
+        if let Some((_, name, gain)) = self.most_improved(MOST_IMPROVED_WINDOW) {
+            report.push_str(&format!(
+                "进步最快: {}（最近 {} 局 {} 分）\n",
+                name,
+                MOST_IMPROVED_WINDOW.min(self.history.len()),
+                self.delta_text(gain)
+            ));
+        }
+        if self.rounds_played() > 0 {
+            match self.sort_note(PlayerSort::Score) {
+                Some(note) => report.push_str(&format!("积分起伏（{}）:\n", note)),
+                None => report.push_str("积分起伏:\n"),
+            }
+            for (id, name, score) in self.ranked() {
+                if let Some((low, high)) = self.score_extremes(id) {
+                    report.push_str(&format!(
+                        "  {}: 当前 {}，最高 {}，最低 {}\n",
+                        name,
+                        self.score_text(score),
+                        self.score_text(high),
+                        self.score_text(low)
+                    ));
+                }
+            }
+        }
+        if let Some(target) = self.target_score {
+            let winners = self.players_at_target();
+            if winners.is_empty() {
+                report.push_str(&format!("目标积分: {}（尚无玩家达到）\n", target));
+            } else {
+                report.push_str(&format!(
+                    "目标积分: {}（{} 已达到）\n",
+                    target,
+                    self.names_of(&winners).join(" / ")
+                ));
+            }
+        }
+        report.push_str("积分规则:\n");
+        for line in self.scoring_config().describe() {
+            report.push_str(&format!("  {}\n", line));
+        }
+        report
+    }
+
+    // 标记玩家退出比赛，其积分保持不变，之后的对局不再扣分
+    fn withdraw_player(&mut self, id: PlayerId) -> Result<(), String> {
+        let name = self.players.get(&id).ok_or_else(|| self.unknown_player_error(id))?;
+        if !self.withdrawn.insert(id) {
+            return Err(format!("玩家 '{}' 已经退出", name));
+        }
+        Ok(())
+    }
+
+    fn reinstate_player(&mut self, id: PlayerId) -> Result<(), String> {
+        let name = self.players.get(&id).ok_or_else(|| self.unknown_player_error(id))?;
+        if !self.withdrawn.remove(&id) {
+            return Err(format!("玩家 '{}' 未退出", name));
+        }
+        Ok(())
+    }
+
+    fn is_withdrawn(&self, id: PlayerId) -> bool {
+        self.withdrawn.contains(&id)
+    }
+
+    // 设置比赛标题，规则与玩家名称类似（不能过长、不能包含控制字符）；空字符串表示清除标题
+    fn set_title(&mut self, title: &str) -> Result<(), String> {
+        let title = title.trim();
+        if title.is_empty() {
+            self.title = None;
+            return Ok(());
+        }
+        if title.chars().count() > 40 {
+            return Err("比赛标题过长，请限制在40个字符以内".to_string());
+        }
+        if title.chars().any(char::is_control) {
+            return Err("比赛标题不能包含控制字符".to_string());
+        }
+        self.title = Some(title.to_string());
+        Ok(())
+    }
+
+    // 未启用副积分时返回 None；启用后没有记录的玩家为 0
+    fn secondary_score(&self, id: PlayerId) -> Option<i32> {
+        self.secondary
+            .as_ref()
+            .map(|_| *self.secondary_scores.get(&id).unwrap_or(&0))
+    }
+
+    // 单独调整玩家的副积分，不影响主积分和对局记录，返回调整后的副积分
+    fn adjust_secondary(&mut self, id: PlayerId, delta: i32) -> Result<i32, String> {
+        let current = self.checked_secondary_score(id)?;
+        let updated = current
+            .checked_add(delta)
+            .ok_or_else(|| "副积分超出范围".to_string())?;
+        self.secondary_scores.insert(id, updated);
+        Ok(updated)
+    }
+
+    fn checked_secondary_score(&self, id: PlayerId) -> Result<i32, String> {
+        if !self.players.contains_key(&id) {
+            return Err(self.unknown_player_error(id));
+        }
+        self.secondary_score(id)
+            .ok_or_else(|| "未启用副积分（使用 --secondary 启用）".to_string())
+    }
+
+    // 设置玩家的标记；空字符串表示清除标记。标记最多占 MAX_TAG_WIDTH 个显示宽度（一个 emoji 占两个），
+    // 不能包含空白以外的控制字符
+    fn set_tag(&mut self, id: PlayerId, tag: &str) -> Result<(), String> {
+        if !self.players.contains_key(&id) {
+            return Err(self.unknown_player_error(id));
+        }
+        let tag = tag.trim();
+        if tag.is_empty() {
+            self.tags.remove(&id);
+            return Ok(());
+        }
+        if tag.chars().any(char::is_control) {
+            return Err("标记不能包含控制字符".to_string());
+        }
+        if tag.width() > MAX_TAG_WIDTH || tag.chars().count() > MAX_TAG_CHARS {
+            return Err(format!(
+                "标记过长: '{}'（最多占 {} 个显示宽度，如两个 emoji 或四个字母）",
+                tag, MAX_TAG_WIDTH
+            ));
+        }
+        self.tags.insert(id, tag.to_string());
+        Ok(())
+    }
+
+    fn tag(&self, id: PlayerId) -> Option<&str> {
+        self.tags.get(&id).map(String::as_str)
+    }
+
+    // 显示用的名称，有标记时写在名称前，如 "👑 张三"
+    fn tagged_name(&self, id: PlayerId, name: &str) -> String {
+        match self.tag(id) {
+            Some(tag) => format!("{} {}", tag, name),
+            None => name.to_string(),
+        }
+    }
+
+    // 设置玩家的显示优先级，优先级越高越靠前；不影响积分和名次
+    fn pin_player(&mut self, id: PlayerId, priority: i32) -> Result<(), String> {
+        if !self.players.contains_key(&id) {
+            return Err(self.unknown_player_error(id));
+        }
+        if priority == 0 {
+            self.pins.remove(&id);
+        } else {
+            self.pins.insert(id, priority);
+        }
+        Ok(())
+    }
+
+    fn unpin_player(&mut self, id: PlayerId) -> Result<(), String> {
+        if !self.players.contains_key(&id) {
+            return Err(self.unknown_player_error(id));
+        }
+        self.pins.remove(&id);
+        Ok(())
+    }
+
+    // 玩家胜出的局数
+    fn wins(&self, id: PlayerId) -> u32 {
+        let cleared = self.cleared.wins.get(&id).copied().unwrap_or(0);
+        cleared + self.history.iter().filter(|record| record.winner_id == id).count() as u32
+    }
+
+    // 玩家的完整战绩（名称、积分、名次、胜负局数、连胜），序号不存在时返回 None
+    fn player_record(&self, id: PlayerId) -> Option<PlayerRecord> {
+        let name = self.players.get(&id)?.clone();
+        let losses = self
+            .history
+            .iter()
+            .filter(|record| record.winner_id != id && record.participants.contains(&id))
+            .count() as u32;
+        let current_streak = self.current_streak(id);
+        // 当前历史中最长的连胜；连胜从清空前延续下来时由 current_streak 计入
+        let mut best_streak = current_streak;
+        let mut run = 0;
+        for record in &self.history {
+            run = if record.winner_id == id { run + 1 } else { 0 };
+            best_streak = best_streak.max(run);
+        }
+        Some(PlayerRecord {
+            name,
+            score: *self.scores.get(&id).unwrap_or(&0),
+            rank: self.ranks()[&id],
+            wins: self.wins(id),
+            losses,
+            current_streak,
+            best_streak,
+            withdrawn: self.is_withdrawn(id),
+        })
+    }
+
+    // 按积分和排名依据比较两名玩家，排名更靠前的玩家排在前面；不比较序号
+    fn compare_standing(&self, a: (PlayerId, i32), b: (PlayerId, i32)) -> std::cmp::Ordering {
+        let by_score = b.1.cmp(&a.1);
+        match self.tie_break {
+            TieBreak::None => by_score,
+            TieBreak::Wins => by_score.then(self.wins(b.0).cmp(&self.wins(a.0))),
+        }
+    }
+
+    // 按积分从高到低排列的玩家，返回 (序号, 名称, 积分)。
+    // 积分和排名依据都相同时按序号排列，保证每次显示的顺序一致。
+    fn ranked(&self) -> Vec<(PlayerId, &str, i32)> {
+        let mut ranked: Vec<_> = self
+            .players
+            .iter()
+            .map(|(id, name)| (*id, name.as_str(), *self.scores.get(id).unwrap_or(&0)))
+            .collect();
+        ranked.sort_by(|a, b| self.compare_standing((a.0, a.2), (b.0, b.2)).then(a.0.cmp(&b.0)));
+        ranked
+    }
+
+    // 每位玩家的名次，积分和排名依据都相同的玩家名次并列（如 1, 1, 3）。
+    // 每次调用都按当前积分重新计算，不做缓存；显示、导出和查询名次时都应使用它（或 ranked()），
+    // 这样任何修改积分的操作之后名次都立即正确。last_displayed_ranks 只记录上次显示的名次，用于标出名次变化。
+    fn ranks(&self) -> HashMap<PlayerId, usize> {
+        let ranked = self.ranked();
+        let mut ranks = HashMap::new();
+        for (index, (id, _, score)) in ranked.iter().enumerate() {
+            let rank = match index {
+                0 => 1,
+                _ if self
+                    .compare_standing((ranked[index - 1].0, ranked[index - 1].2), (*id, *score))
+                    .is_eq() =>
+                {
+                    ranks[&ranked[index - 1].0]
+                }
+                _ => index + 1,
+            };
+            ranks.insert(*id, rank);
+        }
+        ranks
+    }
+
+    // 当前排名第一的玩家（同分并列时全部返回），没有玩家时为空
+    fn leaders(&self) -> Vec<(PlayerId, &str, i32)> {
+        let ranks = self.ranks();
+        self.ranked()
+            .into_iter()
+            .filter(|(id, _, _)| ranks[id] == 1)
+            .collect()
+    }
+
+    // 供直播软件读取的单行领先者信息，如 "张三 3分"
+    fn overlay_text(&self) -> String {
+        let leaders = self.leaders();
+        match leaders.first() {
+            Some((_, _, score)) => {
+                let names: Vec<&str> = leaders.iter().map(|(_, name, _)| *name).collect();
+                format!("{} {}分\n", names.join(" / "), self.score_text(*score))
+            }
+            None => "暂无玩家\n".to_string(),
+        }
+    }
+
+    // 玩家落后当前领先者的分数，领先者（包括并列领先者）为 0；玩家不存在时返回 None
+    fn points_behind_leader(&self, id: PlayerId) -> Option<i32> {
+        let score = *self.scores.get(&id).filter(|_| self.players.contains_key(&id))?;
+        let (_, _, top) = *self.leaders().first()?;
+        Some(top - score)
+    }
+
+    // 排名相邻的每对玩家之间的分差：(名次较高者, 名次较低者, 分差)，同分并列时分差为 0
+    fn rank_gaps(&self) -> Vec<(PlayerId, PlayerId, i32)> {
+        self.ranked()
+            .windows(2)
+            .map(|pair| (pair[0].0, pair[1].0, pair[0].2 - pair[1].2))
+            .collect()
+    }
+
+    // 积分分布：键为区间下限（区间为 [下限, 下限 + bucket_size)），值为该区间内的玩家人数。
+    // 负积分按向下取整分入区间（如区间大小 5 时 -1 属于 [-5, 0)）；最低与最高区间之间没有玩家的区间也列出，人数为 0，
+    // 但区间总数会超过 MAX_HISTOGRAM_BUCKETS 时只列出有玩家的区间。bucket_size 小于 1 时按 1 计算；没有玩家时为空。
+    fn score_histogram(&self, bucket_size: i32) -> BTreeMap<i32, usize> {
+        let size = bucket_size.max(1);
+        let mut histogram = BTreeMap::new();
+        for score in self.players.keys().map(|id| *self.scores.get(id).unwrap_or(&0)) {
+            // 接近 i32::MIN 的积分所在区间的下限可能超出范围，此时取 i32::MIN
+            let low = score.checked_sub(score.rem_euclid(size)).unwrap_or(i32::MIN);
+            *histogram.entry(low).or_insert(0) += 1;
+        }
+        if let (Some(&low), Some(&high)) = (histogram.keys().next(), histogram.keys().next_back())
+            && (i64::from(high) - i64::from(low)) / i64::from(size) < MAX_HISTOGRAM_BUCKETS as i64
+        {
+            let mut bucket = low;
+            while let Some(next) = bucket.checked_add(size).filter(|next| *next < high) {
+                bucket = next;
+                histogram.entry(bucket).or_insert(0);
+            }
+        }
+        histogram
+    }
+
+    fn display_score_histogram(&self, bucket_size: i32) {
+        // 人数很多时按比例缩短，最长的条形不超过该宽度
+        const MAX_BAR: usize = 40;
+        println!("\n=== 积分分布（每 {} 分一组）===", bucket_size);
+        let histogram = self.score_histogram(bucket_size);
+        if histogram.is_empty() {
+            println!("暂无玩家");
+            println!();
+            return;
+        }
+        let labels: Vec<String> = histogram
+            .keys()
+            .map(|low| match low.saturating_add(bucket_size.max(1) - 1) {
+                high if high == *low => self.amount_text(*low),
+                high => format!("{} ~ {}", self.amount_text(*low), self.amount_text(high)),
+            })
+            .collect();
+        let width = labels.iter().map(|label| label.width()).max().unwrap_or(0);
+        let most = histogram.values().copied().max().unwrap_or(0).max(1);
+        for (label, count) in labels.iter().zip(histogram.values()) {
+            let bar = if most > MAX_BAR { (count * MAX_BAR).div_ceil(most) } else { *count };
+            let bar = format!("{}{}", "#".repeat(bar), if bar > 0 { " " } else { "" });
+            println!("{} | {}{}", pad_display(label, width), bar, count);
+        }
+        println!();
+    }
+
+    fn display_rank_gaps(&self) {
+        println!("\n=== 相邻名次的分差 ===");
+        let gaps = self.rank_gaps();
+        if gaps.is_empty() {
+            println!("至少需要两名玩家");
+            println!();
+            return;
+        }
+        let ranks = self.ranks();
+        for (higher, lower, gap) in gaps {
+            let names = self.names_of(&[higher, lower]);
+            if gap == 0 {
+                println!("第{}名 {} 与 {} 同分", ranks[&higher], names[0], names[1]);
+            } else {
+                println!(
+                    "第{}名 {} 领先第{}名 {} {} 分",
+                    ranks[&higher],
+                    names[0],
+                    ranks[&lower],
+                    names[1],
+                    self.amount_text(gap)
+                );
+            }
+        }
+        println!();
+    }
+
+    fn display_gaps(&self) {
+        println!("\n=== 与第一名的差距 ===");
+        let Some((_, _, top)) = self.leaders().first().copied() else {
+            println!("暂无玩家");
+            println!();
+            return;
+        };
+        let leaders: Vec<&str> = self.leaders().iter().map(|(_, name, _)| *name).collect();
+        println!("第一名: {}（{} 分）", leaders.join(" / "), self.score_text(top));
+        for (id, name, _) in self.ranked() {
+            match self.points_behind_leader(id) {
+                Some(gap) if gap > 0 => println!("  {}: 落后 {} 分", name, self.amount_text(gap)),
+                _ => {}
+            }
+        }
+        println!();
+    }
+
+    // 按序号分配的固定颜色，每次运行都相同
+    fn player_color(&self, id: PlayerId) -> Color {
+        PLAYER_COLORS[id.get().saturating_sub(1) % PLAYER_COLORS.len()]
+    }
+
+    // 开启颜色时给玩家名称上色
+    fn colored_name(&self, id: PlayerId, name: &str) -> String {
+        if self.use_color {
+            self.player_color(id).paint(name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    // 按积分精度设置格式化积分，用于所有显示和导出
+    fn score_text(&self, score: i32) -> String {
+        self.show_positive_sign.apply(self.amount_text(score))
+    }
+
+    // 分差等非积分数值的显示文本：与积分相同的舍入规则，但不加正号
+    fn amount_text(&self, amount: i32) -> String {
+        format_score_rounded(amount as f64, self.score_precision, self.rounding_mode)
+    }
+
+    // 积分变化（如 +3、-2）的显示文本：与积分相同的舍入规则，非零时总是带符号，不受 --positive-sign 影响
+    fn delta_text(&self, delta: i32) -> String {
+        format_delta(delta, self.score_precision, self.rounding_mode)
+    }
+
+    // 随机打乱未退出玩家的顺序（用于决定出场顺序，不影响积分）。
+    // 先按序号排列再用 Fisher-Yates 洗牌，因此同一种子和同一名单总是得到相同结果。
+    fn shuffle_order(&self, seed: Option<u64>) -> Vec<(PlayerId, &str)> {
+        let mut order: Vec<(PlayerId, &str)> = self
+            .players
+            .iter()
+            .filter(|(id, _)| !self.is_withdrawn(**id))
+            .map(|(id, name)| (*id, name.as_str()))
+            .collect();
+        order.sort_by_key(|(id, _)| *id);
+
+        let mut rng = SeededRng::new(seed.unwrap_or_else(random_seed));
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.below(i + 1));
+        }
+        order
+    }
+
+    // 用给定种子随机模拟 rounds 局，每局从未退出的玩家中随机选出胜者，按当前的积分规则结算。
+    // 用于快速生成演示数据；同一种子和同一名单总是得到相同结果。某局无法记录（如已达到目标积分）时停止并返回错误。
+    fn simulate(&mut self, rounds: usize, seed: u64) -> Result<(), String> {
+        let candidates: Vec<PlayerId> = self
+            .existing_ids()
+            .into_iter()
+            .filter(|id| !self.is_withdrawn(*id))
+            .collect();
+        if candidates.len() < 2 {
+            return Err("至少需要两名未退出的玩家才能模拟对局".to_string());
+        }
+        let mut rng = SeededRng::new(seed);
+        for round in 1..=rounds {
+            let winner = candidates[rng.below(candidates.len())];
+            self.update_scores(winner)
+                .map_err(|e| format!("模拟到第 {} 局时停止: {}", round, e))?;
+        }
+        Ok(())
+    }
+
+    // 积分榜的显示顺序：先按手动优先级，再按积分排名
+    fn display_order(&self) -> Vec<(PlayerId, &str, i32)> {
+        let mut order = self.ranked();
+        order.sort_by_key(|(id, _, _)| std::cmp::Reverse(self.pins.get(id).copied().unwrap_or(0)));
+        order
+    }
+
+    // 检查积分是否与已进行的局数相符，返回可疑情况的警告。
+    // 正常计分下每局每名玩家最多被扣除单局最大扣分，胜者最多得到 (人数-1) 倍的最大扣分，
+    // 且每局积分总和不变。导入的积分板以导入时的积分为起点计算变化。
+    fn anomalies(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let rounds = self.rounds_played() as i64;
+        // 加权对局按权重计为多局
+        let weighted_rounds: i64 = self.cleared.weighted_rounds as i64
+            + self.history.iter().map(|r| r.weight as i64).sum::<i64>();
+        let max_penalty = self.scoring.max_penalty().max(0) as i64;
+        // 连胜奖励不是零和的，需要计入上限和总和
+        let total_bonus: i64 =
+            self.cleared.total_bonus + self.history.iter().map(|r| r.bonus as i64).sum::<i64>();
+        let max_gain = weighted_rounds * max_penalty * self.players.len().saturating_sub(1) as i64
+            + total_bonus.max(0);
+        let max_loss = weighted_rounds * max_penalty;
+
+        let mut ids: Vec<_> = self.players.keys().copied().collect();
+        ids.sort();
+        for id in &ids {
+            let baseline = self.cleared.baseline.get(id).copied().unwrap_or(0) as i64;
+            let score = *self.scores.get(id).unwrap_or(&0) as i64;
+            if score > baseline + max_gain {
+                warnings.push(format!(
+                    "玩家 '{}' 的积分 {} 超过 {} 局内可能获得的最高积分 {}",
+                    self.players[id],
+                    score,
+                    rounds,
+                    baseline + max_gain
+                ));
+            } else if score < baseline - max_loss {
+                warnings.push(format!(
+                    "玩家 '{}' 的积分 {} 低于 {} 局内可能的最低积分 {}",
+                    self.players[id],
+                    score,
+                    rounds,
+                    baseline - max_loss
+                ));
+            }
+        }
+
+        // 设置积分下限后扣分可能被截断，此时总和不再等于奖励分之和
+        if self.min_score.is_none() {
+            let total: i64 = self.scores.values().map(|s| *s as i64).sum();
+            let expected = total_bonus
+                + ids
+                    .iter()
+                    .map(|id| self.cleared.baseline.get(id).copied().unwrap_or(0) as i64)
+                    .sum::<i64>();
+            if total != expected {
+                warnings.push(format!(
+                    "所有玩家的积分总和为 {}，正常计分下应为 {}",
+                    total, expected
+                ));
+            }
+        }
+
+        warnings
+    }
+
+    // 便于粘贴到聊天软件的积分榜文本：按排名列出名次、名称和积分，有比赛标题时写在第一行
+    fn standings_text(&self, format: StandingsFormat) -> String {
+        let ranks = self.ranks();
+        let mut text = String::new();
+        match format {
+            StandingsFormat::Plain => {
+                if let Some(title) = &self.title {
+                    text.push_str(&format!("{} 积分榜\n", title));
+                }
+                for (id, name, score) in self.ranked() {
+                    text.push_str(&format!(
+                        "{}. {} {}分\n",
+                        ranks[&id],
+                        self.tagged_name(id, name),
+                        self.score_text(score)
+                    ));
+                }
+            }
+            StandingsFormat::Markdown => {
+                if let Some(title) = &self.title {
+                    text.push_str(&format!("**{} 积分榜**\n\n", title));
+                }
+                if let Some(note) = self.sort_note(PlayerSort::Score) {
+                    text.push_str(&format!("_{}_\n\n", note));
+                }
+                text.push_str("| 排名 | 玩家 | 积分 |\n|---|---|---|\n");
+                for (id, name, score) in self.ranked() {
+                    text.push_str(&format!(
+                        "| {} | {} | {} |\n",
+                        ranks[&id],
+                        self.tagged_name(id, name).replace('|', "\\|"),
+                        self.score_text(score)
+                    ));
+                }
+            }
+        }
+        text
+    }
+
+    // 隐去真实姓名的副本，用于公开分享：按当前排名依次命名为 玩家A、玩家B……（超过 26 人时为 玩家AA、玩家AB……），
+    // 积分、名次和对局记录保持不变，同一积分板每次得到相同的对应关系
+    fn anonymized(&self) -> Scoreboard {
+        let mut board = self.clone();
+        board.players = self
+            .ranked()
+            .into_iter()
+            .enumerate()
+            .map(|(index, (id, _, _))| (id, format!("玩家{}", alphabetic_label(index))))
+            .collect();
+        board
+    }
+
+    // 开启 --sort-note 时写在导出内容中的排序说明
+    fn sort_note(&self, order: PlayerSort) -> Option<String> {
+        self.sort_note.then(|| format!("排序: {}", order.describe()))
+    }
+
+    // 以 id,name,score 格式导出积分榜（按序号排列），启用副积分时增加 secondary 列，有标记时增加 tag 列；
+    // 开启 --sort-note 时第一行为 # 开头的排序说明
+    fn export_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        if let Some(note) = self.sort_note(PlayerSort::Id) {
+            writeln!(writer, "# {}", note)?;
+        }
+        // 有玩家设置了标记时增加 tag 列
+        let with_tags = !self.tags.is_empty();
+        let mut header = String::from("id,name,score");
+        if self.secondary.is_some() {
+            header.push_str(",secondary");
+        }
+        if with_tags {
+            header.push_str(",tag");
+        }
+        writeln!(writer, "{}", header)?;
+        for id in self.existing_ids() {
+            write!(
+                writer,
+                "{},{},{}",
+                id,
+                csv_field(&self.players[&id]),
+                self.score_text(*self.scores.get(&id).unwrap_or(&0))
+            )?;
+            if let Some(secondary) = self.secondary_score(id) {
+                write!(writer, ",{}", secondary)?;
+            }
+            if with_tags {
+                write!(writer, ",{}", csv_field(self.tag(id).unwrap_or("")))?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    // 对阵矩阵：行和列都是按序号排列的现有玩家，第 i 行第 j 列为第 i 位玩家在有第 j 位玩家参赛的对局中获胜的局数。
+    // 未记录参赛玩家的旧对局无法得知对手，不计入；没有交手记录的玩家对应的格子为 0。
+    fn matchup_matrix(&self) -> Vec<Vec<u32>> {
+        let ids = self.existing_ids();
+        let index: HashMap<PlayerId, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let mut matrix = vec![vec![0; ids.len()]; ids.len()];
+        for record in &self.history {
+            let Some(&row) = index.get(&record.winner_id) else {
+                continue;
+            };
+            for id in record.participants.iter().filter(|id| **id != record.winner_id) {
+                if let Some(&column) = index.get(id) {
+                    matrix[row][column] += 1;
+                }
+            }
+        }
+        matrix
+    }
+
+    // 以 CSV 格式导出对阵矩阵，首行为对手名称，每行首列为胜者名称，对角线留空
+    fn export_matchups_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let names = self.names_of(&self.existing_ids());
+        let header: Vec<String> = names.iter().map(|name| csv_field(name)).collect();
+        if let Some(note) = self.sort_note(PlayerSort::Id) {
+            writeln!(writer, "# {}", note)?;
+        }
+        writeln!(writer, "winner\\opponent,{}", header.join(","))?;
+        for (i, row) in self.matchup_matrix().iter().enumerate() {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(j, wins)| if i == j { String::new() } else { wins.to_string() })
+                .collect();
+            writeln!(writer, "{},{}", csv_field(&names[i]), cells.join(","))?;
+        }
+        Ok(())
+    }
+
+    // 以 Markdown 表格导出对阵矩阵，对角线显示为 -
+    fn export_matchups_markdown<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let names: Vec<String> = self
+            .names_of(&self.existing_ids())
+            .iter()
+            .map(|name| name.replace('|', "\\|"))
+            .collect();
+        if let Some(title) = &self.title {
+            writeln!(writer, "## {} 对阵矩阵\n", title)?;
+        }
+        if let Some(note) = self.sort_note(PlayerSort::Id) {
+            writeln!(writer, "_{}_\n", note)?;
+        }
+        writeln!(writer, "| 胜者 \\ 对手 | {} |", names.join(" | "))?;
+        writeln!(writer, "|---|{}", "---|".repeat(names.len()))?;
+        for (i, row) in self.matchup_matrix().iter().enumerate() {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(j, wins)| if i == j { "-".to_string() } else { wins.to_string() })
+                .collect();
+            writeln!(writer, "| {} | {} |", names[i], cells.join(" | "))?;
+        }
+        Ok(())
+    }
+
+    // 以 round,timestamp,winner_id,winner_name,event_type 格式导出对局记录。
+    // 目前所有记录都是胜局（event_type 为 win），胜者已删除时名称留空。
+    fn export_history_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "round,timestamp,winner_id,winner_name,event_type")?;
+        for summary in self.history_view() {
+            writeln!(
+                writer,
+                "{},{},{},{},win",
+                summary.round,
+                format_timestamp_rfc3339(summary.timestamp),
+                summary.winner_id,
+                csv_field(summary.winner_name.as_deref().unwrap_or(""))
+            )?;
+        }
+        Ok(())
+    }
+
+    // 对比本积分板（之前）与另一积分板（之后）的积分变化，只返回有变化的玩家。
+    // 优先按名称匹配玩家；名称找不到时，若对方同一序号的玩家名称也无人匹配，则视为改名。
+    fn diff(&self, other: &Scoreboard) -> Vec<ScoreDiff> {
+        let score_in = |board: &Scoreboard, id: PlayerId| *board.scores.get(&id).unwrap_or(&0);
+        let find_by_name = |board: &Scoreboard, name: &str| {
+            board.players.iter().find(|(_, n)| *n == name).map(|(id, _)| *id)
+        };
+
+        let mut ids: Vec<_> = self.players.keys().copied().collect();
+        ids.sort();
+        let mut matched = HashSet::new();
+        let mut diffs = Vec::new();
+        for id in ids {
+            let name = &self.players[&id];
+            let other_id = find_by_name(other, name).or_else(|| {
+                other
+                    .players
+                    .get(&id)
+                    .filter(|other_name| find_by_name(self, other_name).is_none())
+                    .map(|_| id)
+            });
+            match other_id {
+                Some(other_id) => {
+                    matched.insert(other_id);
+                    let other_name = &other.players[&other_id];
+                    let before = score_in(self, id);
+                    let after = score_in(other, other_id);
+                    if before != after || other_name != name {
+                        diffs.push(ScoreDiff {
+                            name: other_name.clone(),
+                            previous_name: (other_name != name).then(|| name.clone()),
+                            before: Some(before),
+                            after: Some(after),
+                        });
+                    }
+                }
+                None => diffs.push(ScoreDiff {
+                    name: name.clone(),
+                    previous_name: None,
+                    before: Some(score_in(self, id)),
+                    after: None,
+                }),
+            }
+        }
+
+        let mut added: Vec<_> = other.players.keys().filter(|id| !matched.contains(*id)).copied().collect();
+        added.sort();
+        for id in added {
+            diffs.push(ScoreDiff {
+                name: other.players[&id].clone(),
+                previous_name: None,
+                before: None,
+                after: Some(score_in(other, id)),
+            });
+        }
+        diffs
+    }
+
+    // 以 SVG 折线图导出每位玩家的积分走势（横轴为局数，纵轴为积分），
+    // 坐标轴按数据自动缩放。中途加入的玩家从其首次出现的局开始绘制，
+    // 没有积分快照的旧记录会被跳过。
+    fn export_svg<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        const WIDTH: i64 = 640;
+        const HEIGHT: i64 = 360;
+        const MARGIN: i64 = 40;
+        const LEGEND_WIDTH: i64 = 160;
+
+        let mut ids: Vec<_> = self.players.keys().copied().collect();
+        ids.sort();
+        let series: Vec<(PlayerId, Vec<(u32, i32)>)> = ids
+            .iter()
+            .map(|id| {
+                let points = self
+                    .history
+                    .iter()
+                    .filter_map(|record| record.scores_after.get(id).map(|score| (record.round, *score)))
+                    .collect();
+                (*id, points)
+            })
+            .collect();
+
+        let max_round = series
+            .iter()
+            .flat_map(|(_, points)| points.iter().map(|(round, _)| *round))
+            .max()
+            .unwrap_or(0)
+            .max(1) as i64;
+        let scores = series.iter().flat_map(|(_, points)| points.iter().map(|(_, score)| *score as i64));
+        let min_score = scores.clone().min().unwrap_or(0).min(0);
+        let max_score = scores.max().unwrap_or(0).max(0);
+        let span = (max_score - min_score).max(1);
+
+        let plot_width = WIDTH - 2 * MARGIN;
+        let plot_height = HEIGHT - 2 * MARGIN;
+        let x_of = |round: i64| MARGIN + round * plot_width / max_round;
+        let y_of = |score: i64| MARGIN + (max_score - score) * plot_height / span;
+
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = WIDTH + LEGEND_WIDTH,
+            h = HEIGHT
+        )?;
+        writeln!(writer, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+        if let Some(title) = &self.title {
+            writeln!(writer, "<title>{}</title>", xml_escape(title))?;
+            writeln!(
+                writer,
+                r#"<text x="{}" y="24" font-size="16" font-weight="bold">{}</text>"#,
+                MARGIN,
+                xml_escape(title)
+            )?;
+        }
+
+        // 坐标轴与刻度
+        writeln!(
+            writer,
+            r#"<line x1="{x}" y1="{top}" x2="{x}" y2="{bottom}" stroke="black"/>"#,
+            x = MARGIN,
+            top = MARGIN,
+            bottom = HEIGHT - MARGIN
+        )?;
+        writeln!(
+            writer,
+            r##"<line x1="{left}" y1="{y}" x2="{right}" y2="{y}" stroke="#999" stroke-dasharray="4"/>"##,
+            left = MARGIN,
+            right = WIDTH - MARGIN,
+            y = y_of(0)
+        )?;
+        writeln!(
+            writer,
+            r#"<line x1="{left}" y1="{y}" x2="{right}" y2="{y}" stroke="black"/>"#,
+            left = MARGIN,
+            right = WIDTH - MARGIN,
+            y = HEIGHT - MARGIN
+        )?;
+        for (score, anchor) in [(max_score, "end"), (min_score, "end")] {
+            writeln!(
+                writer,
+                r#"<text x="{}" y="{}" font-size="12" text-anchor="{}">{}</text>"#,
+                MARGIN - 4,
+                y_of(score) + 4,
+                anchor,
+                self.score_text(score as i32)
+            )?;
+        }
+        writeln!(
+            writer,
+            r#"<text x="{}" y="{}" font-size="12" text-anchor="middle">第 {} 局</text>"#,
+            WIDTH - MARGIN,
+            HEIGHT - MARGIN + 16,
+            max_round
+        )?;
+
+        // 每位玩家一条折线，图例列在右侧
+        for (index, (id, points)) in series.iter().enumerate() {
+            let color = self.player_color(*id).hex();
+            if !points.is_empty() {
+                let coords: Vec<String> = points
+                    .iter()
+                    .map(|(round, score)| format!("{},{}", x_of(*round as i64), y_of(*score as i64)))
+                    .collect();
+                writeln!(
+                    writer,
+                    r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"/>"#,
+                    color,
+                    coords.join(" ")
+                )?;
+            }
+            let legend_y = MARGIN + index as i64 * 20;
+            writeln!(
+                writer,
+                r#"<rect x="{}" y="{}" width="12" height="12" fill="{}"/>"#,
+                WIDTH,
+                legend_y,
+                color
+            )?;
+            writeln!(
+                writer,
+                r#"<text x="{}" y="{}" font-size="12">{}</text>"#,
+                WIDTH + 18,
+                legend_y + 11,
+                xml_escape(&self.tagged_name(*id, &self.players[id]))
+            )?;
+        }
+
+        writeln!(writer, "</svg>")
+    }
+
+    // 以 PNG 表格图片导出当前积分榜（排名、序号、玩家、积分），标题写在表格上方。
+    // 使用内置的 ASCII 点阵字体，不依赖系统字体；字体中没有的字符（中文、emoji 等）画成方框，
+    // 并按出现顺序去重后返回，由调用方提示用户。
+    #[cfg(feature = "png")]
+    fn export_png<W: Write>(&self, writer: W) -> io::Result<Vec<char>> {
+        let ranks = self.ranks();
+        let mut rows = vec![["Rank".to_string(), "ID".to_string(), "Player".to_string(), "Score".to_string()]];
+        for (id, name, score) in self.ranked() {
+            rows.push([
+                ranks[&id].to_string(),
+                id.to_string(),
+                self.tagged_name(id, name),
+                self.score_text(score),
+            ]);
+        }
+        // 排名、序号和积分右对齐，玩家名称左对齐
+        let right_aligned = [true, true, false, true];
+
+        let mut widths = [0; 4];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        let title_rows = usize::from(self.title.is_some());
+        let table_cells: usize = widths.iter().sum::<usize>() + PNG_COLUMN_GAP * (widths.len() - 1);
+        let title_cells = self.title.as_deref().map_or(0, UnicodeWidthStr::width);
+        let width = 2 * PNG_MARGIN + table_cells.max(title_cells) * PNG_CELL_WIDTH;
+        let height = 2 * PNG_MARGIN + (title_rows + rows.len()) * PNG_ROW_HEIGHT;
+
+        let mut canvas = PngCanvas::new(width, height);
+        let mut missing = Vec::new();
+        let text_offset = (PNG_ROW_HEIGHT - PNG_GLYPH_HEIGHT * PNG_SCALE) / 2;
+        if let Some(title) = &self.title {
+            canvas.draw_text(PNG_MARGIN, PNG_MARGIN + text_offset, title, &mut missing);
+        }
+        let table_top = PNG_MARGIN + title_rows * PNG_ROW_HEIGHT;
+        let table_width = table_cells * PNG_CELL_WIDTH;
+        for (index, row) in rows.iter().enumerate() {
+            let top = table_top + index * PNG_ROW_HEIGHT;
+            if index == 0 {
+                canvas.fill_rect(PNG_MARGIN, top, table_width, PNG_ROW_HEIGHT, PNG_HEADER);
+            } else if index.is_multiple_of(2) {
+                canvas.fill_rect(PNG_MARGIN, top, table_width, PNG_ROW_HEIGHT, PNG_STRIPE);
+            }
+            let mut left = PNG_MARGIN;
+            for ((cell, width), right) in row.iter().zip(widths).zip(right_aligned) {
+                let indent = if right { width - cell.width() } else { 0 };
+                canvas.draw_text(left + indent * PNG_CELL_WIDTH, top + text_offset, cell, &mut missing);
+                left += (width + PNG_COLUMN_GAP) * PNG_CELL_WIDTH;
+            }
+        }
+        // 表头下方和表格底部的分隔线
+        canvas.fill_rect(PNG_MARGIN, table_top + PNG_ROW_HEIGHT - 1, table_width, 1, PNG_INK);
+        canvas.fill_rect(PNG_MARGIN, table_top + rows.len() * PNG_ROW_HEIGHT - 1, table_width, 1, PNG_INK);
+
+        canvas.write_png(writer)?;
+        Ok(missing)
+    }
+
+    // 从 export_csv 导出的 id,name,score 格式重建积分榜，next_id 设为最大序号 + 1。
+    // 玩家名称与录入玩家时一样按 policy 校验
+    fn import_csv<R: BufRead>(reader: R, policy: NamePolicy) -> Result<Scoreboard, String> {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.name_policy = policy;
+        // 表头只能出现在开头的注释行（如 --sort-note 写入的排序说明）之后
+        let mut header_allowed = true;
+        // 表头中 id,name,score 之后的可选列；没有表头时第 4 列为副积分
+        let mut extra_columns: Option<Vec<String>> = None;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.map_err(|e| format!("读取第 {} 行失败: {}", line_no, e))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = parse_csv_line(line).map_err(|e| format!("第 {} 行: {}", line_no, e))?;
+            if std::mem::take(&mut header_allowed) {
+                let columns: Vec<String> = fields.iter().map(|field| field.trim().to_lowercase()).collect();
+                if columns.len() >= 3
+                    && columns[..3] == ["id", "name", "score"]
+                    && columns[3..].iter().all(|column| column == "secondary" || column == "tag")
+                {
+                    extra_columns = Some(columns[3..].to_vec());
+                    continue;
+                }
+            }
+
+            let extras: Vec<&str> = match &extra_columns {
+                Some(columns) => {
+                    if fields.len() != 3 + columns.len() {
+                        return Err(format!(
+                            "第 {} 行: 应为 {} 列（与表头一致），实际为 {} 列",
+                            line_no,
+                            3 + columns.len(),
+                            fields.len()
+                        ));
+                    }
+                    columns.iter().map(String::as_str).collect()
+                }
+                // 没有表头时第 4 列为可选的副积分（由启用副积分的积分板导出）
+                None => match fields.len() {
+                    3 => Vec::new(),
+                    4 => vec!["secondary"],
+                    _ => {
+                        return Err(format!(
+                            "第 {} 行: 应为 3 列 (id,name,score) 或 4 列 (id,name,score,secondary)，实际为 {} 列",
+                            line_no,
+                            fields.len()
+                        ));
+                    }
+                },
+            };
+
+            let id = fields[0]
+                .trim()
+                .parse::<usize>()
+                .map(PlayerId)
+                .map_err(|_| format!("第 {} 行: 无效的玩家序号 '{}'", line_no, fields[0]))?;
+            let score = fields[2]
+                .trim()
+                .parse::<i32>()
+                .map_err(|_| format!("第 {} 行: 无效的积分 '{}'", line_no, fields[2]))?;
+            let name = fields[1].clone();
+
+            if id.get() == 0 {
+                return Err(format!("第 {} 行: 玩家序号必须大于0", line_no));
+            }
+            if id.get() > MAX_PLAYER_ID {
+                return Err(format!("第 {} 行: 玩家序号 {} 过大（最大为 {}）", line_no, id, MAX_PLAYER_ID));
+            }
+            if scoreboard.players.contains_key(&id) {
+                return Err(format!("第 {} 行: 玩家序号 {} 重复", line_no, id));
+            }
+            scoreboard
+                .validate_name(&name, None)
+                .map_err(|e| format!("第 {} 行: {}", line_no, e))?;
+
+            scoreboard.players.insert(id, name);
+            scoreboard.scores.insert(id, score);
+            scoreboard.cleared.baseline.insert(id, score);
+            scoreboard.next_id = scoreboard.next_id.max(id.get() + 1);
+
+            for (column, field) in extras.iter().zip(&fields[3..]) {
+                if *column == "secondary" {
+                    let secondary = field
+                        .trim()
+                        .parse::<i32>()
+                        .map_err(|_| format!("第 {} 行: 无效的副积分 '{}'", line_no, field))?;
+                    scoreboard.secondary.get_or_insert_with(|| SecondaryScore {
+                        label: DEFAULT_SECONDARY_LABEL.to_string(),
+                        per_win: 0,
+                    });
+                    scoreboard.secondary_scores.insert(id, secondary);
+                } else {
+                    scoreboard
+                        .set_tag(id, field)
+                        .map_err(|e| format!("第 {} 行: {}", line_no, e))?;
+                }
+            }
+        }
+
+        Ok(scoreboard)
+    }
+
+    // 从每行一个 "名称=积分" 的简单文本导入积分板，按出现顺序分配新的序号。
+    // 空行和以 # 开头的注释行会被忽略，名称需满足与添加玩家相同的规则。
+    fn import_kv<R: BufRead>(reader: R, policy: NamePolicy) -> Result<Scoreboard, String> {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.name_policy = policy;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.map_err(|e| format!("读取第 {} 行失败: {}", line_no, e))?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, score) = line
+                .split_once('=')
+                .ok_or_else(|| format!("第 {} 行: 应为 名称=积分 格式，缺少 '='", line_no))?;
+            let name = name.trim();
+            let score = score
+                .trim()
+                .parse::<i32>()
+                .map_err(|_| format!("第 {} 行: 无效的积分 '{}'", line_no, score.trim()))?;
+            if scoreboard.players.values().any(|existing| existing == name) {
+                return Err(format!("第 {} 行: 玩家名称 '{}' 重复", line_no, name));
+            }
+
+            let id = scoreboard
+                .add_player(name.to_string())
+                .map_err(|e| format!("第 {} 行: {}", line_no, e))?;
+            scoreboard.scores.insert(id, score);
+            scoreboard.cleared.baseline.insert(id, score);
+        }
+
+        Ok(scoreboard)
+    }
+
+    fn history_view(&self) -> Vec<RoundSummary> {
+        self.history
+            .iter()
+            .map(|record| RoundSummary {
+                round: record.round,
+                timestamp: record.timestamp,
+                winner_id: record.winner_id,
+                winner_name: self.players.get(&record.winner_id).cloned(),
+                bonus: record.bonus,
+                weight: record.weight,
+            })
+            .collect()
+    }
+
+    fn display_history(&self) {
+        println!("\n=== 对局记录 ===");
+        let rounds = self.history_view();
+        if rounds.is_empty() {
+            println!("暂无对局记录");
+            println!();
+            return;
+        }
+
+        println!("{:<6} {:<24} {:<15}", "局数", "时间", "胜出玩家");
+        println!("{}", "-".repeat(40));
+        for summary in rounds {
+            let mut winner = match summary.winner_name {
+                Some(name) => format!("{} ({})", name, summary.winner_id),
+                None => format!("(已删除) ({})", summary.winner_id),
+            };
+            if summary.weight != 1 {
+                winner.push_str(&format!(" 权重 x{}", summary.weight));
+            }
+            if summary.bonus != 0 {
+                winner.push_str(&format!(" 连胜奖励 {}", self.delta_text(summary.bonus)));
+            }
+            println!(
+                "{:<6} {:<24} {:<15}",
+                summary.round,
+                format_timestamp(summary.timestamp),
+                winner
+            );
+        }
+        println!();
+    }
+
+    fn display_scoreboard(&self) {
+        let heading = match &self.title {
+            Some(title) => format!("{} 积分榜", title),
+            None => "积分榜".to_string(),
+        };
+        if self.practice {
+            println!("\n=== {}（练习模式）===", heading);
+        } else {
+            println!("\n=== {} ===", heading);
+        }
+        if self.is_empty() {
+            println!("暂无玩家");
+            println!();
+            return;
+        }
+        let ranks = self.ranks();
+        // 上次显示过积分榜时才显示名次变化
+        let deltas = self.rank_deltas();
+        let show_changes = !self.last_displayed_ranks.borrow().is_empty();
+        let rows: Vec<Vec<String>> = self
+            .display_order()
+            .into_iter()
+            .map(|(id, name, score)| {
+                // 手动调整过显示顺序或已退出的玩家在名称后标注
+                let mut name = self.tagged_name(id, name);
+                match self.pins.get(&id) {
+                    Some(priority) if *priority > 0 => name.push_str(" (置顶)"),
+                    Some(_) => name.push_str(" (置底)"),
+                    None => {}
+                }
+                if self.is_withdrawn(id) {
+                    name.push_str(" (已退出)");
+                }
+                let name = self.colored_name(id, &name);
+                let mut row = vec![ranks[&id].to_string(), id.to_string(), name, self.score_text(score)];
+                if let Some(secondary) = self.secondary_score(id) {
+                    row.push(secondary.to_string());
+                }
+                if self.show_relative_to_mean {
+                    row.push(self.relative_to_mean_text(id));
+                }
+                if show_changes {
+                    row.push(match deltas.get(&id) {
+                        Some(delta) => self.rank_change_text(*delta),
+                        None => "新".to_string(),
+                    });
+                }
+                row
+            })
+            .collect();
+
+        let mut headers = vec!["排名", "序号", "玩家名称", "积分"];
+        if let Some(secondary) = &self.secondary {
+            headers.push(&secondary.label);
+        }
+        if self.show_relative_to_mean {
+            headers.push("相对平均");
+        }
+        if show_changes {
+            headers.push("名次变化");
+        }
+        match self.table_style.box_chars() {
+            Some(chars) => print!("{}", render_box_table(&chars, &headers, &rows)),
+            None => {
+                // 积分列宽度随内容变化（如显示正号或小数时变宽），分隔线同步加长
+                let score_width = score_column_width(rows.iter().map(|row| row[3].as_str()));
+                // 积分之后的附加列（副积分、相对平均、名次变化）各占 8 个显示宽度
+                let extras = |cells: &[&str]| -> String {
+                    cells.iter().map(|cell| format!(" {}", pad_display(cell, 8))).collect()
+                };
+                println!(
+                    "{} {} {} {}{}",
+                    pad_display(headers[0], 4),
+                    pad_display(headers[1], 4),
+                    pad_display(headers[2], 15),
+                    pad_display(headers[3], score_width),
+                    extras(&headers[4..]).trim_end()
+                );
+                println!("{}", "-".repeat(29 + score_width + 9 * (headers.len() - 4)));
+                for row in &rows {
+                    // 名称可能带颜色转义序列和中文、emoji 等宽字符，按显示宽度补齐
+                    let padding = 15usize.saturating_sub(visible_width(&row[2]));
+                    let cells: Vec<&str> = row[4..].iter().map(String::as_str).collect();
+                    println!(
+                        "{:<4} {:<4} {}{} {:<score_width$}{}",
+                        row[0],
+                        row[1],
+                        row[2],
+                        " ".repeat(padding),
+                        row[3],
+                        extras(&cells).trim_end()
+                    );
+                }
+            }
+        }
+        *self.last_displayed_ranks.borrow_mut() = ranks;
+
+        for warning in self.anomalies() {
+            println!("警告: {}", warning);
+        }
+        println!();
+    }
+
+    // 按当前排名取前 n 名未退出的玩家作为种子，生成单败淘汰赛第一轮对阵。
+    // 对阵表人数补足到 2 的幂，第 1 名对最后一名、第 2 名对倒数第二名……，
+    // 并按标准顺序排列，使前两号种子只可能在决赛相遇；补足的空位为 None（轮空）。
+    fn generate_bracket(&self, n: usize) -> Vec<BracketMatch<'_>> {
+        let seeds: Vec<(PlayerId, &str)> = self
+            .ranked()
+            .into_iter()
+            .filter(|(id, _, _)| !self.is_withdrawn(*id))
+            .map(|(id, name, _)| (id, name))
+            .take(n)
+            .collect();
+        if seeds.len() < 2 {
+            return Vec::new();
+        }
+
+        // 种子顺序: [1, 2] -> [1, 4, 2, 3] -> [1, 8, 4, 5, 2, 7, 3, 6] ...
+        let size = seeds.len().next_power_of_two();
+        let mut order = vec![1usize];
+        while order.len() < size {
+            let total = order.len() * 2 + 1;
+            order = order.iter().flat_map(|seed| [*seed, total - seed]).collect();
+        }
+        order
+            .chunks(2)
+            .map(|pair| (seeds.get(pair[0] - 1).copied(), seeds.get(pair[1] - 1).copied()))
+            .collect()
+    }
+
+    // 与上次显示积分榜时相比的名次变化，正数表示名次上升了几位；上次显示后才加入的玩家不在结果中
+    fn rank_deltas(&self) -> HashMap<PlayerId, i32> {
+        let previous = self.last_displayed_ranks.borrow();
+        self.ranks()
+            .into_iter()
+            .filter_map(|(id, rank)| previous.get(&id).map(|before| (id, *before as i32 - rank as i32)))
+            .collect()
+    }
+
+    // 名次变化标记：▲ 上升、▼ 下降、= 不变，开启颜色时分别用绿、红、灰色
+    fn rank_change_text(&self, delta: i32) -> String {
+        let (text, color) = match delta {
+            d if d > 0 => (format!("▲{}", d), Color { r: 0x3c, g: 0xb4, b: 0x4b }),
+            d if d < 0 => (format!("▼{}", -d), Color { r: 0xe6, g: 0x19, b: 0x4b }),
+            _ => ("=".to_string(), Color { r: 0x88, g: 0x88, b: 0x88 }),
+        };
+        if self.use_color { color.paint(&text) } else { text }
+    }
+
+    // 所有玩家的平均积分，没有玩家时返回 None
+    fn mean_score(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let total: i64 = self.players.keys().map(|id| *self.scores.get(id).unwrap_or(&0) as i64).sum();
+        Some(total as f64 / self.player_count() as f64)
+    }
+
+    // 玩家积分与平均积分之差，正数表示高于平均；玩家不存在时返回 None
+    fn score_relative_to_mean(&self, id: PlayerId) -> Option<f64> {
+        let score = *self.scores.get(&id).filter(|_| self.players.contains_key(&id))?;
+        Some(score as f64 - self.mean_score()?)
+    }
+
+    // 相对平均积分的显示文本，高于平均时带 "+" 号
+    fn relative_to_mean_text(&self, id: PlayerId) -> String {
+        match self.score_relative_to_mean(id) {
+            Some(delta) => PositiveSign::NonZero.apply(format_score_rounded(
+                delta,
+                self.score_precision,
+                self.rounding_mode,
+            )),
+            None => "-".to_string(),
+        }
+    }
+
+    // 修改操作（如记录胜局）完成后按 auto_display 设置自动显示积分榜
+    fn display_after_action(&self) {
+        if self.auto_display {
+            self.display_scoreboard();
+        }
+    }
+
+    // 冻结当前积分，替换之前的冻结快照
+    fn freeze(&mut self, label: String) {
+        self.frozen = Some(FrozenSnapshot {
+            label,
+            timestamp: current_timestamp(),
+            round: self.rounds_played(),
+            scores: self.scores.clone(),
+        });
+    }
+
+    // 按当前排名列出每位玩家的冻结时积分与当前积分；冻结后加入的玩家冻结时积分为 None
+    fn frozen_comparison(&self) -> Option<Vec<FrozenRow<'_>>> {
+        let frozen = self.frozen.as_ref()?;
+        Some(
+            self.ranked()
+                .into_iter()
+                .map(|(id, name, score)| (id, name, frozen.scores.get(&id).copied(), score))
+                .collect(),
+        )
+    }
+
+    fn display_frozen_comparison(&self) {
+        print!("{}", self.frozen_comparison_text());
+    }
+
+    // 冻结积分与当前积分的对比表，尚未冻结时为提示文本
+    fn frozen_comparison_text(&self) -> String {
+        let (Some(frozen), Some(rows)) = (&self.frozen, self.frozen_comparison()) else {
+            return "尚未冻结积分，请先使用\"冻结积分\"。\n".to_string();
+        };
+        let mut text = format!(
+            "\n=== 积分对比: {}（第 {} 局后，{}）===\n",
+            frozen.label,
+            frozen.round,
+            format_timestamp(frozen.timestamp)
+        );
+        let lines: Vec<(PlayerId, &str, String, String, String)> = rows
+            .into_iter()
+            .map(|(id, name, before, now)| {
+                let (before_text, delta_text) = match before {
+                    Some(before) => (self.score_text(before), self.delta_text(now - before)),
+                    None => ("-".to_string(), "新加入".to_string()),
+                };
+                (id, name, before_text, self.score_text(now), delta_text)
+            })
+            .collect();
+        let width = score_column_width(
+            lines
+                .iter()
+                .flat_map(|(_, _, before, now, _)| [before.as_str(), now.as_str()]),
+        );
+        // 表头、名称和"新加入"含中文等宽字符，按显示宽度补齐，分隔线与表头等宽
+        let header = format!(
+            "{} {} {} {} {}",
+            pad_display("序号", 4),
+            pad_display("玩家名称", 15),
+            pad_display("冻结时", width),
+            pad_display("当前", width),
+            pad_display("变化", 6)
+        );
+        text.push_str(&format!("{}\n", header));
+        text.push_str(&format!("{}\n", "-".repeat(visible_width(&header))));
+        for (id, name, before_text, now_text, delta_text) in lines {
+            text.push_str(&format!(
+                "{:<4} {} {:<width$} {:<width$} {}\n",
+                id,
+                pad_display(name, 15),
+                before_text,
+                now_text,
+                pad_display(&delta_text, 6)
+            ));
+        }
+        text.push('\n');
+        text
+    }
+
+    fn list_players(&self) {
+        self.list_players_sorted(PlayerSort::Id);
+    }
+
+    // 按指定方式排列的玩家；名称或积分相同时按序号排列
+    fn sorted_players(&self, by: PlayerSort) -> Vec<(PlayerId, &str)> {
+        let mut players: Vec<(PlayerId, &str)> = match by {
+            PlayerSort::Score => self.ranked().into_iter().map(|(id, name, _)| (id, name)).collect(),
+            _ => self.players.iter().map(|(id, name)| (*id, name.as_str())).collect(),
+        };
+        match by {
+            PlayerSort::Id => players.sort_by_key(|(id, _)| *id),
+            PlayerSort::Name => players.sort_by(|(a_id, a), (b_id, b)| {
+                a.to_lowercase()
+                    .cmp(&b.to_lowercase())
+                    .then_with(|| a.cmp(b))
+                    .then(a_id.cmp(b_id))
+            }),
+            PlayerSort::Score => {}
+        }
+        players
+    }
+
+    fn list_players_sorted(&self, by: PlayerSort) {
+        println!("\n=== 玩家列表 ===");
+        if self.is_empty() {
+            println!("暂无玩家");
+        }
+
+        for (id, name) in self.sorted_players(by) {
+            let mut line = format!("{}: {}", id, self.tagged_name(id, name));
+            if by == PlayerSort::Score {
+                line.push_str(&format!("  {}分", self.score_text(*self.scores.get(&id).unwrap_or(&0))));
+            }
+            if self.is_withdrawn(id) {
+                line.push_str(" (已退出)");
+            }
+            println!("{}", line);
+        }
+
+        // 序号不连续时明确提示，避免以为中间缺的序号也存在
+        let ids = self.existing_ids();
+        if ids.last().is_some_and(|last| last.get() != ids.len()) {
+            println!("注意: 序号不连续，只能使用以上列出的序号（{}）", join_ids(&ids));
+        }
+        println!();
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BoardEntry {
+    name: String,
+    board: Scoreboard,
+}
+
+// 存档格式版本。修改存档结构时递增，并在 Workspace::migrate 中处理旧版本。
+// 版本 1 为最初没有 version 字段的格式，版本 2 起写入 version 字段。
+const FORMAT_VERSION: u32 = 2;
+
+fn legacy_format_version() -> u32 {
+    1
+}
+
+// 同一会话中的多个独立积分板，所有操作作用于当前激活的积分板，并一起保存到同一个文件
+#[derive(Serialize, Deserialize)]
+struct Workspace {
+    #[serde(default = "legacy_format_version")]
+    version: u32,
+    boards: Vec<BoardEntry>,
+    active: usize,
+    // 练习模式下所有操作作用于当前积分板的临时副本，该副本不会被保存
+    #[serde(skip)]
+    practice: Option<Scoreboard>,
+}
+
+impl Workspace {
+    fn new(board: Scoreboard) -> Self {
+        Workspace {
+            boards: vec![BoardEntry {
+                name: "默认".to_string(),
+                board,
+            }],
+            version: FORMAT_VERSION,
+            active: 0,
+            practice: None,
+        }
+    }
+
+    fn active_name(&self) -> &str {
+        &self.boards[self.active].name
+    }
+
+    fn active_board(&self) -> &Scoreboard {
+        match &self.practice {
+            Some(board) => board,
+            None => &self.boards[self.active].board,
+        }
+    }
+
+    fn active_board_mut(&mut self) -> &mut Scoreboard {
+        match &mut self.practice {
+            Some(board) => board,
+            None => &mut self.boards[self.active].board,
+        }
+    }
+
+    fn in_practice(&self) -> bool {
+        self.practice.is_some()
+    }
+
+    // 进入练习模式：复制当前积分板作为临时积分板
+    fn enter_practice(&mut self) {
+        let mut board = self.boards[self.active].board.clone();
+        board.practice = true;
+        self.practice = Some(board);
+    }
+
+    // 退出练习模式：丢弃临时积分板，恢复原积分板
+    fn exit_practice(&mut self) {
+        self.practice = None;
+    }
+
+    fn validate_board_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("积分板名称不能为空".to_string());
+        }
+        if name.chars().count() > 20 {
+            return Err("积分板名称过长，请限制在20个字符以内".to_string());
+        }
+        if name.chars().any(|c| c.is_control()) {
+            return Err("积分板名称不能包含控制字符".to_string());
+        }
+        if self.boards.iter().any(|entry| entry.name == name) {
+            return Err(format!("积分板 '{}' 已存在", name));
+        }
+        Ok(())
+    }
+
+    fn ensure_not_practice(&self) -> Result<(), String> {
+        if self.in_practice() {
+            return Err("练习模式下无法管理积分板，请先退出练习模式".to_string());
+        }
+        Ok(())
+    }
+
+    // 新建积分板并切换到该积分板，返回其序号（从 1 开始）
+    fn add_board(&mut self, name: String, board: Scoreboard) -> Result<usize, String> {
+        self.ensure_not_practice()?;
+        self.validate_board_name(&name)?;
+        self.boards.push(BoardEntry { name, board });
+        self.active = self.boards.len() - 1;
+        Ok(self.boards.len())
+    }
+
+    // 切换到指定序号（从 1 开始）的积分板
+    fn switch_to(&mut self, number: usize) -> Result<(), String> {
+        self.ensure_not_practice()?;
+        if number == 0 || number > self.boards.len() {
+            return Err(format!("积分板序号 {} 不存在", number));
+        }
+        self.active = number - 1;
+        Ok(())
+    }
+
+    fn rename_active(&mut self, name: String) -> Result<(), String> {
+        self.ensure_not_practice()?;
+        if self.active_name() == name {
+            return Ok(());
+        }
+        self.validate_board_name(&name)?;
+        self.boards[self.active].name = name;
+        Ok(())
+    }
+
+    // 将旧版本存档升级到当前格式
+    fn migrate(&mut self) {
+        // 版本 1 -> 2：仅新增 version 字段，其余新字段均有默认值
+        if self.version < FORMAT_VERSION {
+            self.version = FORMAT_VERSION;
+        }
+    }
+
+    // 先把上一次成功保存的文件复制为 .bak，再原子地写入新内容，
+    // 这样即使保存过程中程序崩溃，也至少还有一份完整的存档
+    fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("序列化失败: {}", e))?;
+        // 已损坏的旧文件不作为备份，以免覆盖之前完好的备份
+        if let Ok(previous) = fs::read_to_string(path)
+            && serde_json::from_str::<serde_json::Value>(&previous).is_ok()
+        {
+            let backup = backup_path(path);
+            fs::write(&backup, previous)
+                .map_err(|e| format!("无法写入备份文件 '{}': {}", backup.display(), e))?;
+        }
+        write_atomic(path, &json).map_err(|e| format!("无法写入文件 '{}': {}", path.display(), e))
+    }
+
+    // 从文件加载工作区，同时返回修复手动编辑造成的不一致时产生的警告。
+    // 文件内容不是完整的 JSON（如保存时崩溃导致文件被截断）时，尝试从上一次保存的 .bak 备份恢复。
+    fn load_from_file(path: &Path) -> Result<(Workspace, Vec<String>), String> {
+        let error = match Self::load_from_path(path) {
+            Ok(loaded) => return Ok(loaded),
+            Err(error) => error,
+        };
+        let corrupt = fs::read_to_string(path)
+            .is_ok_and(|content| serde_json::from_str::<serde_json::Value>(&content).is_err());
+        let backup = backup_path(path);
+        if !corrupt || !backup.exists() {
+            return Err(error);
+        }
+        let (workspace, mut warnings) = Self::load_from_path(&backup)
+            .map_err(|backup_error| format!("{}；备份也无法使用: {}", error, backup_error))?;
+        warnings.insert(
+            0,
+            format!(
+                "{}，已从备份 '{}' 恢复上一次保存的内容",
+                error,
+                backup.display()
+            ),
+        );
+        Ok((workspace, warnings))
+    }
+
+    fn load_from_path(path: &Path) -> Result<(Workspace, Vec<String>), String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("无法读取文件 '{}': {}", path.display(), e))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("文件 '{}' 格式错误: {}", path.display(), e))?;
+        // 先检查版本，避免用当前结构解析更新版本的存档时产生难以理解的错误
+        let version = match value.get("version") {
+            None => legacy_format_version(),
+            Some(version) => version
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| format!("文件 '{}' 的格式版本无效: {}", path.display(), version))?,
+        };
+        if version > FORMAT_VERSION {
+            return Err(format!(
+                "文件 '{}' 的格式版本为 {}，高于本程序支持的版本 {}，请升级程序后再打开",
+                path.display(),
+                version,
+                FORMAT_VERSION
+            ));
+        }
+        let mut workspace: Workspace = serde_json::from_value(value)
+            .map_err(|e| format!("文件 '{}' 格式错误: {}", path.display(), e))?;
+        workspace.migrate();
+        if workspace.boards.is_empty() {
+            return Err(format!("文件 '{}' 中没有积分板", path.display()));
+        }
+        if workspace.active >= workspace.boards.len() {
+            return Err(format!("文件 '{}' 中的当前积分板序号无效", path.display()));
+        }
+
+        let mut warnings = Vec::new();
+        for entry in &mut workspace.boards {
+            for warning in entry.board.reconcile() {
+                warnings.push(format!("积分板 '{}': {}", entry.name, warning));
+            }
+        }
+        Ok((workspace, warnings))
+    }
+
+    fn list_boards(&self) {
+        println!("\n=== 积分板列表 ===");
+        for (index, entry) in self.boards.iter().enumerate() {
+            let marker = if index == self.active { " (当前)" } else { "" };
+            println!(
+                "{}: {} - {} 名玩家，{} 局{}",
+                index + 1,
+                entry.name,
+                entry.board.players.len(),
+                entry.board.rounds_played(),
+                marker
+            );
+        }
+        println!();
+    }
+}
+
+// 赛季：跨越多个比赛日的联赛，每个比赛日为一个存档文件中的当前积分板
+struct Season {
+    // （比赛日名称, 积分板），按比赛日的先后排列
+    sessions: Vec<(String, Scoreboard)>,
+}
+
+// 赛季玩家与各比赛日玩家的对应关系
+struct SeasonMapping {
+    // 赛季序号 -> （名称, [（比赛日在赛季中的位置, 该比赛日中的序号）]）
+    players: BTreeMap<PlayerId, (String, Vec<(usize, PlayerId)>)>,
+    // 可能把不同的人合并、或把同一人分开统计的名称
+    warnings: Vec<String>,
+}
+
+// 合并赛季玩家时比较的名称形式：Unicode 兼容分解后再组合（全角与半角字符视为相同），连续空白视为一个空格
+fn season_name_key(name: &str) -> String {
+    let normalized: String = name.nfkc().collect();
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// 保存到文件的赛季：按顺序列出各比赛日的存档文件，相对路径相对于赛季文件所在的目录
+#[derive(Serialize, Deserialize)]
+struct SeasonFile {
+    sessions: Vec<PathBuf>,
+}
+
+impl Season {
+    // 从赛季文件或存档目录读取赛季，返回赛季、各比赛日的存档文件和加载时的警告
+    fn load(path: &Path) -> Result<(Season, Vec<PathBuf>, Vec<String>), String> {
+        let files = if path.is_dir() {
+            Self::session_files(path)?
+        } else {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("无法读取赛季文件 '{}': {}", path.display(), e))?;
+            let season: SeasonFile = serde_json::from_str(&content)
+                .map_err(|e| format!("赛季文件 '{}' 格式错误: {}", path.display(), e))?;
+            let base = path.parent().unwrap_or(Path::new(""));
+            season.sessions.iter().map(|file| base.join(file)).collect()
+        };
+        if files.is_empty() {
+            return Err(format!("'{}' 中没有比赛日存档", path.display()));
+        }
+
+        let mut sessions = Vec::new();
+        let mut warnings = Vec::new();
+        for file in &files {
+            let (workspace, loaded_warnings) = Workspace::load_from_file(file)?;
+            let label = file
+                .file_stem()
+                .map_or_else(|| file.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+            warnings.extend(loaded_warnings.into_iter().map(|warning| format!("{}: {}", label, warning)));
+            sessions.push((label, workspace.active_board().clone()));
+        }
+        Ok((Season { sessions }, files, warnings))
+    }
+
+    // 目录中的比赛日存档：所有 .json 文件（赛季文件除外），按文件名排列
+    fn session_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let entries = fs::read_dir(dir).map_err(|e| format!("无法读取目录 '{}': {}", dir.display(), e))?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+            .filter(|path| {
+                fs::read_to_string(path)
+                    .ok()
+                    .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                    .is_none_or(|value| value.get("sessions").is_none())
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    // 保存赛季文件；与赛季文件位于同一目录的存档只记录文件名，便于整个目录一起移动
+    fn save(path: &Path, files: &[PathBuf]) -> Result<(), String> {
+        let base = path.parent().unwrap_or(Path::new(""));
+        let sessions = files
+            .iter()
+            .map(|file| file.strip_prefix(base).map_or_else(|_| file.clone(), Path::to_path_buf))
+            .collect();
+        let json = serde_json::to_string_pretty(&SeasonFile { sessions })
+            .map_err(|e| format!("序列化失败: {}", e))?;
+        write_atomic(path, &json).map_err(|e| format!("无法写入赛季文件 '{}': {}", path.display(), e))
+    }
+
+    // 按名称对应各比赛日的玩家：同一人在不同比赛日的序号可以不同，名称按 season_name_key 规范化后相同即视为同一人，
+    // 玩家按第一次出现的先后分配赛季序号，名称取第一次出现时的写法。
+    // 同一比赛日中有多名玩家规范化后名称相同时，他们分开统计，各自只合并写法完全相同的玩家；
+    // 只有大小写或重音不同的名称不合并。两种情况都会在警告中列出。
+    fn player_mapping(&self) -> SeasonMapping {
+        let mut mapping = SeasonMapping { players: BTreeMap::new(), warnings: Vec::new() };
+        // 规范化名称 -> 第一次出现的赛季玩家；原始写法 -> 赛季玩家
+        let mut by_key: HashMap<String, PlayerId> = HashMap::new();
+        let mut by_name: HashMap<String, PlayerId> = HashMap::new();
+        for (index, (label, board)) in self.sessions.iter().enumerate() {
+            // 按序号顺序分组，使赛季序号按玩家在比赛日中的先后分配
+            let mut groups: Vec<(String, Vec<PlayerId>)> = Vec::new();
+            for id in board.existing_ids() {
+                let key = season_name_key(&board.players[&id]);
+                match groups.iter_mut().find(|(existing, _)| *existing == key) {
+                    Some((_, ids)) => ids.push(id),
+                    None => groups.push((key, vec![id])),
+                }
+            }
+            let mut assigned = Vec::new();
+            for (key, ids) in groups {
+                if ids.len() > 1 {
+                    let names: Vec<String> = ids.iter().map(|id| format!("'{}'", board.players[id])).collect();
+                    mapping.warnings.push(format!(
+                        "{}: {} 规范化后名称相同，但在同一比赛日中是不同玩家，按不同玩家统计",
+                        label,
+                        names.join("、")
+                    ));
+                }
+                let mut used = Vec::new();
+                for id in ids {
+                    let name = &board.players[&id];
+                    let existing = if used.is_empty() && !by_name.contains_key(name) {
+                        by_key.get(&key).copied()
+                    } else {
+                        by_name.get(name).copied()
+                    };
+                    let season_id = match existing.filter(|season_id| !used.contains(season_id)) {
+                        Some(season_id) => season_id,
+                        None => {
+                            let season_id = PlayerId(mapping.players.len() + 1);
+                            mapping.players.insert(season_id, (name.clone(), Vec::new()));
+                            season_id
+                        }
+                    };
+                    by_key.entry(key.clone()).or_insert(season_id);
+                    by_name.entry(name.clone()).or_insert(season_id);
+                    used.push(season_id);
+                    assigned.push((season_id, id));
+                }
+            }
+            for (season_id, id) in assigned {
+                if let Some((_, sources)) = mapping.players.get_mut(&season_id) {
+                    sources.push((index, id));
+                }
+            }
+        }
+
+        // 只有大小写或重音不同的赛季玩家可能是同一人；规范化后完全相同的已在上面提示过
+        let relaxed = NamePolicy { accent_insensitive: true, ..NamePolicy::default() };
+        let mut similar: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for (name, _) in mapping.players.values() {
+            similar.entry(relaxed.comparison_key(&season_name_key(name))).or_default().push(name);
+        }
+        let distinct = |names: &&Vec<&str>| {
+            let keys: HashSet<String> = names.iter().map(|name| season_name_key(name)).collect();
+            keys.len() > 1
+        };
+        for names in similar.values().filter(distinct) {
+            mapping.warnings.push(format!(
+                "{} 名称相近，按不同玩家统计；如为同一人，请在存档中统一名称",
+                names.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join("、")
+            ));
+        }
+        mapping
+    }
+
+    // 赛季累计积分榜：按 player_mapping 合并各比赛日的玩家，积分和胜场分别相加。
+    // 累计积分板没有逐局记录，各比赛日的局数、胜场和连胜奖励按清空对局记录的方式记入汇总，
+    // 使胜场排名依据和积分校验仍然有效。累计积分超出 i32 范围时返回错误。
+    fn aggregate(&self) -> Result<Scoreboard, String> {
+        let mut season = Scoreboard::new();
+        for (season_id, (name, sources)) in self.player_mapping().players {
+            let mut total: i32 = 0;
+            for (index, id) in sources {
+                let (label, board) = &self.sessions[index];
+                total = total.checked_add(*board.scores.get(&id).unwrap_or(&0)).ok_or_else(|| {
+                    format!("玩家 '{}' 的赛季累计积分超出可记录的范围（加上 {} 的积分时）", name, label)
+                })?;
+                *season.cleared.wins.entry(season_id).or_insert(0) += board.wins(id);
+                if let Some(start) = board.cleared.baseline.get(&id) {
+                    let baseline = season.cleared.baseline.entry(season_id).or_insert(0);
+                    *baseline = baseline.checked_add(*start).ok_or_else(|| {
+                        format!("玩家 '{}' 的赛季累计初始积分超出可记录的范围（加上 {} 的积分时）", name, label)
+                    })?;
+                }
+            }
+            season.scores.insert(season_id, total);
+            season.players.insert(season_id, name);
+            season.next_id = season.next_id.max(season_id.get() + 1);
+        }
+        for (_, board) in &self.sessions {
+            let mut cleared = board.clone();
+            cleared.clear_history();
+            season.cleared.rounds += cleared.cleared.rounds;
+            season.cleared.weighted_rounds += cleared.cleared.weighted_rounds;
+            season.cleared.total_bonus += cleared.cleared.total_bonus;
+            // 积分校验按各比赛日中单局扣分最多的规则计算上限
+            if board.scoring.max_penalty() > season.scoring.max_penalty() {
+                season.scoring = board.scoring.clone();
+            }
+            if board.min_score.is_some() {
+                season.min_score = board.min_score;
+            }
+        }
+        season.title = Some(format!("赛季（{} 个比赛日）", self.sessions.len()));
+        Ok(season)
+    }
+}
+
+fn run_season(path: &str, save: Option<&str>, show_mapping: bool) -> Result<(), String> {
+    let (season, files, warnings) = Season::load(Path::new(path))?;
+    for warning in warnings {
+        println!("警告: {}", warning);
+    }
+    for (label, board) in &season.sessions {
+        println!("{}: {} 名玩家，{} 局", label, board.player_count(), board.rounds_played());
+    }
+    let mapping = season.player_mapping();
+    for warning in &mapping.warnings {
+        println!("注意: {}", warning);
+    }
+    if show_mapping {
+        println!("\n=== 赛季玩家对应关系 ===");
+        for (season_id, (name, sources)) in &mapping.players {
+            let sources: Vec<String> = sources
+                .iter()
+                .map(|(index, id)| {
+                    let (label, board) = &season.sessions[*index];
+                    format!("{} 序号 {}（{}）", label, id, board.players[id])
+                })
+                .collect();
+            println!("{}: {} <- {}", season_id, name, sources.join("，"));
+        }
+    }
+    season.aggregate()?.display_scoreboard();
+    if let Some(save) = save {
+        Season::save(Path::new(save), &files)?;
+        println!("赛季文件已保存到 {}", save);
+    }
+    Ok(())
+}
+
+// 把一组积分计入各玩家的（最低分, 最高分）
+fn fold_extremes(extremes: &mut HashMap<PlayerId, (i32, i32)>, scores: &HashMap<PlayerId, i32>) {
+    for (id, score) in scores {
+        let entry = extremes.entry(*id).or_insert((*score, *score));
+        *entry = (entry.0.min(*score), entry.1.max(*score));
+    }
+}
+
+// 按 A..Z、AA..AZ、BA…… 的顺序为第 index 个（从 0 开始）编号
+fn alphabetic_label(mut index: usize) -> String {
+    let mut label = Vec::new();
+    loop {
+        label.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap_or_default()
+}
+
+// 存档的备份文件，与存档位于同一目录，如 scores.json.bak
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+// 示例工作区：所有会保存到文件的字段都填入示例值。
+// 由真实的存档类型构造并序列化，存档格式变化时示例会随之变化，不会与实际格式不一致。
+fn example_workspace() -> Result<Workspace, String> {
+    const EXAMPLE_TIME: u64 = 1_700_000_000;
+    let mut board = Scoreboard::new();
+    board.started_at = EXAMPLE_TIME;
+    board.title = Some("周五联赛".to_string());
+    board.min_score = Some(-10);
+    board.name_policy = NamePolicy {
+        reject_numeric: true,
+        accent_insensitive: true,
+    };
+    board.tie_break = TieBreak::Wins;
+    board.streak_bonus = Some(StreakBonus { milestone: 3, bonus: 1 });
+    board.set_scoring(ScoringRule {
+        loser_delta: 1,
+        rank_penalties: vec![2],
+    })?;
+    board.score_precision = 1;
+    board.show_positive_sign = PositiveSign::NonZero;
+    board.lock_roster_after_first_round = true;
+    board.roster_unlocked = true;
+    board.table_style = TableStyle::Ascii;
+    board.target_score = Some(10);
+    board.stop_at_target = true;
+    board.show_relative_to_mean = true;
+    board.withdrawn_winner = WithdrawnWinnerPolicy::Reinstate;
+    board.secondary = Some(SecondaryScore {
+        label: "筹码".to_string(),
+        per_win: 1,
+    });
+
+    let zhang = board.add_player("张三".to_string())?;
+    let li = board.add_player("李四".to_string())?;
+    let wang = board.add_player("王五".to_string())?;
+    // 第 1 局之后清空对局记录，使存档中带有清空记录的汇总
+    board.update_scores(zhang)?;
+    board.clear_history();
+    board.update_scores(zhang)?;
+    board.update_scores_weighted(li, 2)?;
+    board.update_scores_among(zhang, &[zhang, wang])?;
+    for record in &mut board.history {
+        record.timestamp = EXAMPLE_TIME + 300 * u64::from(record.round);
+        record.duration = Some(Duration::from_secs(300));
+    }
+    board.adjust_secondary(wang, 2)?;
+    board.pin_player(wang, -1)?;
+    board.withdraw_player(wang)?;
+    board.freeze("半场".to_string());
+    if let Some(frozen) = board.frozen.as_mut() {
+        frozen.timestamp = EXAMPLE_TIME + 1_200;
+    }
+    Ok(Workspace::new(board))
+}
+
+// 示例存档的 JSON 文本。JSON 不支持注释，说明写在 "$comment" 字段中，加载时会被忽略
+fn example_save_json() -> Result<String, String> {
+    let workspace = example_workspace()?;
+    let mut value = serde_json::to_value(&workspace).map_err(|e| format!("序列化失败: {}", e))?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert(
+            "$comment".to_string(),
+            serde_json::Value::String(
+                "示例存档（--print-schema 生成）。boards 为所有积分板，active 为当前积分板的下标；\
+                 玩家以序号为键，时间为 Unix 时间戳（秒），duration 为 {secs, nanos}。\
+                 积分板中除 players、scores、next_id、history、name_policy、pins 外的字段都可以省略，省略时使用默认值"
+                    .to_string(),
+            ),
+        );
+    }
+    serde_json::to_string_pretty(&value).map_err(|e| format!("序列化失败: {}", e))
+}
+
+fn run_dump_ids(file: Option<&str>) -> Result<(), String> {
+    let path = file.ok_or_else(|| "--dump-ids 需要与 --file 一起使用".to_string())?;
+    let (workspace, warnings) = Workspace::load_from_file(Path::new(path))?;
+    for warning in warnings {
+        eprintln!("警告: {}", warning);
+    }
+    let json = serde_json::to_string_pretty(&workspace.active_board().id_name_map())
+        .map_err(|e| format!("序列化失败: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+// 玩家标记的最大显示宽度和字符数（组合 emoji 由多个字符组成，字符数放宽一些）
+const MAX_TAG_WIDTH: usize = 4;
+const MAX_TAG_CHARS: usize = 8;
+
+// 积分分布中最多列出的区间数（含没有玩家的区间）
+const MAX_HISTOGRAM_BUCKETS: usize = 100;
+
+// 观看模式默认的刷新间隔（秒）
+const DEFAULT_WATCH_INTERVAL: u64 = 3;
+
+// 观看模式：每隔 interval 秒重新读取存档并清屏显示积分榜，供大屏幕展示。
+// 存档由另一个正在记分的程序写入（原子替换），这里只读取、从不保存。
+fn run_watch(file: Option<&str>, interval: u64) -> Result<(), String> {
+    use crossterm::cursor::MoveTo;
+    use crossterm::execute;
+    use crossterm::terminal::{Clear, ClearType};
+
+    let path = file.ok_or_else(|| "--watch 需要与 --file 一起使用".to_string())?;
+    // 第一次读取失败直接报错，避免文件名写错时一直空刷新
+    Workspace::load_from_file(Path::new(path))?;
+    loop {
+        let _ = execute!(io::stdout(), Clear(ClearType::All), MoveTo(0, 0));
+        match Workspace::load_from_file(Path::new(path)) {
+            Ok((workspace, _)) => workspace.active_board().display_scoreboard(),
+            // 读取失败（如文件暂时不可读）时保留提示，下次刷新再试
+            Err(e) => println!("读取存档失败: {}", e),
+        }
+        println!(
+            "观看模式：每 {} 秒刷新，最后更新 {}。按 q 退出。",
+            interval,
+            format_timestamp(current_timestamp())
+        );
+        if wait_for_quit(Duration::from_secs(interval)) {
+            return Ok(());
+        }
+    }
+}
+
+// 等待 timeout，期间按下 q、Esc 或 Ctrl+C 时返回 true。
+// 原始模式下 Ctrl+C 不会产生信号，因此按键处理；无法进入原始模式（如输出重定向）时
+// 只是等待，Ctrl+C 仍按默认方式结束程序。
+fn wait_for_quit(timeout: Duration) -> bool {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+
+    if terminal::enable_raw_mode().is_err() {
+        std::thread::sleep(timeout);
+        return false;
+    }
+    let deadline = Instant::now() + timeout;
+    let quit = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break false;
+        }
+        match event::poll(remaining) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q' | 'Q') | KeyCode::Esc => break true,
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break true,
+                    _ => {}
+                },
+                Ok(_) => {}
+                Err(_) => break false,
+            },
+            Ok(false) => break false,
+            Err(_) => break false,
+        }
+    };
+    // 恢复终端，否则之后的输出会错乱
+    let _ = terminal::disable_raw_mode();
+    quit
+}
+
+// 先写入同目录下的临时文件再重命名，避免读取方看到写了一半的内容
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = Path::new(&tmp);
+    fs::write(tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+// 按 CSV 规则转义字段：包含逗号或引号时用双引号包裹，内部引号写作两个引号
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 按指定小数位数格式化积分：去掉末尾多余的 0 和小数点，且不会出现 "-0"
+fn format_score(value: f64, precision: usize) -> String {
+    let mut text = format!("{:.*}", precision, value);
+    if text.contains('.') {
+        text = text.trim_end_matches('0').trim_end_matches('.').to_string();
+    }
+    if text == "-0" {
+        text = "0".to_string();
+    }
+    text
+}
+
+// 先按舍入方式舍入到指定小数位数再格式化；内部保存的数值不受影响
+fn format_score_rounded(value: f64, precision: usize, mode: RoundingMode) -> String {
+    format_score(mode.apply(value, precision), precision)
+}
+
+// 积分变化的格式化：与积分相同的舍入规则，非零时总是带符号，所有显示积分变化的地方都应使用它
+fn format_delta(delta: i32, precision: usize, mode: RoundingMode) -> String {
+    PositiveSign::NonZero.apply(format_score_rounded(delta as f64, precision, mode))
+}
+
+// 转义 XML/SVG 文本中的特殊字符
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 解析一行 CSV，支持双引号包裹的字段
+fn parse_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut in_quotes = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("引号未闭合".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// 将 Unix 时间戳格式化为 UTC 时间 "YYYY-MM-DD HH:MM:SS UTC"
+fn format_timestamp(timestamp: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_timestamp(timestamp);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, hour, minute, second
+    )
+}
+
+// 将 Unix 时间戳格式化为 RFC 3339 格式 "YYYY-MM-DDTHH:MM:SSZ"，便于电子表格识别
+fn format_timestamp_rfc3339(timestamp: u64) -> String {
+    let (year, month, day, hour, minute, second) = civil_from_timestamp(timestamp);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, minute, second
+    )
+}
+
+// 将 Unix 时间戳换算为 UTC 的 (年, 月, 日, 时, 分, 秒)
+fn civil_from_timestamp(timestamp: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let days = (timestamp / 86_400) as i64;
+    let secs_of_day = timestamp % 86_400;
+
+    // 由天数推算公历日期
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60,
+    )
+}
+
+// 将时长格式化为 "X小时YY分ZZ秒" 或 "X分YY秒"，不足一分钟时显示 "Y.Z秒"
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{:.1}秒", duration.as_secs_f64())
+    } else if secs < 3_600 {
+        format!("{}分{:02}秒", secs / 60, secs % 60)
+    } else {
+        format!("{}小时{:02}分{:02}秒", secs / 3_600, secs % 3_600 / 60, secs % 60)
+    }
+}
+
+// 单行输入的读取上限（字节），超出部分直接丢弃而不会缓存到内存中
+const MAX_LINE_BYTES: usize = 1024;
+
+// 读取一行，最多缓存 max_bytes 字节；超长的行会被读完并丢弃，返回错误
+fn read_line_capped<R: BufRead>(reader: &mut R, max_bytes: usize) -> Result<String, String> {
+    let read_error = |_| "读取输入失败，可能是由于输入流错误或中断".to_string();
+
+    let mut buffer = Vec::new();
+    let read = reader
+        .by_ref()
+        .take(max_bytes as u64 + 1)
+        .read_until(b'\n', &mut buffer)
+        .map_err(read_error)?;
+    if read == 0 {
+        return Err("输入流已结束 (EOF)".to_string());
+    }
+
+    if buffer.len() > max_bytes && buffer.last() != Some(&b'\n') {
+        // 丢弃本行剩余内容，直到换行或输入结束
+        loop {
+            let available = reader.fill_buf().map_err(read_error)?;
+            if available.is_empty() {
+                break;
+            }
+            match available.iter().position(|b| *b == b'\n') {
+                Some(pos) => {
+                    reader.consume(pos + 1);
+                    break;
+                }
+                None => {
+                    let len = available.len();
+                    reader.consume(len);
+                }
+            }
+        }
+        return Err("输入内容过长，请限制在50个字符以内".to_string());
+    }
+
+    // 整行已被读取，因此编码错误不影响之后的输入，调用方可以提示重新输入
+    String::from_utf8(buffer).map_err(|_| "输入包含无效的 UTF-8 编码，请检查终端的字符编码设置后重新输入".to_string())
+}
+
+// 从 reader 读取一行并去除首尾空白，检查输入长度
+fn read_input<R: BufRead>(reader: &mut R) -> Result<String, String> {
+    let input = read_line_capped(reader, MAX_LINE_BYTES)?;
+    let trimmed = input.trim().to_string();
+    // 检查输入长度
+    if trimmed.len() > 50 {
+        Err("输入内容过长，请限制在50个字符以内".to_string())
+    } else {
+        Ok(trimmed)
+    }
+}
+
+fn get_input(prompt: &str) -> Result<String, String> {
+    print!("{}", prompt);
+    if io::stdout().flush().is_err() {
+        return Err("输出缓冲区刷新失败".to_string());
+    }
+    
+    let input = read_input(&mut io::stdin().lock())?;
+    record_input_history(&input);
+    Ok(input)
+}
+
+// 跨会话保存的输入历史，最多保留的条数
+const MAX_HISTORY_ENTRIES: usize = 500;
+const DEFAULT_HISTORY_FILE: &str = ".scoreboard_history";
+
+// 输入历史：每行一条，保存在文件中供之后的会话查看
+struct InputHistory {
+    path: PathBuf,
+    entries: Vec<String>,
+}
+
+impl InputHistory {
+    // 读取历史文件，文件不存在时从空历史开始；超出上限时只保留最近的条目
+    fn load(path: PathBuf) -> Self {
+        let mut entries: Vec<String> = fs::read_to_string(&path)
+            .map(|content| content.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        let excess = entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        entries.drain(..excess);
+        InputHistory { path, entries }
+    }
+
+    // 记录一条非空输入并写回文件；与上一条相同的输入不重复记录
+    fn record(&mut self, input: &str) -> io::Result<()> {
+        if input.is_empty() || self.entries.last().is_some_and(|last| last == input) {
+            return Ok(());
+        }
+        self.entries.push(input.to_string());
+        let excess = self.entries.len().saturating_sub(MAX_HISTORY_ENTRIES);
+        self.entries.drain(..excess);
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        write_atomic(&self.path, &content)
+    }
+
+    // 最近的 count 条输入，按时间顺序排列
+    fn recent(&self, count: usize) -> &[String] {
+        &self.entries[self.entries.len().saturating_sub(count)..]
+    }
+}
+
+// 是否输出详细的内部状态变化（--verbose），默认关闭
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+// 详细模式下输出调试信息；消息按需生成，关闭时没有额外开销
+fn verbose_log<F: FnOnce() -> String>(message: F) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("[详细] {}", message());
+    }
+}
+
+// 当前会话的输入历史，未开启时为 None
+static INPUT_HISTORY: Mutex<Option<InputHistory>> = Mutex::new(None);
+
+fn record_input_history(input: &str) {
+    let Ok(mut history) = INPUT_HISTORY.lock() else {
+        return;
+    };
+    if let Some(current) = history.as_mut()
+        && current.record(input).is_err()
+    {
+        // 写入失败时停止记录，避免每次输入都报错
+        eprintln!("警告: 无法写入输入历史文件 '{}'，本次会话不再记录", current.path.display());
+        *history = None;
+    }
+}
+
+// 积分列的显示宽度：至少 6 个字符，积分文本更长时（正号、小数）随之加宽
+fn score_column_width<'a>(texts: impl Iterator<Item = &'a str>) -> usize {
+    texts.map(visible_width).max().unwrap_or(0).max(6)
+}
+
+// 以逗号分隔列出玩家序号，如 "1, 2, 5"
+fn join_ids(ids: &[PlayerId]) -> String {
+    ids.iter().map(PlayerId::to_string).collect::<Vec<_>>().join(", ")
+}
+
+// 胜出玩家序号的输入提示，列出实际存在的序号
+fn winner_prompt(scoreboard: &Scoreboard) -> String {
+    format!("请输入胜出玩家的序号（{}，直接回车取消）: ", join_ids(&scoreboard.existing_ids()))
+}
+
+// 录入胜者或参赛玩家时直接回车表示取消：返回菜单，不记录结果
+const CANCEL_NOTICE: &str = "已取消，未记录结果。";
+
+// 胜者序号的输入，空输入为 None（取消）
+fn parse_winner_input(input: &str) -> Result<Option<PlayerId>, String> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_player_id(input).map(Some)
+}
+
+// 参赛玩家名单的输入，空输入为 None（取消）
+fn parse_participants_input(input: &str) -> Result<Option<Vec<PlayerId>>, String> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_player_ids(input).map(Some)
+}
+
+// 玩家序号的上限，输入和导入的序号都不能超过
+const MAX_PLAYER_ID: usize = 1000;
+
+// 解析用户输入的玩家序号，只接受 1-MAX_PLAYER_ID 范围内的正整数
+// 序号前后的空白会被忽略，批量输入或程序调用时传入未去除空白的内容也能正确解析
+fn parse_player_id(input: &str) -> Result<PlayerId, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("输入不能为空！".to_string());
+    }
+
+    // 检查是否包含非数字字符
+    if !input.chars().all(|c| c.is_ascii_digit()) {
+        return Err("请输入有效的正整数！".to_string());
+    }
+
+    let id = input
+        .parse::<usize>()
+        .map_err(|_| "数字解析失败，请输入有效的数字！".to_string())?;
+
+    // 检查数字范围
+    if id == 0 {
+        return Err("玩家序号必须大于0！".to_string());
+    }
+
+    if id > MAX_PLAYER_ID {
+        return Err("玩家序号过大，请输入合理的序号！".to_string());
+    }
+
+    Ok(PlayerId(id))
+}
+
+// 解析以空格或逗号分隔的多个玩家序号
+fn parse_player_ids(input: &str) -> Result<Vec<PlayerId>, String> {
+    let ids = input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '，')
+        .filter(|part| !part.is_empty())
+        .map(parse_player_id)
+        .collect::<Result<Vec<_>, _>>()?;
+    if ids.is_empty() {
+        return Err("输入不能为空！".to_string());
+    }
+    Ok(ids)
+}
+
+fn get_input_safe(prompt: &str) -> String {
+    loop {
+        match get_input(prompt) {
+            Ok(input) => return input,
+            Err(e) => {
+                eprintln!("输入错误: {}", e);
+                eprintln!("请重试或按 Ctrl+C 退出程序。");
+                // 如果是EOF或严重错误，退出程序
+                if is_end_of_input(&e) {
+                    eprintln!("程序将退出。");
+                    release_session_lock();
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+// 输入错误是否表示输入已结束（EOF）或输入流已无法读取，此时重试没有意义
+fn is_end_of_input(error: &str) -> bool {
+    error.contains("EOF") || error.contains("输入流")
+}
+
+// 主菜单的输入：一行内容，或输入已结束（如标准输入为 /dev/null 或管道已关闭）
+#[derive(Debug, PartialEq)]
+enum MenuInput {
+    Line(String),
+    End,
+}
+
+fn read_menu_input<R: BufRead>(reader: &mut R) -> Result<MenuInput, String> {
+    match read_input(reader) {
+        Ok(line) => Ok(MenuInput::Line(line)),
+        Err(e) if is_end_of_input(&e) => Ok(MenuInput::End),
+        Err(e) => Err(e),
+    }
+}
+
+// 读取主菜单的选择。与 get_input_safe 不同，输入结束时不直接退出程序，而是交给调用方按正常退出处理
+fn get_menu_input(prompt: &str) -> MenuInput {
+    loop {
+        print!("{}", prompt);
+        let _ = io::stdout().flush();
+        match read_menu_input(&mut io::stdin().lock()) {
+            Ok(MenuInput::Line(line)) => {
+                record_input_history(&line);
+                return MenuInput::Line(line);
+            }
+            Ok(MenuInput::End) => return MenuInput::End,
+            Err(e) => {
+                eprintln!("输入错误: {}", e);
+                eprintln!("请重试或按 Ctrl+C 退出程序。");
+            }
+        }
+    }
+}
+
+// 退出命令的菜单键
+fn quit_key() -> &'static str {
+    MENU_COMMANDS
+        .iter()
+        .find(|command| command.action == MenuAction::Quit)
+        .map_or("4", |command| command.key)
+}
+
+// 单键菜单模式的提示，列出可以直接按下的快捷键
+const SINGLE_KEY_PROMPT: &str = "请按键选择（w 记录结果 / s 积分榜 / l 玩家列表 / q 退出 / h 帮助，其他命令输入数字后回车）: ";
+
+// 单键菜单模式：在终端原始模式下读取一个按键，快捷键（如 w、q）按下即生效，不需要回车；
+// 按下数字键时回显该数字，再按行读取剩余部分，以便输入 1-35 等多位的菜单序号。
+// 无法进入原始模式（如输入不是终端）时返回 None，由调用方改用按行输入。
+fn read_menu_key(prompt: &str) -> Option<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+    terminal::enable_raw_mode().ok()?;
+    let key = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => break Some(key),
+            Ok(_) => continue,
+            Err(_) => break None,
+        }
+    };
+    // 无论读取是否成功都要恢复终端，否则之后的输出会错乱
+    let _ = terminal::disable_raw_mode();
+    let key = key?;
+
+    match key.code {
+        // 原始模式下 Ctrl+C / Ctrl+D 不会产生信号，按退出处理（会正常保存）
+        KeyCode::Char('c' | 'd') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            println!();
+            MENU_COMMANDS
+                .iter()
+                .find(|command| command.action == MenuAction::Quit)
+                .map(|command| command.key.to_string())
+        }
+        KeyCode::Char(digit) if digit.is_ascii_digit() => {
+            print!("{}", digit);
+            io::stdout().flush().ok()?;
+            Some(format!("{}{}", digit, get_input_safe("")))
+        }
+        KeyCode::Char(key) => {
+            println!("{}", key);
+            Some(key.to_string())
+        }
+        _ => {
+            println!();
+            Some(String::new())
+        }
+    }
+}
+
+// 游戏循环中的菜单操作
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum MenuAction {
+    RecordWin,
+    ShowScoreboard,
+    ListPlayers,
+    Quit,
+    ShowHistory,
+    SplitPlayer,
+    Help,
+    ToggleTimer,
+    PinPlayer,
+    ExportCsv,
+    SwitchBoard,
+    NewBoard,
+    RenameBoard,
+    Save,
+    WithdrawPlayer,
+    TogglePractice,
+    ExportHistoryCsv,
+    RecordMatch,
+    Rematch,
+    ExportSvg,
+    UnlockRoster,
+    ShuffleOrder,
+    RenamePlayer,
+    Freeze,
+    ShowFrozen,
+    AddPlayer,
+    ShowInputHistory,
+    ShowGaps,
+    QueryScore,
+    RecordWeightedWin,
+    OpenReport,
+    ClearHistory,
+    ToggleRelativeToMean,
+    GenerateBracket,
+    RecomputeScores,
+    CompactIds,
+    ExportMatchups,
+    AdjustSecondary,
+    SetTitle,
+    ListPlayersSorted,
+    CopyStandings,
+    ShowRankGaps,
+    ShowPlayerRecord,
+    RecordBatch,
+    ShowHistogram,
+    SetTag,
+    ExportPng,
+}
+
+struct MenuCommand {
+    key: &'static str,
+    aliases: &'static [&'static str],
+    action: MenuAction,
+    // 只读模式下是否可用（仅查看和查询类命令可用）
+    read_only: bool,
+    label: &'static str,
+    description: &'static str,
+}
+
+// 菜单、命令分发与帮助信息均由此表生成，新增命令时只需在此登记
+const MENU_COMMANDS: &[MenuCommand] = &[
+    MenuCommand {
+        key: "1",
+        aliases: &["w"],
+        action: MenuAction::RecordWin,
+        read_only: false,
+        label: "记录游戏结果（输入胜出玩家序号）",
+        description: "输入本局胜出玩家的序号，按积分规则更新所有玩家的积分",
+    },
+    MenuCommand {
+        key: "2",
+        aliases: &["s"],
+        action: MenuAction::ShowScoreboard,
+        read_only: true,
+        label: "查看积分榜",
+        description: "按积分排名显示所有玩家的当前积分",
+    },
+    MenuCommand {
+        key: "3",
+        aliases: &["l"],
+        action: MenuAction::ListPlayers,
+        read_only: true,
+        label: "查看玩家列表",
+        description: "显示所有玩家的序号和名称",
+    },
+    MenuCommand {
+        key: "4",
+        aliases: &["q"],
+        action: MenuAction::Quit,
+        read_only: true,
+        label: "退出程序",
+        description: "结束程序运行",
+    },
+    MenuCommand {
+        key: "5",
+        aliases: &[],
+        action: MenuAction::ShowHistory,
+        read_only: true,
+        label: "查看对局记录",
+        description: "列出每局的局数、时间和胜出玩家，已删除的玩家显示为 (已删除)",
+    },
+    MenuCommand {
+        key: "6",
+        aliases: &[],
+        action: MenuAction::SplitPlayer,
+        read_only: false,
+        label: "拆分玩家",
+        description: "将一名玩家拆分为两人，并把指定积分转移给新玩家",
+    },
+    MenuCommand {
+        key: "7",
+        aliases: &["h", "?", "帮助"],
+        action: MenuAction::Help,
+        read_only: true,
+        label: "帮助",
+        description: "显示所有命令的说明以及当前生效的积分规则、限制和模式",
+    },
+    MenuCommand {
+        key: "8",
+        aliases: &[],
+        action: MenuAction::ToggleTimer,
+        read_only: false,
+        label: "暂停/继续计时",
+        description: "暂停或继续本局计时（需使用 --round-timer 开启计时），暂停期间不计入本局用时",
+    },
+    MenuCommand {
+        key: "9",
+        aliases: &[],
+        action: MenuAction::PinPlayer,
+        read_only: false,
+        label: "置顶/置底玩家",
+        description: "手动调整玩家在积分榜中的显示位置（正数置顶，负数置底，0 取消），不影响积分和名次",
+    },
+    MenuCommand {
+        key: "10",
+        aliases: &[],
+        action: MenuAction::ExportCsv,
+        read_only: false,
+        label: "导出积分榜为 CSV",
+        description: "将积分榜以 id,name,score 格式写入文件，可通过 --import-csv 重新导入",
+    },
+    MenuCommand {
+        key: "11",
+        aliases: &[],
+        action: MenuAction::SwitchBoard,
+        read_only: true,
+        label: "切换积分板",
+        description: "列出本次会话中的所有积分板，并切换当前操作的积分板",
+    },
+    MenuCommand {
+        key: "12",
+        aliases: &[],
+        action: MenuAction::NewBoard,
+        read_only: false,
+        label: "新建积分板",
+        description: "新建一个独立的积分板并录入玩家，新建后自动切换到该积分板",
+    },
+    MenuCommand {
+        key: "13",
+        aliases: &[],
+        action: MenuAction::RenameBoard,
+        read_only: false,
+        label: "重命名积分板",
+        description: "修改当前积分板的名称",
+    },
+    MenuCommand {
+        key: "14",
+        aliases: &[],
+        action: MenuAction::Save,
+        read_only: false,
+        label: "保存",
+        description: "将所有积分板保存到 --file 指定的文件（退出时也会自动保存）",
+    },
+    MenuCommand {
+        key: "15",
+        aliases: &[],
+        action: MenuAction::WithdrawPlayer,
+        read_only: false,
+        label: "玩家退出/恢复",
+        description: "标记玩家中途退出（保留最终积分，之后不再扣分），或恢复已退出的玩家",
+    },
+    MenuCommand {
+        key: "16",
+        aliases: &[],
+        action: MenuAction::TogglePractice,
+        read_only: false,
+        label: "进入/退出练习模式",
+        description: "练习模式下的操作只作用于当前积分板的临时副本，不会被保存；退出后恢复原积分板",
+    },
+    MenuCommand {
+        key: "17",
+        aliases: &[],
+        action: MenuAction::ExportHistoryCsv,
+        read_only: false,
+        label: "导出对局记录为 CSV",
+        description: "将每局的局数、时间、胜者序号和名称以 CSV 格式写入文件，便于在电子表格中分析",
+    },
+    MenuCommand {
+        key: "18",
+        aliases: &[],
+        action: MenuAction::RecordMatch,
+        read_only: false,
+        label: "记录对战结果（指定参赛玩家）",
+        description: "先输入本局参赛玩家的序号（如两人对战），再输入胜出玩家，只有参赛玩家的积分会变化",
+    },
+    MenuCommand {
+        key: "19",
+        aliases: &["r"],
+        action: MenuAction::Rematch,
+        read_only: false,
+        label: "再战上一组",
+        description: "沿用上一局的参赛玩家，只需输入本局胜出玩家",
+    },
+    MenuCommand {
+        key: "20",
+        aliases: &[],
+        action: MenuAction::ExportSvg,
+        read_only: false,
+        label: "导出积分走势图 (SVG)",
+        description: "将每位玩家每局结束后的积分绘制成折线图并写入 SVG 文件，可用浏览器打开",
+    },
+    MenuCommand {
+        key: "21",
+        aliases: &[],
+        action: MenuAction::UnlockRoster,
+        read_only: false,
+        label: "解锁玩家名单",
+        description: "开启名单锁定（--lock-roster）后，比赛开始即不能添加玩家；解锁后可继续添加",
+    },
+    MenuCommand {
+        key: "22",
+        aliases: &[],
+        action: MenuAction::ShuffleOrder,
+        read_only: true,
+        label: "随机排序",
+        description: "随机生成未退出玩家的出场顺序并显示所用种子；输入相同种子可复现同一顺序，不影响积分",
+    },
+    MenuCommand {
+        key: "23",
+        aliases: &[],
+        action: MenuAction::RenamePlayer,
+        read_only: false,
+        label: "按名称修改玩家名",
+        description: "输入原名称（不区分大小写）和新名称，无需知道玩家序号即可更正名称",
+    },
+    MenuCommand {
+        key: "24",
+        aliases: &[],
+        action: MenuAction::Freeze,
+        read_only: false,
+        label: "冻结积分",
+        description: "记录当前积分并加上标签（如\"中场休息\"），之后可与实时积分对比；再次冻结会替换之前的快照",
+    },
+    MenuCommand {
+        key: "25",
+        aliases: &[],
+        action: MenuAction::ShowFrozen,
+        read_only: true,
+        label: "对比冻结积分",
+        description: "并列显示每位玩家冻结时的积分、当前积分和变化",
+    },
+    MenuCommand {
+        key: "26",
+        aliases: &[],
+        action: MenuAction::AddPlayer,
+        read_only: false,
+        label: "添加玩家",
+        description: "在游戏中途录入迟到的玩家，输入 'done' 结束（与开局录入相同）；新玩家积分从 0 开始，名单锁定时不可用",
+    },
+    MenuCommand {
+        key: "27",
+        aliases: &[],
+        action: MenuAction::QueryScore,
+        read_only: true,
+        label: "查询玩家积分",
+        description: "输入玩家名称（不区分大小写），只显示该玩家的当前积分和名次",
+    },
+    MenuCommand {
+        key: "28",
+        aliases: &[],
+        action: MenuAction::RecordWeightedWin,
+        read_only: false,
+        label: "记录加权胜局",
+        description: "用于决赛等重要对局：输入胜出玩家和本局权重，本局扣分和胜者所得都乘以权重",
+    },
+    MenuCommand {
+        key: "29",
+        aliases: &[],
+        action: MenuAction::ShowInputHistory,
+        read_only: true,
+        label: "查看输入历史",
+        description: "显示本次及之前会话中最近输入的内容（玩家名称、命令等），可用 --no-history 关闭记录",
+    },
+    MenuCommand {
+        key: "30",
+        aliases: &[],
+        action: MenuAction::ShowGaps,
+        read_only: true,
+        label: "与第一名的差距",
+        description: "列出每位非领先玩家还差多少分追上第一名，并列第一的玩家差距均为 0",
+    },
+    MenuCommand {
+        key: "31",
+        aliases: &[],
+        action: MenuAction::OpenReport,
+        read_only: true,
+        label: "导出并打开报告",
+        description: "把比赛报告写入临时文件并用系统默认程序打开；没有图形界面时只显示文件路径",
+    },
+    MenuCommand {
+        key: "32",
+        aliases: &[],
+        action: MenuAction::ClearHistory,
+        read_only: false,
+        label: "清空对局记录",
+        description: "删除所有对局记录以减小存档，保留当前积分、玩家、局数和胜场；清空后无法再查看之前的对局",
+    },
+    MenuCommand {
+        key: "33",
+        aliases: &[],
+        action: MenuAction::ToggleRelativeToMean,
+        read_only: true,
+        label: "切换相对平均分显示",
+        description: "在积分榜中显示或隐藏每位玩家的积分与平均积分之差，便于查看谁高于或低于平均水平",
+    },
+    MenuCommand {
+        key: "34",
+        aliases: &[],
+        action: MenuAction::GenerateBracket,
+        read_only: true,
+        label: "生成淘汰赛对阵",
+        description: "按当前排名取前 N 名玩家作为种子，生成单败淘汰赛第一轮对阵（人数不是 2 的幂时高位种子轮空）",
+    },
+    MenuCommand {
+        key: "35",
+        aliases: &[],
+        action: MenuAction::RecomputeScores,
+        read_only: false,
+        label: "按对局记录重算积分",
+        description: "把积分清零后按对局记录逐局重新结算，列出与当前积分的差异，确认后替换当前积分",
+    },
+    MenuCommand {
+        key: "36",
+        aliases: &[],
+        action: MenuAction::CompactIds,
+        read_only: false,
+        label: "整理玩家序号",
+        description: "把不连续的玩家序号重新编为 1、2、3……（保持原有顺序），积分和对局记录同步更新",
+    },
+    MenuCommand {
+        key: "37",
+        aliases: &[],
+        action: MenuAction::ExportMatchups,
+        read_only: false,
+        label: "导出对阵矩阵",
+        description: "导出每位玩家对每位对手的获胜局数，文件名以 .md 结尾时导出为 Markdown 表格，否则导出为 CSV",
+    },
+    MenuCommand {
+        key: "38",
+        aliases: &[],
+        action: MenuAction::AdjustSecondary,
+        read_only: false,
+        label: "调整副积分",
+        description: "单独增减某位玩家的副积分（需用 --secondary 启用），不影响主积分和排名",
+    },
+    MenuCommand {
+        key: "39",
+        aliases: &[],
+        action: MenuAction::SetTitle,
+        read_only: false,
+        label: "设置比赛标题",
+        description: "设置或清除比赛标题，标题显示在积分榜上方，并写入比赛报告和导出文件",
+    },
+    MenuCommand {
+        key: "40",
+        aliases: &[],
+        action: MenuAction::ListPlayersSorted,
+        read_only: true,
+        label: "按名称/积分列出玩家",
+        description: "按序号、名称或积分排列玩家列表，便于在人数较多时查找某位玩家",
+    },
+    MenuCommand {
+        key: "41",
+        aliases: &[],
+        action: MenuAction::CopyStandings,
+        read_only: true,
+        label: "复制积分榜到剪贴板",
+        description: "把积分榜以纯文本或 Markdown 表格复制到系统剪贴板，便于粘贴到聊天软件；无法访问剪贴板时直接输出",
+    },
+    MenuCommand {
+        key: "42",
+        aliases: &[],
+        action: MenuAction::ShowRankGaps,
+        read_only: true,
+        label: "相邻名次的分差",
+        description: "列出排名相邻的每两位玩家之间的分差，看出哪里的名次争夺最激烈",
+    },
+    MenuCommand {
+        key: "43",
+        aliases: &[],
+        action: MenuAction::ShowPlayerRecord,
+        read_only: true,
+        label: "玩家战绩",
+        description: "查看一名玩家的积分、名次、胜负局数和连胜",
+    },
+    MenuCommand {
+        key: "44",
+        aliases: &[],
+        action: MenuAction::RecordBatch,
+        read_only: false,
+        label: "批量记录结果",
+        description: "粘贴多个胜者序号（空格、逗号或换行分隔），按顺序每个记录一局，适合补录线下对局",
+    },
+    MenuCommand {
+        key: "45",
+        aliases: &[],
+        action: MenuAction::ShowHistogram,
+        read_only: true,
+        label: "积分分布",
+        description: "按积分区间统计玩家人数并画出文字直方图，一眼看出积分的分布情况",
+    },
+    MenuCommand {
+        key: "46",
+        aliases: &[],
+        action: MenuAction::SetTag,
+        read_only: false,
+        label: "设置玩家标记",
+        description: "为玩家设置显示在名称前的短标记（如卫冕冠军的 👑），只影响显示",
+    },
+    MenuCommand {
+        key: "47",
+        aliases: &[],
+        action: MenuAction::ExportPng,
+        read_only: false,
+        label: "导出积分榜图片 (PNG)",
+        description: "把积分榜画成表格图片，便于在不支持文本表格的地方分享（需启用 png 功能编译）",
+    },
+];
+
+fn find_command(input: &str) -> Option<&'static MenuCommand> {
+    let input = input.to_lowercase();
+    MENU_COMMANDS
+        .iter()
+        .find(|command| command.key == input || command.aliases.contains(&input.as_str()))
+}
+
+fn print_menu(workspace: &Workspace, read_only: bool) {
+    if read_only {
+        println!("【只读模式】仅可查看积分，无法修改");
+    }
+    if workspace.in_practice() {
+        println!("【练习模式】当前操作不会影响正式积分，也不会被保存");
+    }
+    if workspace.active_board().roster_locked() {
+        println!("【名单已锁定】比赛已开始，无法添加玩家");
+    }
+    if let Some(deadline) = workspace.active_board().session_deadline {
+        if workspace.active_board().session_over {
+            println!("【限时赛已结束】不再记录新的对局");
+        } else {
+            let remaining = deadline.saturating_sub(current_timestamp());
+            println!(
+                "【限时赛】结束时间: {}（剩余 {} 分钟）",
+                format_timestamp(deadline),
+                remaining.div_ceil(60)
+            );
+        }
+    }
+    let board = workspace.active_board();
+    if board.elimination {
+        match board.elimination_survivor() {
+            Some(survivor) => println!("【逐轮淘汰已结束】最终胜者: {}", board.players[&survivor]),
+            None => println!("【逐轮淘汰】每局结束后排名最后的玩家被淘汰（剩余 {} 人）", board.active_ids().len()),
+        }
+    }
+    if workspace.boards.len() > 1 {
+        println!(
+            "当前积分板: {} ({}/{})",
+            workspace.active_name(),
+            workspace.active + 1,
+            workspace.boards.len()
+        );
+    }
+    println!("请选择操作:");
+    for command in MENU_COMMANDS.iter().filter(|c| c.read_only || !read_only) {
+        println!("{}. {}", command.key, command.label);
+    }
+}
+
+fn print_help(scoreboard: &Scoreboard, read_only: bool, practice: bool) {
+    print!("{}", help_text(scoreboard, read_only, practice));
+}
+
+// 帮助内容：命令列表来自 MENU_COMMANDS，积分规则和模式来自积分板本身的设置汇总，新增命令或模式时自动同步
+fn help_text(scoreboard: &Scoreboard, read_only: bool, practice: bool) -> String {
+    let mut text = String::from("\n=== 帮助 ===\n命令:\n");
+    for command in MENU_COMMANDS.iter().filter(|c| c.read_only || !read_only) {
+        let mut keys = vec![command.key];
+        keys.extend_from_slice(command.aliases);
+        text.push_str(&format!("  {:<12} {}\n", keys.join("/"), command.label));
+        text.push_str(&format!("  {:<12} {}\n", "", command.description));
+    }
+
+    text.push_str("积分规则:\n");
+    for line in scoreboard.scoring_config().describe() {
+        text.push_str(&format!("  {}\n", line));
+    }
+
+    text.push_str("限制:\n");
+    text.push_str("  玩家名称最多 20 个字符，不能包含控制字符，不能重复\n");
+    text.push_str("  单次输入最多 50 个字符，玩家序号范围 1-1000\n");
+
+    text.push_str("模式:\n");
+    if read_only {
+        text.push_str("  只读模式: 开启（仅可查看，修改类命令已隐藏）\n");
+    }
+    if practice {
+        text.push_str("  练习模式: 开启（当前操作不会影响正式积分，也不会被保存）\n");
+    }
+    for line in scoreboard.mode_summary() {
+        text.push_str(&format!("  {}\n", line));
+    }
+    text.push('\n');
+    text
+}
+
+fn run_adjust_secondary(scoreboard: &mut Scoreboard) {
+    let Some(label) = scoreboard.secondary.as_ref().map(|secondary| secondary.label.clone()) else {
+        println!("未启用副积分，请使用 --secondary <名称> 启动程序。");
+        return;
+    };
+    scoreboard.list_players();
+    let id = match parse_player_id(&get_input_safe("请输入玩家序号: ")) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let delta = match get_input_safe(&format!("请输入{}变化（如 5 或 -3）: ", label)).parse::<i32>() {
+        Ok(value) => value,
+        Err(_) => {
+            println!("请输入有效的整数！");
+            return;
+        }
+    };
+
+    match scoreboard.adjust_secondary(id, delta) {
+        Ok(updated) => {
+            println!("玩家 '{}' 的{}变为 {}", scoreboard.players[&id], label, updated);
+            scoreboard.display_after_action();
+        }
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_elimination_round(scoreboard: &mut Scoreboard, winner_id: PlayerId) {
+    match scoreboard.elimination_round(winner_id) {
+        Ok(None) => println!("错误: {}，不再记录对局", scoreboard.elimination_over_error()),
+        Ok(Some(eliminated)) => {
+            println!("积分已更新！");
+            println!("玩家 '{}' 被淘汰。", scoreboard.players[&eliminated]);
+            if let Some(survivor) = scoreboard.elimination_survivor() {
+                println!("逐轮淘汰结束，最终胜者: {}！", scoreboard.players[&survivor]);
+            }
+            scoreboard.display_after_action();
+        }
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_record_batch(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    println!(
+        "请粘贴各局胜者的序号，用空格、逗号或换行分隔，每个序号记录一局；输入空行或 {} 结束。",
+        DONE_KEYWORD
+    );
+    let mut input = String::new();
+    loop {
+        let line = get_input_safe("> ");
+        if line.is_empty() || is_done_keyword(&line) {
+            break;
+        }
+        input.push_str(&line);
+        input.push('\n');
+    }
+    let summary = scoreboard.record_wins_batch(&input);
+    println!("已记录 {} 局，跳过 {} 项。", summary.added, summary.skipped.len());
+    for (entry, reason) in &summary.skipped {
+        println!("  第 {} 项 {}", entry, reason);
+    }
+    if let Some(survivor) = scoreboard.elimination_survivor() {
+        println!("逐轮淘汰结束，最终胜者: {}！", scoreboard.players[&survivor]);
+    }
+    if summary.added > 0 {
+        scoreboard.display_after_action();
+    }
+}
+
+fn run_set_tag(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    let id = match parse_player_id(&get_input_safe("请输入玩家序号: ")) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let tag = get_input_safe("请输入标记（如 👑，直接回车清除标记）: ");
+    match scoreboard.set_tag(id, &tag) {
+        Ok(()) if tag.is_empty() => println!("已清除标记。"),
+        Ok(()) => println!("标记已设置。"),
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_show_histogram(scoreboard: &Scoreboard) {
+    let input = get_input_safe("请输入每组的分数区间大小（正整数，直接回车为 5）: ");
+    let bucket_size = if input.is_empty() {
+        5
+    } else {
+        match input.parse::<i32>() {
+            Ok(size) if size > 0 => size,
+            _ => {
+                println!("无效的区间大小: {}（应为正整数）", input);
+                return;
+            }
+        }
+    };
+    scoreboard.display_score_histogram(bucket_size);
+}
+
+fn run_show_player_record(scoreboard: &Scoreboard) {
+    scoreboard.list_players();
+    let id = match parse_player_id(&get_input_safe("请输入玩家序号: ")) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let Some(record) = scoreboard.player_record(id) else {
+        println!("错误: {}", scoreboard.unknown_player_error(id));
+        return;
+    };
+    println!("\n=== {} 的战绩 ===", record.name);
+    if record.withdrawn {
+        println!("状态: 已退出");
+    }
+    println!("积分: {}", scoreboard.score_text(record.score));
+    println!("名次: 第{}名", record.rank);
+    println!("胜局: {}", record.wins);
+    println!("负局: {}", record.losses);
+    println!("当前连胜: {}", record.current_streak);
+    println!("最长连胜: {}", record.best_streak);
+    println!();
+}
+
+fn run_pin_player(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    let id = match parse_player_id(&get_input_safe("请输入玩家序号: ")) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let priority = match get_input_safe("请输入显示优先级（正数置顶，负数置底，0 取消）: ").parse::<i32>() {
+        Ok(value) => value,
+        Err(_) => {
+            println!("请输入有效的整数！");
+            return;
+        }
+    };
+
+    let result = if priority == 0 {
+        scoreboard.unpin_player(id)
+    } else {
+        scoreboard.pin_player(id, priority)
+    };
+    match result {
+        Ok(()) => scoreboard.display_after_action(),
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+// 创建文件并写入导出内容
+fn write_export_file<F>(path: &str, export: F) -> io::Result<()>
+where
+    F: FnOnce(&mut io::BufWriter<fs::File>) -> io::Result<()>,
+{
+    let mut writer = io::BufWriter::new(fs::File::create(path)?);
+    export(&mut writer)?;
+    writer.flush()
+}
+
+// 导出时使用的积分板：指定 --anonymize 时为隐去真实姓名的副本
+fn export_board(scoreboard: &Scoreboard, anonymize: bool) -> Cow<'_, Scoreboard> {
+    if anonymize {
+        Cow::Owned(scoreboard.anonymized())
+    } else {
+        Cow::Borrowed(scoreboard)
+    }
+}
+
+// 提示输入导出文件路径并写入导出内容
+fn run_export<F>(what: &str, export: F)
+where
+    F: FnOnce(&mut io::BufWriter<fs::File>) -> io::Result<()>,
+{
+    let path = get_input_safe("请输入导出文件路径: ");
+    if path.is_empty() {
+        println!("文件路径不能为空！");
+        return;
+    }
+    match write_export_file(&path, export) {
+        Ok(()) => println!("{}已导出到 '{}'", what, path),
+        Err(e) => println!("导出失败: {}", e),
+    }
+}
+
+// 导出积分榜图片，字体中缺少的字符画成方框并给出提示
+#[cfg(feature = "png")]
+fn run_export_png(scoreboard: &Scoreboard) {
+    let path = get_input_safe("请输入导出文件路径: ");
+    if path.is_empty() {
+        println!("文件路径不能为空！");
+        return;
+    }
+    let mut missing = Vec::new();
+    match write_export_file(&path, |writer| {
+        missing = scoreboard.export_png(writer)?;
+        Ok(())
+    }) {
+        Ok(()) => {
+            println!("积分榜图片已导出到 '{}'", path);
+            if !missing.is_empty() {
+                let chars: String = missing.iter().take(20).collect();
+                let more = if missing.len() > 20 { "……" } else { "" };
+                println!(
+                    "警告: 图片内置字体只包含英文字母、数字和常用符号，以下字符已显示为方框: {}{}",
+                    chars, more
+                );
+            }
+        }
+        Err(e) => println!("导出失败: {}", e),
+    }
+}
+
+#[cfg(not(feature = "png"))]
+fn run_export_png(_scoreboard: &Scoreboard) {
+    println!("此版本未包含 PNG 导出功能，请使用 cargo build --features png 重新编译");
+}
+
+// PNG 图片的调色板：背景、文字和线条、表头底色、隔行底色
+#[cfg(feature = "png")]
+const PNG_PALETTE: [[u8; 3]; 4] = [[255, 255, 255], [32, 32, 32], [200, 210, 225], [238, 242, 248]];
+#[cfg(feature = "png")]
+const PNG_BACKGROUND: u8 = 0;
+#[cfg(feature = "png")]
+const PNG_INK: u8 = 1;
+#[cfg(feature = "png")]
+const PNG_HEADER: u8 = 2;
+#[cfg(feature = "png")]
+const PNG_STRIPE: u8 = 3;
+// 点阵字体每个字形 5×7 点，每点放大为 PNG_SCALE×PNG_SCALE 像素，字符间留 1 点空隙
+#[cfg(feature = "png")]
+const PNG_GLYPH_WIDTH: usize = 5;
+#[cfg(feature = "png")]
+const PNG_GLYPH_HEIGHT: usize = 7;
+#[cfg(feature = "png")]
+const PNG_SCALE: usize = 2;
+#[cfg(feature = "png")]
+const PNG_CELL_WIDTH: usize = (PNG_GLYPH_WIDTH + 1) * PNG_SCALE;
+#[cfg(feature = "png")]
+const PNG_ROW_HEIGHT: usize = PNG_GLYPH_HEIGHT * PNG_SCALE + 10;
+#[cfg(feature = "png")]
+const PNG_MARGIN: usize = 12;
+// 表格列之间空出的字符宽度
+#[cfg(feature = "png")]
+const PNG_COLUMN_GAP: usize = 2;
+
+// 可打印 ASCII 字符（空格到 ~）的 5×7 点阵字形，每个字节是一列，最低位在最上方
+#[cfg(feature = "png")]
+const PNG_FONT: [[u8; PNG_GLYPH_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+// 使用调色板的 8 位 PNG 画布，每个像素存放调色板下标
+#[cfg(feature = "png")]
+struct PngCanvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+#[cfg(feature = "png")]
+impl PngCanvas {
+    fn new(width: usize, height: usize) -> Self {
+        PngCanvas { width, height, pixels: vec![PNG_BACKGROUND; width * height] }
+    }
+
+    // 填充矩形，超出画布的部分被裁掉
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: u8) {
+        for row in y..(y + height).min(self.height) {
+            let start = row * self.width;
+            let end = start + (x + width).min(self.width);
+            if start + x < end {
+                self.pixels[start + x..end].fill(color);
+            }
+        }
+    }
+
+    // 从 (x, y) 开始逐字绘制文字，宽字符占两个字符宽度。
+    // 字体中没有的字符画成方框，并记入 missing（不重复）
+    fn draw_text(&mut self, x: usize, y: usize, text: &str, missing: &mut Vec<char>) {
+        use unicode_width::UnicodeWidthChar;
+
+        let mut left = x;
+        for c in text.chars() {
+            let cells = c.width().unwrap_or(0);
+            if cells == 0 {
+                continue;
+            }
+            match (c as usize).checked_sub(0x20).and_then(|index| PNG_FONT.get(index)) {
+                Some(glyph) => {
+                    for (column, bits) in glyph.iter().enumerate() {
+                        for row in 0..PNG_GLYPH_HEIGHT {
+                            if bits >> row & 1 == 1 {
+                                self.fill_rect(
+                                    left + column * PNG_SCALE,
+                                    y + row * PNG_SCALE,
+                                    PNG_SCALE,
+                                    PNG_SCALE,
+                                    PNG_INK,
+                                );
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let box_width = cells * PNG_CELL_WIDTH - PNG_SCALE;
+                    let box_height = PNG_GLYPH_HEIGHT * PNG_SCALE;
+                    self.fill_rect(left, y, box_width, 1, PNG_INK);
+                    self.fill_rect(left, y + box_height - 1, box_width, 1, PNG_INK);
+                    self.fill_rect(left, y, 1, box_height, PNG_INK);
+                    self.fill_rect(left + box_width - 1, y, 1, box_height, PNG_INK);
+                    if !missing.contains(&c) {
+                        missing.push(c);
+                    }
+                }
+            }
+            left += cells * PNG_CELL_WIDTH;
+        }
+    }
+
+    // 按 PNG 格式写出：IHDR、PLTE、IDAT（不压缩的 zlib 数据流）和 IEND。
+    // 图片只有几种颜色且尺寸不大，不压缩可以省去压缩库依赖
+    fn write_png<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "图片尺寸过大");
+        let width = u32::try_from(self.width).map_err(|_| too_large())?;
+        let height = u32::try_from(self.height).map_err(|_| too_large())?;
+
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        // 位深 8、颜色类型 3（调色板）、默认压缩和过滤方式、不隔行
+        header.extend_from_slice(&[8, 3, 0, 0, 0]);
+        write_png_chunk(&mut writer, b"IHDR", &header)?;
+        write_png_chunk(&mut writer, b"PLTE", PNG_PALETTE.as_flattened())?;
+
+        // 每行前加过滤类型 0（不过滤）
+        let mut raw = Vec::with_capacity(self.height * (self.width + 1));
+        for row in self.pixels.chunks(self.width) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        let mut data = vec![0x78, 0x01];
+        let blocks = raw.chunks(u16::MAX as usize);
+        let block_count = blocks.len();
+        for (index, block) in blocks.enumerate() {
+            let len = block.len() as u16;
+            data.push(u8::from(index + 1 == block_count));
+            data.extend_from_slice(&len.to_le_bytes());
+            data.extend_from_slice(&(!len).to_le_bytes());
+            data.extend_from_slice(block);
+        }
+        data.extend_from_slice(&adler32(&raw).to_be_bytes());
+        write_png_chunk(&mut writer, b"IDAT", &data)?;
+        write_png_chunk(&mut writer, b"IEND", &[])
+    }
+}
+
+// 写出一个 PNG 数据块：长度、类型、内容和对类型与内容计算的 CRC32
+#[cfg(feature = "png")]
+fn write_png_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "图片尺寸过大"))?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc32(kind.iter().chain(data)).to_be_bytes())
+}
+
+#[cfg(feature = "png")]
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(feature = "png")]
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// 导出对阵矩阵，按文件扩展名选择 Markdown 或 CSV 格式
+fn run_export_matchups(scoreboard: &Scoreboard) {
+    let path = get_input_safe("请输入导出文件路径（.md 为 Markdown，其他为 CSV）: ");
+    if path.is_empty() {
+        println!("文件路径不能为空！");
+        return;
+    }
+    let markdown = Path::new(&path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+    let result = write_export_file(&path, |writer| {
+        if markdown {
+            scoreboard.export_matchups_markdown(writer)
+        } else {
+            scoreboard.export_matchups_csv(writer)
+        }
+    });
+    match result {
+        Ok(()) => println!("对阵矩阵已导出到 '{}'", path),
+        Err(e) => println!("导出失败: {}", e),
+    }
+}
+
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    if !desktop_available() {
+        return Err("未检测到图形界面".to_string());
+    }
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+    clipboard.set_text(text).map_err(|e| e.to_string())
+}
+
+// 把积分榜复制到系统剪贴板；没有图形界面或无法访问剪贴板时改为直接输出，供手动复制
+fn run_copy_standings(scoreboard: &Scoreboard, format: StandingsFormat) {
+    let text = scoreboard.standings_text(format);
+    match copy_to_clipboard(&text) {
+        Ok(()) => println!("积分榜已复制到剪贴板。"),
+        Err(e) => {
+            println!("提示: 无法访问剪贴板（{}），请手动复制以下内容:\n", e);
+            print!("{}", text);
+        }
+    }
+}
+
+// 把比赛报告写入系统临时目录，文件名带时间戳，不会覆盖之前导出的报告
+fn write_report_file(scoreboard: &Scoreboard) -> io::Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!(
+        "scoreboard_report_{}_{}.txt",
+        current_timestamp(),
+        std::process::id()
+    ));
+    fs::write(&path, scoreboard.generate_report())?;
+    Ok(path)
+}
+
+// 是否有图形界面可以打开文件；Linux 等系统上没有 DISPLAY / WAYLAND_DISPLAY 时视为无界面（如 SSH 登录）
+fn desktop_available() -> bool {
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return true;
+    }
+    std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+// 用系统默认程序打开文件
+fn open_with_default_app(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    let status = command
+        .arg(path)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("打开程序退出状态为 {}", status)));
+    }
+    Ok(())
+}
+
+fn run_open_report(scoreboard: &Scoreboard) {
+    let path = match write_report_file(scoreboard) {
+        Ok(path) => path,
+        Err(e) => {
+            println!("导出失败: {}", e);
+            return;
+        }
+    };
+    println!("比赛报告已导出到 '{}'", path.display());
+    if !desktop_available() {
+        println!("未检测到图形界面，请手动打开上述文件。");
+        return;
+    }
+    match open_with_default_app(&path) {
+        Ok(()) => println!("已用默认程序打开报告。"),
+        Err(e) => println!("无法自动打开报告（{}），请手动打开上述文件。", e),
+    }
+}
+
+// 在给定的参赛玩家中提示输入胜者并记录本局
+fn record_match_among(scoreboard: &mut Scoreboard, participants: &[PlayerId]) {
+    println!("\n=== 参赛玩家 ===");
+    for id in participants {
+        let name = scoreboard.players.get(id).map_or("(已删除)", String::as_str);
+        println!("{}: {}", id, name);
+    }
+    let winner_id = match parse_winner_input(&get_input_safe("请输入胜出玩家的序号（直接回车取消）: ")) {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            println!("{}", CANCEL_NOTICE);
+            return;
+        }
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    if !confirm_winner(scoreboard, winner_id, get_input_safe) {
+        return;
+    }
+    match scoreboard.update_scores_among(winner_id, participants) {
+        Ok(()) => {
+            println!("积分已更新！");
+            scoreboard.display_after_action();
+        }
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_record_weighted_win(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    let winner_id = match parse_winner_input(&get_input_safe(&winner_prompt(scoreboard))) {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            println!("{}", CANCEL_NOTICE);
+            return;
+        }
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    if !confirm_winner(scoreboard, winner_id, get_input_safe) {
+        return;
+    }
+    let input = get_input_safe(&format!("请输入本局权重（1-{} 的整数，直接回车为 1）: ", MAX_WEIGHT));
+    let weight = if input.is_empty() {
+        1
+    } else {
+        match input.parse::<u32>() {
+            Ok(weight) if (1..=MAX_WEIGHT).contains(&weight) => weight,
+            _ => {
+                println!("无效的权重: {}（应为 1 到 {} 的整数）", input, MAX_WEIGHT);
+                return;
+            }
+        }
+    };
+    match scoreboard.update_scores_weighted(winner_id, weight) {
+        Ok(()) => {
+            println!("积分已更新（本局权重 x{}）！", weight);
+            scoreboard.display_after_action();
+        }
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_record_match(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    match parse_participants_input(&get_input_safe("请输入参赛玩家的序号（以空格或逗号分隔，直接回车取消）: ")) {
+        Ok(Some(participants)) => record_match_among(scoreboard, &participants),
+        Ok(None) => println!("{}", CANCEL_NOTICE),
+        Err(e) => println!("{}", e),
+    }
+}
+
+fn run_rematch(scoreboard: &mut Scoreboard) {
+    match scoreboard.last_participants() {
+        Some(participants) => record_match_among(scoreboard, &participants),
+        None => println!("暂无上一局的参赛记录。"),
+    }
+}
+
+fn run_withdraw_player(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    let id = match parse_player_id(&get_input_safe("请输入要退出或恢复的玩家序号: ")) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let result = if scoreboard.is_withdrawn(id) {
+        scoreboard.reinstate_player(id).map(|()| "已恢复")
+    } else {
+        scoreboard.withdraw_player(id).map(|()| "已退出")
+    };
+    match result {
+        Ok(status) => println!("玩家 '{}' {}", scoreboard.players[&id], status),
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_split_player(scoreboard: &mut Scoreboard) {
+    scoreboard.list_players();
+    let id = match parse_player_id(&get_input_safe("请输入要拆分的玩家序号: ")) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let new_name = get_input_safe("请输入新玩家名称: ");
+    let transfer = match get_input_safe("请输入转移给新玩家的积分: ").parse::<i32>() {
+        Ok(value) => value,
+        Err(_) => {
+            println!("请输入有效的整数！");
+            return;
+        }
+    };
+
+    match scoreboard.split_player(id, new_name.clone(), transfer) {
+        Ok(new_id) => {
+            println!("已拆分出新玩家 '{}'，序号为: {}", new_name, new_id);
+            scoreboard.display_after_action();
+        }
+        Err(e) => println!("拆分失败: {}", e),
+    }
+}
+
+// 命令行参数
+#[derive(Default)]
+struct CliOptions {
+    file: Option<String>,
+    import_csv: Option<String>,
+    import_kv: Option<String>,
+    // 可以多次指定，按顺序依次导入
+    import_players: Vec<String>,
+    min_score: Option<i32>,
+    reject_numeric_names: bool,
+    round_timer: bool,
+    tie_break: Option<TieBreak>,
+    overlay_file: Option<String>,
+    practice: bool,
+    streak_bonus: Option<StreakBonus>,
+    export_history: Option<String>,
+    export_svg: Option<String>,
+    accent_insensitive_names: bool,
+    read_only: bool,
+    loser_delta: Option<i32>,
+    rank_penalties: Option<Vec<i32>>,
+    strict: bool,
+    score_precision: Option<usize>,
+    lock_roster: bool,
+    table_style: Option<TableStyle>,
+    // 跳过会丢失数据的操作前的确认，便于脚本使用
+    yes: bool,
+    color: ColorChoice,
+    history_file: Option<String>,
+    no_history: bool,
+    autosave_interval: Option<usize>,
+    rounding_mode: Option<RoundingMode>,
+    positive_sign: Option<PositiveSign>,
+    target_score: Option<i32>,
+    stop_at_target: bool,
+    end_at: Option<ClockTime>,
+    title: Option<String>,
+    // 导出文件中用 玩家A、玩家B…… 代替真实姓名
+    anonymize: bool,
+    // 导出文件中注明行的排列方式
+    sort_note: bool,
+    elimination: bool,
+    repeat_window: Option<u64>,
+    // 把当前积分板的积分榜复制到剪贴板后退出
+    copy: Option<StandingsFormat>,
+    // 启动时随机模拟的局数和随机种子（隐藏参数，用于生成演示数据）
+    simulate: Option<usize>,
+    seed: Option<u64>,
+    // 以 JSON 输出 --file 中当前积分板的序号与名称对应关系后退出
+    dump_ids: bool,
+    // 计算赛季累计积分榜后退出：赛季文件或比赛日存档所在的目录，以及可选的赛季文件保存位置
+    season: Option<String>,
+    save_season: Option<String>,
+    // 列出赛季玩家与各比赛日玩家的对应关系
+    season_mapping: bool,
+    // 观看模式：定时重新读取 --file 并刷新积分榜，不进入菜单
+    watch: bool,
+    watch_interval: Option<u64>,
+    // 输出示例存档后退出（隐藏参数，供编写或校验存档的工具使用）
+    print_schema: bool,
+    // 副积分列的名称和每局胜者获得的副积分
+    secondary: Option<String>,
+    secondary_per_win: Option<i32>,
+    recompute: bool,
+    single_key: bool,
+    // 修改操作后是否自动显示积分榜；None 表示交互使用时显示、批量运行（输入不是终端）时不显示
+    auto_display: Option<bool>,
+    // 记录胜局前是否确认胜者名称；None 表示交互使用时确认、批量运行时不确认
+    confirm_winner: Option<bool>,
+    withdrawn_winner: Option<WithdrawnWinnerPolicy>,
+    // 只使用 ASCII 字符画表格，用于无法显示制表符的终端
+    ascii: bool,
+    verbose: bool,
+}
+
+impl CliOptions {
+    // 命令行指定的玩家名称校验策略，导入名单时与之后录入玩家使用同一策略
+    fn name_policy(&self) -> NamePolicy {
+        NamePolicy {
+            reject_numeric: self.reject_numeric_names,
+            accent_insensitive: self.accent_insensitive_names,
+        }
+    }
+}
+
+// 解析 "连胜局数:奖励分" 格式的连胜奖励设置，如 "3:1"
+fn parse_streak_bonus(value: &str) -> Result<StreakBonus, String> {
+    let invalid = || format!("无效的连胜奖励设置: {}（格式为 连胜局数:奖励分，如 3:1）", value);
+    let (milestone, bonus) = value.split_once(':').ok_or_else(invalid)?;
+    let milestone = milestone.trim().parse::<u32>().map_err(|_| invalid())?;
+    let bonus = bonus.trim().parse::<i32>().map_err(|_| invalid())?;
+    if milestone == 0 {
+        return Err("连胜局数必须大于0".to_string());
+    }
+    if !(-MAX_PENALTY..=MAX_PENALTY).contains(&bonus) {
+        return Err(format!("连胜奖励 {} 过大，最多为 {} 分", bonus, MAX_PENALTY));
+    }
+    Ok(StreakBonus { milestone, bonus })
+}
+
+// 限时赛的结束时刻 "时:分"，可附带时区偏移（如 "20:00+08:00"），未指定时区时与程序中其他时间一样按 UTC 计算
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ClockTime {
+    minute_of_day: u32,
+    offset_minutes: i32,
+}
+
+impl ClockTime {
+    fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("无效的结束时间: {}（格式为 时:分，如 20:00 或 20:00+08:00）", value);
+        let value = value.trim();
+        let (time, offset_minutes) = match value.find(['+', '-']) {
+            Some(pos) => {
+                let (time, offset) = value.split_at(pos);
+                let sign = if offset.starts_with('-') { -1 } else { 1 };
+                let minutes = parse_hour_minute(&offset[1..]).ok_or_else(invalid)?;
+                if minutes > 14 * 60 {
+                    return Err(invalid());
+                }
+                (time, sign * minutes as i32)
+            }
+            None => (value, 0),
+        };
+        let minute_of_day = parse_hour_minute(time).ok_or_else(invalid)?;
+        Ok(ClockTime { minute_of_day, offset_minutes })
+    }
+
+    // 从 now 起最近一次到达该时刻的时间戳；今天的该时刻已过时取次日，因此跨越午夜的比赛（如 23:00 开始、01:00 结束）也能正确计算
+    fn next_after(&self, now: u64) -> u64 {
+        let local_now = now as i64 + i64::from(self.offset_minutes) * 60;
+        let seconds_of_day = local_now.rem_euclid(86_400);
+        let target = i64::from(self.minute_of_day) * 60;
+        now + (target - seconds_of_day).rem_euclid(86_400) as u64
+    }
+}
+
+// 解析 "时:分"，返回从零点起的分钟数
+fn parse_hour_minute(value: &str) -> Option<u32> {
+    let (hour, minute) = value.split_once(':')?;
+    let hour = hour.parse::<u32>().ok()?;
+    let minute = minute.parse::<u32>().ok()?;
+    (hour < 24 && minute < 60 && value.len() <= 5).then_some(hour * 60 + minute)
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<CliOptions, String> {
+    let mut options = CliOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--import-players" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--import-players 需要指定文件路径".to_string())?;
+                options.import_players.push(path);
+            }
+            "--file" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--file 需要指定文件路径".to_string())?;
+                options.file = Some(path);
+            }
+            "--export-history" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--export-history 需要指定文件路径".to_string())?;
+                options.export_history = Some(path);
+            }
+            "--simulate" => {
+                let value = args.next().ok_or_else(|| "--simulate 需要指定局数".to_string())?;
+                let rounds = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("无效的局数: {}", value))?;
+                options.simulate = Some(rounds);
+            }
+            "--seed" => {
+                let value = args.next().ok_or_else(|| "--seed 需要指定随机种子".to_string())?;
+                let seed = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("无效的随机种子: {}", value))?;
+                options.seed = Some(seed);
+            }
+            "--copy" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--copy 需要指定格式（plain 或 markdown）".to_string())?;
+                options.copy = Some(StandingsFormat::parse(&value)?);
+            }
+            "--export-svg" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--export-svg 需要指定文件路径".to_string())?;
+                options.export_svg = Some(path);
+            }
+            "--import-kv" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--import-kv 需要指定文件路径".to_string())?;
+                options.import_kv = Some(path);
+            }
+            "--import-csv" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--import-csv 需要指定文件路径".to_string())?;
+                options.import_csv = Some(path);
+            }
+            "--min-score" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--min-score 需要指定积分下限".to_string())?;
+                let floor = value
+                    .parse::<i32>()
+                    .map_err(|_| format!("无效的积分下限: {}", value))?;
+                options.min_score = Some(floor);
+            }
+            "--score-precision" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--score-precision 需要指定小数位数".to_string())?;
+                let precision = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|precision| *precision <= 6)
+                    .ok_or_else(|| format!("无效的小数位数: {}（应为 0 到 6）", value))?;
+                options.score_precision = Some(precision);
+            }
+            "--reject-numeric-names" => options.reject_numeric_names = true,
+            "--accent-insensitive-names" => options.accent_insensitive_names = true,
+            "--round-timer" => options.round_timer = true,
+            "--practice" => options.practice = true,
+            "--read-only" => options.read_only = true,
+            "--strict" => options.strict = true,
+            "--verbose" => options.verbose = true,
+            "--ascii" => options.ascii = true,
+            "--anonymize" => options.anonymize = true,
+            "--sort-note" => options.sort_note = true,
+            "--elimination" => options.elimination = true,
+            "--print-schema" => options.print_schema = true,
+            "--dump-ids" => options.dump_ids = true,
+            "--watch" => options.watch = true,
+            "--season-mapping" => options.season_mapping = true,
+            "--season" => {
+                options.season = Some(args.next().ok_or_else(|| "--season 需要指定赛季文件或目录".to_string())?);
+            }
+            "--save-season" => {
+                options.save_season =
+                    Some(args.next().ok_or_else(|| "--save-season 需要指定文件路径".to_string())?);
+            }
+            "--watch-interval" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--watch-interval 需要指定秒数".to_string())?;
+                let seconds = value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|seconds| *seconds > 0)
+                    .ok_or_else(|| format!("无效的刷新间隔: {}（应为正整数秒）", value))?;
+                options.watch_interval = Some(seconds);
+            }
+            "--recompute" => options.recompute = true,
+            "--single-key" => options.single_key = true,
+            "--auto-display" => options.auto_display = Some(true),
+            "--no-auto-display" => options.auto_display = Some(false),
+            "--confirm-winner" => options.confirm_winner = Some(true),
+            "--no-confirm-winner" => options.confirm_winner = Some(false),
+            "--stop-at-target" => options.stop_at_target = true,
+            "--target-score" => {
+                let value = args.next().ok_or_else(|| "--target-score 需要指定目标积分".to_string())?;
+                let target = value
+                    .parse::<i32>()
+                    .map_err(|_| format!("无效的目标积分: {}", value))?;
+                if target <= 0 {
+                    return Err("目标积分必须大于0".to_string());
+                }
+                options.target_score = Some(target);
+            }
+            "--repeat-window" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--repeat-window 需要指定秒数".to_string())?;
+                let seconds = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("无效的秒数: {}", value))?;
+                options.repeat_window = Some(seconds);
+            }
+            "--title" => {
+                options.title = Some(args.next().ok_or_else(|| "--title 需要指定比赛标题".to_string())?);
+            }
+            "--secondary" => {
+                let value = args.next().ok_or_else(|| "--secondary 需要指定副积分名称".to_string())?;
+                let label = value.trim();
+                if label.is_empty() || label.chars().any(char::is_control) {
+                    return Err(format!("无效的副积分名称: {:?}", value));
+                }
+                options.secondary = Some(label.to_string());
+            }
+            "--secondary-per-win" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--secondary-per-win 需要指定每局胜者获得的副积分".to_string())?;
+                let per_win = value
+                    .parse::<i32>()
+                    .map_err(|_| format!("无效的副积分: {}", value))?;
+                options.secondary_per_win = Some(per_win);
+            }
+            "--end-at" => {
+                let value = args.next().ok_or_else(|| "--end-at 需要指定结束时间".to_string())?;
+                options.end_at = Some(ClockTime::parse(&value)?);
+            }
+            "--lock-roster" => options.lock_roster = true,
+            "--yes" => options.yes = true,
+            "--no-history" => options.no_history = true,
+            "--autosave" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--autosave 需要指定操作次数".to_string())?;
+                let interval = value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|interval| *interval > 0)
+                    .ok_or_else(|| format!("无效的自动保存间隔: {}（应为正整数）", value))?;
+                options.autosave_interval = Some(interval);
+            }
+            "--history-file" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--history-file 需要指定文件路径".to_string())?;
+                options.history_file = Some(path);
+            }
+            "--loser-delta" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--loser-delta 需要指定扣分数".to_string())?;
+                let delta = value
+                    .parse::<i32>()
+                    .map_err(|_| format!("无效的扣分数: {}", value))?;
+                options.loser_delta = Some(delta);
+            }
+            "--rank-penalties" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--rank-penalties 需要指定各名次的扣分，如 2 或 3,2".to_string())?;
+                let penalties = value
+                    .split(',')
+                    .map(|part| part.trim().parse::<i32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| format!("无效的名次扣分: {}（格式如 3,2）", value))?;
+                options.rank_penalties = Some(penalties);
+            }
+            "--streak-bonus" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--streak-bonus 需要指定 连胜局数:奖励分".to_string())?;
+                options.streak_bonus = Some(parse_streak_bonus(&value)?);
+            }
+            "--overlay-file" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| "--overlay-file 需要指定文件路径".to_string())?;
+                options.overlay_file = Some(path);
+            }
+            "--tie-break" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--tie-break 需要指定排名依据 (none/wins)".to_string())?;
+                options.tie_break = Some(match value.as_str() {
+                    "none" => TieBreak::None,
+                    "wins" => TieBreak::Wins,
+                    _ => return Err(format!("无效的排名依据: {}（可选 none/wins）", value)),
+                });
+            }
+            "--color" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--color 需要指定 auto/always/never".to_string())?;
+                options.color = match value.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    _ => return Err(format!("无效的颜色设置: {}（可选 auto/always/never）", value)),
+                };
+            }
+            "--rounding" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--rounding 需要指定舍入方式 (floor/ceil/round/truncate)".to_string())?;
+                options.rounding_mode = Some(match value.as_str() {
+                    "floor" => RoundingMode::Floor,
+                    "ceil" => RoundingMode::Ceil,
+                    "round" => RoundingMode::Round,
+                    "truncate" => RoundingMode::Truncate,
+                    _ => return Err(format!("无效的舍入方式: {}（可选 floor/ceil/round/truncate）", value)),
+                });
+            }
+            "--positive-sign" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--positive-sign 需要指定显示方式 (off/nonzero/all)".to_string())?;
+                options.positive_sign = Some(match value.as_str() {
+                    "off" => PositiveSign::Off,
+                    "nonzero" => PositiveSign::NonZero,
+                    "all" => PositiveSign::All,
+                    _ => return Err(format!("无效的正号显示方式: {}（可选 off/nonzero/all）", value)),
+                });
+            }
+            "--withdrawn-winner" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--withdrawn-winner 需要指定处理方式 (reject/reinstate)".to_string())?;
+                options.withdrawn_winner = Some(match value.as_str() {
+                    "reject" => WithdrawnWinnerPolicy::Reject,
+                    "reinstate" => WithdrawnWinnerPolicy::Reinstate,
+                    _ => return Err(format!("无效的处理方式: {}（可选 reject/reinstate）", value)),
+                });
+            }
+            "--table-style" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "--table-style 需要指定表格样式 (plain/ascii/unicode)".to_string())?;
+                options.table_style = Some(match value.as_str() {
+                    "plain" => TableStyle::Plain,
+                    "ascii" => TableStyle::Ascii,
+                    "unicode" => TableStyle::Unicode,
+                    _ => return Err(format!("无效的表格样式: {}（可选 plain/ascii/unicode）", value)),
+                });
+            }
+            _ => return Err(format!("未知参数: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn load_csv(path: &str, policy: NamePolicy) -> Result<Scoreboard, String> {
+    let file = fs::File::open(path).map_err(|e| format!("无法打开文件 '{}': {}", path, e))?;
+    Scoreboard::import_csv(io::BufReader::new(file), policy)
+}
+
+fn load_kv(path: &str, policy: NamePolicy) -> Result<Scoreboard, String> {
+    let file = fs::File::open(path).map_err(|e| format!("无法打开文件 '{}': {}", path, e))?;
+    Scoreboard::import_kv(io::BufReader::new(file), policy)
+}
+
+// 从文件（或 '-' 表示标准输入）导入玩家；仅在读取文件时显示进度
+fn run_import_players(scoreboard: &mut Scoreboard, path: &str) -> Result<(), String> {
+    let summary = if path == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| format!("读取标准输入失败: {}", e))?;
+        scoreboard.import_players(content.as_bytes())?
+    } else {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("无法读取文件 '{}': {}", path, e))?;
+        let total = content.lines().count();
+        let summary = scoreboard.import_players_with_progress(content.as_bytes(), |done| {
+            print!("\r导入进度: {:>3}% ({}/{})", done * 100 / total, done, total);
+            let _ = io::stdout().flush();
+        })?;
+        if total > 0 {
+            println!();
+        }
+        summary
+    };
+
+    let source = if path == "-" { "标准输入" } else { path };
+    println!(
+        "导入 '{}' 完成：成功添加 {} 名玩家，跳过 {} 行。",
+        source,
+        summary.added,
+        summary.skipped.len()
+    );
+    for (line_no, reason) in &summary.skipped {
+        println!("  第 {} 行: {}", line_no, reason);
+    }
+    Ok(())
+}
+
+// 将命令行中指定的积分规则和模式应用到积分板
+fn apply_options(options: &CliOptions, scoreboard: &mut Scoreboard) -> Result<(), String> {
+    if options.min_score.is_some() {
+        scoreboard.min_score = options.min_score;
+    }
+    if options.reject_numeric_names {
+        scoreboard.name_policy.reject_numeric = true;
+    }
+    if options.accent_insensitive_names {
+        scoreboard.name_policy.accent_insensitive = true;
+    }
+    if let Some(tie_break) = options.tie_break {
+        scoreboard.tie_break = tie_break;
+    }
+    if options.streak_bonus.is_some() {
+        scoreboard.streak_bonus = options.streak_bonus;
+    }
+    if options.loser_delta.is_some() || options.rank_penalties.is_some() {
+        let mut rule = scoreboard.scoring.clone();
+        if let Some(delta) = options.loser_delta {
+            rule.loser_delta = delta;
+        }
+        if let Some(penalties) = &options.rank_penalties {
+            rule.rank_penalties = penalties.clone();
+        }
+        scoreboard.set_scoring(rule)?;
+    }
+    if options.strict {
+        scoreboard.strict = true;
+    }
+    if let Some(precision) = options.score_precision {
+        scoreboard.score_precision = precision;
+    }
+    if let Some(mode) = options.rounding_mode {
+        scoreboard.rounding_mode = mode;
+    }
+    if let Some(sign) = options.positive_sign {
+        scoreboard.show_positive_sign = sign;
+    }
+    if options.target_score.is_some() {
+        scoreboard.target_score = options.target_score;
+    }
+    if options.stop_at_target {
+        scoreboard.stop_at_target = true;
+    }
+    if let Some(title) = &options.title {
+        scoreboard.set_title(title)?;
+    }
+    match (&options.secondary, scoreboard.secondary.as_mut()) {
+        (Some(label), existing) => {
+            let per_win = options
+                .secondary_per_win
+                .or(existing.map(|secondary| secondary.per_win))
+                .unwrap_or(0);
+            scoreboard.secondary = Some(SecondaryScore { label: label.clone(), per_win });
+        }
+        (None, Some(existing)) => {
+            if let Some(per_win) = options.secondary_per_win {
+                existing.per_win = per_win;
+            }
+        }
+        (None, None) if options.secondary_per_win.is_some() => {
+            return Err("--secondary-per-win 需要与 --secondary 一起使用".to_string());
+        }
+        (None, None) => {}
+    }
+    if options.repeat_window.is_some() {
+        scoreboard.repeat_window = options.repeat_window;
+    }
+    if options.sort_note {
+        scoreboard.sort_note = true;
+    }
+    if options.elimination {
+        scoreboard.elimination = true;
+    }
+    if let Some(end_at) = options.end_at {
+        scoreboard.session_deadline = Some(end_at.next_after(current_timestamp()));
+    }
+    if let Some(policy) = options.withdrawn_winner {
+        scoreboard.withdrawn_winner = policy;
+    }
+    if options.lock_roster {
+        scoreboard.lock_roster_after_first_round = true;
+    }
+    if let Some(style) = options.table_style {
+        scoreboard.table_style = style;
+    }
+    // ASCII 模式下不使用制表符边框（包括存档中保存的样式）
+    if options.ascii && scoreboard.table_style == TableStyle::Unicode {
+        scoreboard.table_style = TableStyle::Ascii;
+    }
+    scoreboard.auto_display = options.auto_display.unwrap_or_else(|| io::stdin().is_terminal());
+    scoreboard.confirm_winner = options.confirm_winner.unwrap_or_else(|| io::stdin().is_terminal());
+    scoreboard.use_color = options.color.enabled(
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        io::stdout().is_terminal(),
+    );
+    Ok(())
+}
+
+// 录入玩家，直到输入 'done' 且至少有一名玩家
+fn setup_players(scoreboard: &mut Scoreboard) {
+    println!("首先，请录入所有参与游戏的玩家名称。");
+    add_players_until_done(scoreboard, 1);
+    
+    println!("\n玩家录入完成！");
+    scoreboard.list_players();
+    scoreboard.display_scoreboard();
+}
+
+// 结束录入玩家的关键字，开局录入和游戏中途添加玩家共用
+const DONE_KEYWORD: &str = "done";
+
+// 输入是否为结束录入的关键字（不区分大小写，忽略前后空白）
+fn is_done_keyword(input: &str) -> bool {
+    input.trim().eq_ignore_ascii_case(DONE_KEYWORD)
+}
+
+// 逐个录入玩家直到输入 'done'，至少录入 min_players 名（包括已有玩家）后才能结束
+fn add_players_until_done(scoreboard: &mut Scoreboard, min_players: usize) {
+    let prompt = format!("请输入玩家名称（输入 '{}' 完成录入）: ", DONE_KEYWORD);
+    add_players_from(scoreboard, min_players, || get_input_safe(&prompt));
+}
+
+// 录入玩家的循环，从 next_name 逐个读取名称。两种录入方式的结束规则相同：
+// 输入结束关键字即结束（人数不足 min_players 时提示并继续录入），名称不合法时提示后继续录入
+fn add_players_from<F: FnMut() -> String>(scoreboard: &mut Scoreboard, min_players: usize, mut next_name: F) {
+    loop {
+        let name = next_name();
+
+        if is_done_keyword(&name) {
+            if scoreboard.players.len() < min_players {
+                println!("至少需要录入一个玩家！");
+                continue;
+            }
+            break;
+        }
+
+        match scoreboard.add_player(name.clone()) {
+            Ok(id) => {
+                println!("玩家 '{}' 已添加，序号为: {}", name, id);
+            }
+            Err(e) => {
+                println!("添加玩家失败: {}", e);
+                continue;
+            }
+        }
+    }
+}
+
+// 游戏中途添加玩家，规则与开局录入相同，并遵守名单锁定
+fn run_add_players(scoreboard: &mut Scoreboard) {
+    if scoreboard.roster_locked() {
+        println!("比赛已开始，玩家名单已锁定，无法添加玩家（可先解锁名单）");
+        return;
+    }
+    let before = scoreboard.players.len();
+    add_players_until_done(scoreboard, 0);
+    let added = scoreboard.players.len() - before;
+    if added > 0 {
+        println!("已添加 {} 名玩家。", added);
+        scoreboard.display_after_action();
+    }
+}
+
+// 启动计时（如已开启 --round-timer）
+fn start_timer_if_enabled(options: &CliOptions, scoreboard: &mut Scoreboard) {
+    if options.round_timer {
+        scoreboard.start_round_timer();
+        println!("已开启单局计时，第 1 局开始计时。");
+    }
+}
+
+fn run_switch_board(workspace: &mut Workspace) {
+    workspace.list_boards();
+    let input = get_input_safe("请输入要切换到的积分板序号: ");
+    let number = match input.parse::<usize>() {
+        Ok(number) => number,
+        Err(_) => {
+            println!("请输入有效的正整数！");
+            return;
+        }
+    };
+    match workspace.switch_to(number) {
+        Ok(()) => {
+            println!("已切换到积分板 '{}'", workspace.active_name());
+            workspace.active_board().display_scoreboard();
+        }
+        Err(e) => println!("错误: {}", e),
+    }
+}
+
+fn run_new_board(workspace: &mut Workspace, options: &CliOptions) {
+    let name = get_input_safe("请输入新积分板名称: ");
+    let mut board = Scoreboard::new();
+    if let Err(e) = apply_options(options, &mut board) {
+        println!("新建积分板失败: {}", e);
+        return;
+    }
+    match workspace.add_board(name, board) {
+        Ok(number) => {
+            println!("已新建积分板 '{}'，序号为: {}", workspace.active_name(), number);
+            let scoreboard = workspace.active_board_mut();
+            setup_players(scoreboard);
+            start_timer_if_enabled(options, scoreboard);
+        }
+        Err(e) => println!("新建积分板失败: {}", e),
+    }
+}
+
+fn run_rename_board(workspace: &mut Workspace) {
+    let name = get_input_safe("请输入积分板的新名称: ");
+    match workspace.rename_active(name) {
+        Ok(()) => println!("当前积分板已重命名为 '{}'", workspace.active_name()),
+        Err(e) => println!("重命名失败: {}", e),
+    }
+}
+
+// 执行会丢失数据的操作前要求输入确认词；以 --yes 启动时直接通过。
+// 库层方法本身不做确认，确认只在命令行交互层进行。
+const CONFIRM_WORD: &str = "确认";
+
+fn confirm_destructive(what: &str, assume_yes: bool) -> bool {
+    if assume_yes {
+        return true;
+    }
+    println!("警告: {}，此操作无法撤销。", what);
+    let input = get_input_safe(&format!("请输入\"{}\"继续，输入其他内容取消: ", CONFIRM_WORD));
+    if is_confirmed(&input) {
+        true
+    } else {
+        println!("已取消操作。");
+        false
+    }
+}
+
+// 同一胜者在短时间内被重复记录时提示确认，返回是否继续记录
+fn confirm_repeat_win(scoreboard: &Scoreboard, winner_id: PlayerId, assume_yes: bool) -> bool {
+    if assume_yes || !scoreboard.is_quick_repeat(winner_id, current_timestamp()) {
+        return true;
+    }
+    let name = scoreboard.players.get(&winner_id).map_or("", String::as_str);
+    println!("警告: 玩家 '{}' 刚刚已被记录为上一局的胜者，可能重复输入了。", name);
+    let input = get_input_safe(&format!("确实要再记录一局请输入\"{}\"，输入其他内容取消: ", CONFIRM_WORD));
+    if is_confirmed(&input) {
+        true
+    } else {
+        println!("未记录本局。");
+        false
+    }
+}
+
+// 记录胜局前显示胜者名称并请求确认，防止输错序号，返回是否继续记录。
+// 未开启 confirm_winner 或序号不存在（由记录方法报告错误）时直接通过；answer 按提示取得回答，交互时为 get_input_safe
+fn confirm_winner<F: FnOnce(&str) -> String>(scoreboard: &Scoreboard, winner_id: PlayerId, answer: F) -> bool {
+    if !scoreboard.confirm_winner {
+        return true;
+    }
+    let Some(name) = scoreboard.players.get(&winner_id) else {
+        return true;
+    };
+    let reply = answer(&format!("确认记录 '{}' 胜出？（直接回车或 y 确认，其他输入取消）: ", name));
+    if matches!(reply.trim().to_lowercase().as_str(), "" | "y" | "yes" | "是") {
+        true
+    } else {
+        println!("{}", CANCEL_NOTICE);
+        false
+    }
+}
+
+fn is_confirmed(input: &str) -> bool {
+    input.trim() == CONFIRM_WORD
+}
+
+fn run_generate_bracket(scoreboard: &Scoreboard) {
+    let active = scoreboard.players.keys().filter(|id| !scoreboard.is_withdrawn(**id)).count();
+    if active < 2 {
+        println!("至少需要两名未退出的玩家才能生成对阵。");
+        return;
+    }
+    let input = get_input_safe(&format!("请输入参加淘汰赛的人数（2-{}，直接回车为全部）: ", active));
+    let n = if input.is_empty() {
+        active
+    } else {
+        match input.parse::<usize>() {
+            Ok(n) if (2..=active).contains(&n) => n,
+            _ => {
+                println!("无效的人数: {}（应为 2 到 {} 之间的整数）", input, active);
+                return;
+            }
+        }
+    };
+
+    let seed_of: HashMap<PlayerId, usize> = scoreboard
+        .ranked()
+        .into_iter()
+        .filter(|(id, _, _)| !scoreboard.is_withdrawn(*id))
+        .enumerate()
+        .map(|(index, (id, _, _))| (id, index + 1))
+        .collect();
+    let describe = |slot: Option<(PlayerId, &str)>| match slot {
+        Some((id, name)) => format!("[{}] {}（序号 {}）", seed_of[&id], name, id),
+        None => "轮空".to_string(),
+    };
+    println!("\n=== 淘汰赛第一轮对阵（{} 人）===", n);
+    for (index, (first, second)) in scoreboard.generate_bracket(n).into_iter().enumerate() {
+        println!("第 {} 场: {} vs {}", index + 1, describe(first), describe(second));
+    }
+    println!();
+}
+
+fn run_compact_ids(scoreboard: &mut Scoreboard, assume_yes: bool) {
+    let ids = scoreboard.existing_ids();
+    if ids.iter().enumerate().all(|(index, id)| id.get() == index + 1) {
+        println!("玩家序号已经是连续的，无需整理。");
+        return;
+    }
+    let what = "玩家序号将重新编号，在别处记下的旧序号（如纸面记录、导出的文件、脚本）将不再对应原来的玩家，按序号分配的玩家颜色也会随之改变";
+    if !confirm_destructive(what, assume_yes) {
+        return;
+    }
+    let mapping = scoreboard.compact_ids();
+    println!("\n=== 序号变化 ===");
+    for old in ids {
+        let new = mapping[&old];
+        if new != old {
+            println!("{}: {} -> {}", scoreboard.players[&new], old, new);
+        }
+    }
+    println!();
+}
+
+fn run_clear_history(scoreboard: &mut Scoreboard, assume_yes: bool) {
+    if scoreboard.history.is_empty() {
+        println!("当前没有对局记录。");
+        return;
+    }
+    let what = format!(
+        "将删除 {} 局对局记录，之后无法再查看、导出或基于这些记录生成走势图",
+        scoreboard.history.len()
+    );
+    if !confirm_destructive(&what, assume_yes) {
+        return;
+    }
+    let cleared = scoreboard.clear_history();
+    println!("已清空 {} 局对局记录，当前积分保持不变。", cleared);
+}
+
+fn run_toggle_practice(workspace: &mut Workspace, options: &CliOptions) {
+    if workspace.in_practice() {
+        if !workspace.active_board().history.is_empty()
+            && !confirm_destructive("退出练习模式将丢弃练习中的所有对局", options.yes)
+        {
+            return;
+        }
+        workspace.exit_practice();
+        println!("已退出练习模式，恢复正式积分板。");
+    } else {
+        workspace.enter_practice();
+        start_timer_if_enabled(options, workspace.active_board_mut());
+        println!("已进入练习模式，以下操作不会影响正式积分，也不会被保存。");
+    }
+    workspace.active_board().display_scoreboard();
+}
+
+// 保存文件的锁文件（<文件>.lock），防止两个程序同时写入同一个文件。
+// 锁文件中记录加锁进程的 pid 和时间，释放时删除。
+struct FileLock {
+    path: PathBuf,
+}
+
+// 已存在的锁文件中记录的信息
+struct LockHolder {
+    pid: Option<u32>,
+    locked_at: Option<u64>,
+}
+
+impl FileLock {
+    fn lock_path(save_path: &Path) -> PathBuf {
+        let mut path = save_path.as_os_str().to_owned();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+
+    // 创建锁文件；锁文件已存在时返回其中记录的持有者信息
+    fn acquire(save_path: &Path) -> Result<FileLock, LockHolder> {
+        let path = Self::lock_path(save_path);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                // 写入失败不影响加锁本身
+                let _ = writeln!(file, "{} {}", std::process::id(), current_timestamp());
+                Ok(FileLock { path })
+            }
+            Err(_) => {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                let mut fields = content.split_whitespace();
+                Err(LockHolder {
+                    pid: fields.next().and_then(|pid| pid.parse().ok()),
+                    locked_at: fields.next().and_then(|time| time.parse().ok()),
+                })
+            }
+        }
+    }
+
+    // 删除旧的锁文件后重新加锁（用于接管异常退出后残留的锁）
+    fn force_acquire(save_path: &Path) -> io::Result<FileLock> {
+        let path = Self::lock_path(save_path);
+        fs::remove_file(&path)?;
+        Self::acquire(save_path).map_err(|_| io::Error::other("锁文件被其他程序抢先创建"))
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl LockHolder {
+    // 持有锁的进程是否仍在运行；无法判断时（如非 Linux 系统）返回 None
+    fn is_alive(&self) -> Option<bool> {
+        let pid = self.pid?;
+        if !Path::new("/proc").exists() {
+            return None;
+        }
+        Some(Path::new(&format!("/proc/{}", pid)).exists())
+    }
+
+    fn describe(&self) -> String {
+        let mut text = match self.pid {
+            Some(pid) => format!("进程 {}", pid),
+            None => "未知进程".to_string(),
+        };
+        if let Some(time) = self.locked_at {
+            text.push_str(&format!("，加锁时间 {}", format_timestamp(time)));
+        }
+        match self.is_alive() {
+            Some(true) => text.push_str("，该进程仍在运行"),
+            Some(false) => text.push_str("，该进程已不存在，可能是上次异常退出残留的锁"),
+            None => {}
+        }
+        text
+    }
+}
+
+// 本次会话持有的锁；程序通过 exit 退出时 Drop 不会执行，需要手动释放
+static SESSION_LOCK: Mutex<Option<FileLock>> = Mutex::new(None);
+
+fn release_session_lock() {
+    if let Ok(mut lock) = SESSION_LOCK.lock() {
+        lock.take();
+    }
+}
+
+// 为保存文件加锁；文件已被锁定时提示，确认后可强制接管，否则退出
+fn lock_save_file(path: &Path, assume_yes: bool) {
+    let lock = match FileLock::acquire(path) {
+        Ok(lock) => lock,
+        Err(holder) => {
+            println!("警告: 文件 '{}' 正在被其他程序使用（{}）。", path.display(), holder.describe());
+            if holder.is_alive() == Some(true)
+                || !confirm_destructive("强制接管锁文件后，另一个程序的保存可能覆盖本次的数据", assume_yes)
+            {
+                eprintln!("请关闭另一个程序后重试，或使用 --read-only 只读查看。");
+                std::process::exit(1);
+            }
+            match FileLock::force_acquire(path) {
+                Ok(lock) => lock,
+                Err(e) => {
+                    eprintln!("接管锁文件失败: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    };
+    if let Ok(mut session) = SESSION_LOCK.lock() {
+        *session = Some(lock);
+    }
+}
+
+// 每 interval 次修改操作后自动保存到 --file 指定的文件
+struct AutoSave {
+    interval: usize,
+    pending: usize,
+}
+
+impl AutoSave {
+    fn new(interval: usize) -> Self {
+        AutoSave { interval, pending: 0 }
+    }
+
+    fn record_operation(&mut self) {
+        self.pending += 1;
+    }
+
+    // 手动保存后重新计数
+    fn reset(&mut self) {
+        self.pending = 0;
+    }
+
+    // 达到间隔时保存并重新计数，返回保存结果；未到间隔时返回 None
+    fn run_if_due(&mut self, workspace: &Workspace, path: &Path) -> Option<Result<(), String>> {
+        if self.pending < self.interval {
+            return None;
+        }
+        self.pending = 0;
+        Some(workspace.save_to_file(path))
+    }
+}
+
+fn save_workspace(workspace: &Workspace, file: Option<&str>) {
+    match file {
+        Some(path) => match workspace.save_to_file(Path::new(path)) {
+            Ok(()) => println!("已保存到 '{}'", path),
+            Err(e) => println!("保存失败: {}", e),
+        },
+        None => println!("未指定保存文件，请使用 --file 参数启动程序。"),
+    }
+}
+
+// 领先者信息有变化时重写直播叠加文件，写入失败只提示不中断
+fn update_overlay(path: &str, scoreboard: &Scoreboard, last_written: &mut Option<String>) {
+    let text = scoreboard.overlay_text();
+    if last_written.as_deref() == Some(text.as_str()) {
+        return;
+    }
+    match write_atomic(Path::new(path), &text) {
+        Ok(()) => *last_written = Some(text),
+        Err(e) => eprintln!("警告: 无法写入叠加文件 '{}': {}", path, e),
+    }
+}
+
+fn run_query_score(scoreboard: &Scoreboard) {
+    let name = get_input_safe("请输入玩家名称: ");
+    if name.is_empty() {
+        println!("名称不能为空！");
+        return;
+    }
+    let id = match scoreboard.find_by_name(&name) {
+        Ok(id) => id,
+        Err(e) => {
+            println!("查询失败: {}", e);
+            return;
+        }
+    };
+    let score = scoreboard.score_of_name(&name).unwrap_or(0);
+    println!(
+        "玩家 '{}'（序号 {}）: {} 分，第 {} 名",
+        scoreboard.players[&id],
+        id,
+        scoreboard.score_text(score),
+        scoreboard.ranks()[&id]
+    );
+}
+
+fn run_rename_by_name(scoreboard: &mut Scoreboard) {
+    let old = get_input_safe("请输入原玩家名称: ");
+    if old.is_empty() {
+        println!("名称不能为空！");
+        return;
+    }
+    let new_name = get_input_safe("请输入新名称: ");
+    match scoreboard.rename_by_name(&old, new_name.clone()) {
+        Ok(()) => println!("玩家 '{}' 已改名为 '{}'", old, new_name),
+        Err(e) => println!("改名失败: {}", e),
+    }
+}
+
+// 显示随机出场顺序；种子留空时自动生成并显示，便于事后核验
+fn run_shuffle_order(scoreboard: &Scoreboard) {
+    let input = get_input_safe("请输入随机种子（直接回车自动生成）: ");
+    let seed = if input.is_empty() {
+        random_seed()
+    } else {
+        match input.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => {
+                println!("无效的种子: {}（应为非负整数）", input);
+                return;
+            }
+        }
+    };
+
+    println!("\n=== 随机出场顺序（种子: {}）===", seed);
+    for (position, (id, name)) in scoreboard.shuffle_order(Some(seed)).iter().enumerate() {
+        println!("{}. {} (序号 {})", position + 1, name, id);
+    }
+    println!();
+}
+
+// diff 子命令：对比两个存档文件当前积分板的积分变化
+fn run_diff(before_path: &str, after_path: &str) -> Result<(), String> {
+    let (before, _) = Workspace::load_from_file(Path::new(before_path))?;
+    let (after, _) = Workspace::load_from_file(Path::new(after_path))?;
+    let before = before.active_board();
+    let after = after.active_board();
+
+    println!("=== 积分变化: {} -> {} ===", before_path, after_path);
+    let diffs = before.diff(after);
+    if diffs.is_empty() {
+        println!("两个文件的积分没有差异");
+    }
+    print_score_diffs(before, after, diffs);
+    Ok(())
+}
+
+// 逐行显示积分差异，分数按 after 积分板的显示设置格式化
+fn print_score_diffs(before: &Scoreboard, after: &Scoreboard, diffs: Vec<ScoreDiff>) {
+    for diff in diffs {
+        match (diff.before, diff.after) {
+            (Some(old), Some(new)) => {
+                let name = match &diff.previous_name {
+                    Some(previous) => format!("{}（原名 {}）", diff.name, previous),
+                    None => diff.name.clone(),
+                };
+                println!(
+                    "{}: {} -> {} ({})",
+                    name,
+                    after.score_text(old),
+                    after.score_text(new),
+                    after.delta_text(new - old)
+                );
+            }
+            (None, Some(new)) => println!("新增 {}: {}", diff.name, after.score_text(new)),
+            (Some(old), None) => println!("移除 {}: {}", diff.name, before.score_text(old)),
+            (None, None) => {}
+        }
+    }
+}
+
+fn run_recompute_scores(scoreboard: &mut Scoreboard, assume_yes: bool) {
+    let mut recomputed = scoreboard.clone();
+    let diffs = match recomputed.recompute_scores() {
+        Ok(diffs) => diffs,
+        Err(e) => {
+            println!("无法重算积分: {}", e);
+            return;
+        }
+    };
+    if diffs.is_empty() {
+        println!("当前积分与对局记录一致，无需修正。");
+        return;
+    }
+    println!("\n=== 按对局记录重算后的积分变化 ===");
+    print_score_diffs(scoreboard, &recomputed, diffs);
+    if confirm_destructive("将用重算结果替换当前积分", assume_yes) {
+        *scoreboard = recomputed;
+        println!("积分已按对局记录修正。");
+        scoreboard.display_after_action();
+    }
+}
+
+fn main() {
+    // 子命令: diff <之前的文件> <之后的文件>
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("diff") {
+        if args.len() != 3 {
+            eprintln!("参数错误: 用法为 diff <之前的文件> <之后的文件>");
+            std::process::exit(2);
+        }
+        if let Err(e) = run_diff(&args[1], &args[2]) {
+            eprintln!("对比失败: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let options = match parse_args(args.into_iter()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("参数错误: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // 只输出示例存档，便于重定向到文件，因此在欢迎语之前处理
+    if options.print_schema {
+        match example_save_json() {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("生成示例存档失败: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    // 同样只向标准输出写 JSON，加载时的警告写到标准错误
+    if options.dump_ids {
+        if let Err(e) = run_dump_ids(options.file.as_deref()) {
+            eprintln!("输出玩家序号失败: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(path) = &options.season {
+        if let Err(e) = run_season(path, options.save_season.as_deref(), options.season_mapping) {
+            eprintln!("计算赛季积分失败: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if options.watch {
+        let interval = options.watch_interval.unwrap_or(DEFAULT_WATCH_INTERVAL);
+        if let Err(e) = run_watch(options.file.as_deref(), interval) {
+            eprintln!("观看模式失败: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    set_verbose(options.verbose);
+    println!("欢迎使用游戏积分板系统！");
+
+    if !options.no_history {
+        let path = options.history_file.as_deref().unwrap_or(DEFAULT_HISTORY_FILE);
+        if let Ok(mut history) = INPUT_HISTORY.lock() {
+            *history = Some(InputHistory::load(PathBuf::from(path)));
+        }
+    }
+
+    let mut workspace = match &options.file {
+        Some(path) if Path::new(path).exists() => match Workspace::load_from_file(Path::new(path)) {
+            Ok((workspace, warnings)) => {
+                for warning in warnings {
+                    println!("警告: {}", warning);
+                }
+                println!("已从 '{}' 加载 {} 个积分板。", path, workspace.boards.len());
+                workspace
+            }
+            Err(e) => {
+                eprintln!("加载失败: {}", e);
+                std::process::exit(1);
+            }
+        },
+        _ => Workspace::new(Scoreboard::new()),
+    };
+
+    if let Some(path) = &options.import_csv {
+        match load_csv(path, options.name_policy()) {
+            Ok(scoreboard) => {
+                println!("已从 '{}' 导入 {} 名玩家。", path, scoreboard.players.len());
+                *workspace.active_board_mut() = scoreboard;
+            }
+            Err(e) => {
+                eprintln!("导入 CSV 失败: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = &options.import_kv {
+        match load_kv(path, options.name_policy()) {
+            Ok(scoreboard) => {
+                println!("已从 '{}' 导入 {} 名玩家。", path, scoreboard.players.len());
+                *workspace.active_board_mut() = scoreboard;
+            }
+            Err(e) => {
+                eprintln!("导入积分文件失败: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    for entry in &mut workspace.boards {
+        if let Err(e) = apply_options(&options, &mut entry.board) {
+            eprintln!("参数错误: {}", e);
+            std::process::exit(2);
+        }
+    }
+
+    // --recompute: 启动时按对局记录重算每个积分板的积分并报告差异
+    if options.recompute {
+        for entry in &mut workspace.boards {
+            let before = entry.board.clone();
+            match entry.board.recompute_scores() {
+                Ok(diffs) if diffs.is_empty() => {
+                    println!("积分板 '{}': 积分与对局记录一致。", entry.name);
+                }
+                Ok(diffs) => {
+                    println!("积分板 '{}': 已按对局记录修正以下积分:", entry.name);
+                    print_score_diffs(&before, &entry.board, diffs);
+                }
+                Err(e) => println!("积分板 '{}': 无法重算积分: {}", entry.name, e),
+            }
+        }
+    }
+
+    // 多个名单文件依次导入，序号连续分配，已存在的名称（按当前的重名规则）跳过
+    for path in &options.import_players {
+        if let Err(e) = run_import_players(workspace.active_board_mut(), path) {
+            eprintln!("导入玩家失败: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // 仅导出对局记录或积分走势图，不进入交互界面
+    if options.export_history.is_some() || options.export_svg.is_some() || options.copy.is_some() {
+        let scoreboard = export_board(workspace.active_board(), options.anonymize);
+        if let Some(path) = &options.export_history {
+            match write_export_file(path, |writer| scoreboard.export_history_csv(writer)) {
+                Ok(()) => println!("对局记录已导出到 '{}'", path),
+                Err(e) => {
+                    eprintln!("导出对局记录失败: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        if let Some(path) = &options.export_svg {
+            match write_export_file(path, |writer| scoreboard.export_svg(writer)) {
+                Ok(()) => println!("积分走势图已导出到 '{}'", path),
+                Err(e) => {
+                    eprintln!("导出积分走势图失败: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        if let Some(format) = options.copy {
+            run_copy_standings(&scoreboard, format);
+        }
+        return;
+    }
+
+    // 只读模式只能查看已加载的积分板
+    if options.read_only && workspace.active_board().players.is_empty() {
+        eprintln!("只读模式需要通过 --file、--import-csv 或 --import-kv 加载已有的积分板。");
+        std::process::exit(1);
+    }
+
+    // 会写入保存文件的会话需要加锁
+    if let Some(path) = &options.file
+        && !options.read_only
+    {
+        lock_save_file(Path::new(path), options.yes);
+    }
+
+    if options.practice {
+        workspace.enter_practice();
+        println!("【练习模式】当前操作不会影响正式积分，也不会被保存。");
+    }
+
+    let scoreboard = workspace.active_board_mut();
+    if scoreboard.players.is_empty() {
+        setup_players(scoreboard);
+    } else {
+        scoreboard.list_players();
+        scoreboard.display_scoreboard();
+    }
+    if let Some(rounds) = options.simulate {
+        if options.read_only {
+            println!("只读模式下不能模拟对局。");
+        } else {
+            match scoreboard.simulate(rounds, options.seed.unwrap_or_else(random_seed)) {
+                Ok(()) => {
+                    println!("已随机模拟 {} 局。", rounds);
+                    scoreboard.display_scoreboard();
+                }
+                Err(e) => println!("模拟对局失败: {}", e),
+            }
+        }
+    }
+    start_timer_if_enabled(&options, scoreboard);
+
+    let mut overlay_written = None;
+    let mut autosave = match (options.autosave_interval, &options.file) {
+        (Some(_), None) => {
+            println!("警告: 未指定 --file，自动保存不会生效。");
+            None
+        }
+        (Some(interval), Some(_)) if !options.read_only => Some(AutoSave::new(interval)),
+        _ => None,
+    };
+    
+    // 单键模式需要终端支持原始模式，输入来自管道或文件时直接使用按行输入
+    let mut single_key = options.single_key && io::stdin().is_terminal();
+    if options.single_key && !single_key {
+        println!("提示: 输入不是终端，单键模式不可用，使用按行输入的菜单。");
+    }
+
+    // 游戏循环
+    loop {
+        for notice in workspace.active_board_mut().take_notices() {
+            println!("提示: {}", notice);
+        }
+        if let Some(message) = workspace.active_board_mut().take_target_announcement() {
+            println!("\n{}\n", message);
+        }
+        if let Some(message) = workspace.active_board_mut().take_session_end_announcement() {
+            println!("\n{}", message);
+            workspace.active_board().display_scoreboard();
+        }
+
+        // 练习模式的结果不输出到直播叠加文件
+        if let Some(path) = &options.overlay_file
+            && !workspace.in_practice()
+        {
+            update_overlay(path, workspace.active_board(), &mut overlay_written);
+        }
+
+        // 自动保存失败只提示，不中断比赛
+        if let (Some(autosave), Some(path)) = (autosave.as_mut(), options.file.as_deref())
+            && let Some(Err(e)) = autosave.run_if_due(&workspace, Path::new(path))
+        {
+            println!("警告: 自动保存失败: {}", e);
+        }
+
+        print_menu(&workspace, options.read_only);
+
+        let prompt = format!("请输入选择 (1-{}，h 查看帮助): ", MENU_COMMANDS.len());
+        let input = match single_key.then(|| read_menu_key(SINGLE_KEY_PROMPT)) {
+            Some(Some(choice)) => MenuInput::Line(choice),
+            Some(None) => {
+                println!("\n提示: 无法读取单个按键，已改用按行输入的菜单。");
+                single_key = false;
+                get_menu_input(&prompt)
+            }
+            None => get_menu_input(&prompt),
+        };
+        let practice = workspace.in_practice();
+        let scoreboard = workspace.active_board_mut();
+        // 在菜单处输入结束时按正常退出处理：显示最终积分榜，输出报告并保存
+        let choice = match input {
+            MenuInput::Line(choice) => choice,
+            MenuInput::End => {
+                println!("\n输入已结束，自动退出。");
+                scoreboard.display_scoreboard();
+                quit_key().to_string()
+            }
+        };
+
+        let command = find_command(&choice);
+        if options.read_only && command.is_some_and(|c| !c.read_only) {
+            println!("错误: 只读模式下无法执行该操作。");
+            continue;
+        }
+
+        if let Some(autosave) = autosave.as_mut()
+            && command.is_some_and(|c| !c.read_only && c.action != MenuAction::Save)
+        {
+            autosave.record_operation();
+        }
+
+        if let Some(command) = command {
+            verbose_log(|| format!("执行命令: {} {}", command.key, command.label));
+        }
+
+        match command.map(|command| command.action) {
+            Some(MenuAction::RecordWin) => {
+                scoreboard.list_players();
+                let winner_input = get_input_safe(&winner_prompt(scoreboard));
+                
+                match parse_winner_input(&winner_input) {
+                    Ok(None) => println!("{}", CANCEL_NOTICE),
+                    Ok(Some(winner_id))
+                        if !confirm_winner(scoreboard, winner_id, get_input_safe)
+                            || !confirm_repeat_win(scoreboard, winner_id, options.yes) => {}
+                    Ok(Some(winner_id)) if scoreboard.elimination => run_elimination_round(scoreboard, winner_id),
+                    Ok(Some(winner_id)) => match scoreboard.update_scores(winner_id) {
+                        Ok(()) => {
+                            println!("积分已更新！");
+                            scoreboard.display_after_action();
+                        }
+                        Err(e) => println!("错误: {}", e),
+                    },
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Some(MenuAction::ShowScoreboard) => {
+                scoreboard.display_scoreboard();
+            }
+            Some(MenuAction::ListPlayers) => {
+                scoreboard.list_players();
+            }
+            Some(MenuAction::Quit) => {
+                println!("\n{}", scoreboard.generate_report());
+                if options.file.is_some() && !options.read_only {
+                    save_workspace(&workspace, options.file.as_deref());
+                }
+                println!("感谢使用游戏积分板系统！再见！");
+                release_session_lock();
+                break;
+            }
+            Some(MenuAction::ShowHistory) => {
+                scoreboard.display_history();
+            }
+            Some(MenuAction::SplitPlayer) => {
+                run_split_player(scoreboard);
+            }
+            Some(MenuAction::Help) => {
+                print_help(scoreboard, options.read_only, practice);
+            }
+            Some(MenuAction::ExportCsv) => {
+                let board = export_board(scoreboard, options.anonymize);
+                run_export("积分榜", |writer| board.export_csv(writer));
+            }
+            Some(MenuAction::ExportHistoryCsv) => {
+                let board = export_board(scoreboard, options.anonymize);
+                run_export("对局记录", |writer| board.export_history_csv(writer));
+            }
+            Some(MenuAction::PinPlayer) => {
+                run_pin_player(scoreboard);
+            }
+            Some(MenuAction::ToggleTimer) => match scoreboard.round_timer.as_mut() {
+                Some(timer) if timer.is_paused() => {
+                    timer.resume();
+                    println!("计时已继续，本局已用时 {}", format_duration(timer.elapsed()));
+                }
+                Some(timer) => {
+                    timer.pause();
+                    println!("计时已暂停，本局已用时 {}", format_duration(timer.elapsed()));
+                }
+                None => println!("未开启单局计时，请使用 --round-timer 参数启动程序。"),
+            },
+            Some(MenuAction::TogglePractice) => {
+                run_toggle_practice(&mut workspace, &options);
+            }
+            Some(MenuAction::RecordMatch) => {
+                run_record_match(scoreboard);
+            }
+            Some(MenuAction::Rematch) => {
+                run_rematch(scoreboard);
+            }
+            Some(MenuAction::ShowRankGaps) => {
+                scoreboard.display_rank_gaps();
+            }
+            Some(MenuAction::ShowPlayerRecord) => {
+                run_show_player_record(scoreboard);
+            }
+            Some(MenuAction::RecordBatch) => {
+                run_record_batch(scoreboard);
+            }
+            Some(MenuAction::ShowHistogram) => {
+                run_show_histogram(scoreboard);
+            }
+            Some(MenuAction::SetTag) => {
+                run_set_tag(scoreboard);
+            }
+            Some(MenuAction::ExportPng) => {
+                run_export_png(&export_board(scoreboard, options.anonymize));
+            }
+            Some(MenuAction::ShowGaps) => {
+                scoreboard.display_gaps();
+            }
+            Some(MenuAction::OpenReport) => {
+                run_open_report(&export_board(scoreboard, options.anonymize));
+            }
+            Some(MenuAction::ClearHistory) => {
+                run_clear_history(scoreboard, options.yes);
+            }
+            Some(MenuAction::CompactIds) => {
+                run_compact_ids(scoreboard, options.yes);
+            }
+            Some(MenuAction::ExportMatchups) => {
+                run_export_matchups(&export_board(scoreboard, options.anonymize));
+            }
+            Some(MenuAction::AdjustSecondary) => {
+                run_adjust_secondary(scoreboard);
+            }
+            Some(MenuAction::CopyStandings) => {
+                match StandingsFormat::parse(&get_input_safe("请选择格式（plain 纯文本 / markdown 表格）: ")) {
+                    Ok(format) => run_copy_standings(&export_board(scoreboard, options.anonymize), format),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            Some(MenuAction::ListPlayersSorted) => {
+                match get_input_safe("请选择排列方式（1 序号 / 2 名称 / 3 积分）: ").as_str() {
+                    "1" => scoreboard.list_players_sorted(PlayerSort::Id),
+                    "2" => scoreboard.list_players_sorted(PlayerSort::Name),
+                    "3" => scoreboard.list_players_sorted(PlayerSort::Score),
+                    _ => println!("无效的选择！"),
+                }
+            }
+            Some(MenuAction::SetTitle) => {
+                if let Some(title) = &scoreboard.title {
+                    println!("当前标题: {}", title);
+                }
+                match scoreboard.set_title(&get_input_safe("请输入比赛标题（留空清除标题）: ")) {
+                    Ok(()) => match &scoreboard.title {
+                        Some(title) => println!("比赛标题已设置为 '{}'", title),
+                        None => println!("已清除比赛标题"),
+                    },
+                    Err(e) => println!("错误: {}", e),
+                }
+            }
+            Some(MenuAction::RecomputeScores) => {
+                run_recompute_scores(scoreboard, options.yes);
+            }
+            Some(MenuAction::GenerateBracket) => {
+                run_generate_bracket(scoreboard);
+            }
+            Some(MenuAction::ToggleRelativeToMean) => {
+                scoreboard.show_relative_to_mean = !scoreboard.show_relative_to_mean;
+                if scoreboard.show_relative_to_mean {
+                    match scoreboard.mean_score() {
+                        Some(mean) => println!(
+                            "积分榜将显示相对平均积分（当前平均 {}）。",
+                            format_score_rounded(mean, scoreboard.score_precision, scoreboard.rounding_mode)
+                        ),
+                        None => println!("积分榜将显示相对平均积分（暂无玩家，无法计算平均积分）。"),
+                    }
+                    scoreboard.display_scoreboard();
+                } else {
+                    println!("积分榜不再显示相对平均积分。");
+                }
+            }
+            Some(MenuAction::ShowInputHistory) => match INPUT_HISTORY.lock() {
+                Ok(history) => match history.as_ref() {
+                    Some(history) if !history.entries.is_empty() => {
+                        println!("\n=== 最近的输入 ===");
+                        for entry in history.recent(20) {
+                            println!("  {}", entry);
+                        }
+                        println!();
+                    }
+                    Some(_) => println!("暂无输入历史"),
+                    None => println!("未开启输入历史（已使用 --no-history 或历史文件无法写入）"),
+                },
+                Err(_) => println!("无法读取输入历史"),
+            },
+            Some(MenuAction::RecordWeightedWin) => {
+                run_record_weighted_win(scoreboard);
+            }
+            Some(MenuAction::QueryScore) => {
+                run_query_score(scoreboard);
+            }
+            Some(MenuAction::AddPlayer) => {
+                run_add_players(scoreboard);
+            }
+            Some(MenuAction::Freeze) => {
+                if let Some(frozen) = &scoreboard.frozen
+                    && !confirm_destructive(
+                        &format!("将替换已有的冻结快照 '{}'", frozen.label),
+                        options.yes,
+                    )
+                {
+                    continue;
+                }
+                let label = get_input_safe("请输入快照标签（直接回车使用\"冻结\"）: ");
+                let label = if label.is_empty() { "冻结".to_string() } else { label };
+                scoreboard.freeze(label.clone());
+                println!("已冻结当前积分: {}", label);
+            }
+            Some(MenuAction::ShowFrozen) => {
+                scoreboard.display_frozen_comparison();
+            }
+            Some(MenuAction::RenamePlayer) => {
+                run_rename_by_name(scoreboard);
+            }
+            Some(MenuAction::ShuffleOrder) => {
+                run_shuffle_order(scoreboard);
+            }
+            Some(MenuAction::UnlockRoster) => {
+                if scoreboard.roster_locked() {
+                    scoreboard.unlock_roster();
+                    println!("玩家名单已解锁，可以继续添加玩家。");
+                } else {
+                    println!("玩家名单当前未锁定。");
+                }
+            }
+            Some(MenuAction::ExportSvg) => {
+                let board = export_board(scoreboard, options.anonymize);
+                run_export("积分走势图", |writer| board.export_svg(writer));
+            }
+            Some(MenuAction::WithdrawPlayer) => {
+                run_withdraw_player(scoreboard);
+            }
+            Some(MenuAction::SwitchBoard) => {
+                run_switch_board(&mut workspace);
+            }
+            Some(MenuAction::NewBoard) => {
+                run_new_board(&mut workspace, &options);
+            }
+            Some(MenuAction::RenameBoard) => {
+                run_rename_board(&mut workspace);
+            }
+            Some(MenuAction::Save) => {
+                save_workspace(&workspace, options.file.as_deref());
+                if let Some(autosave) = autosave.as_mut() {
+                    autosave.reset();
+                }
+            }
+            None => {
+                println!("无效选择，请输入菜单中的序号，或输入 'h' 查看帮助。");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_add_player_validation() {
+    fn test_add_player_validation() {
+        let mut scoreboard = Scoreboard::new();
+        
+        // 测试空名称
+        assert!(scoreboard.add_player("".to_string()).is_err());
+        
+        // 测试过长名称
+        let long_name = "a".repeat(25);
+        assert!(scoreboard.add_player(long_name).is_err());
+        
+        // 测试包含控制字符的名称
+        assert!(scoreboard.add_player("test\n".to_string()).is_err());
+        assert!(scoreboard.add_player("test\t".to_string()).is_err());
+        
+        // 测试正常名称
+        assert!(scoreboard.add_player("张三".to_string()).is_ok());
+        
+        // 测试重复名称
+        assert!(scoreboard.add_player("张三".to_string()).is_err());
+    }
+    
+    #[test]
+    fn test_numeric_name_policy() {
+        // 默认允许纯数字名称
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.add_player("123".to_string()).is_ok());
+
+        // 严格策略下拒绝纯数字名称，但允许包含数字的名称
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.name_policy.reject_numeric = true;
+        assert!(scoreboard.add_player("123".to_string()).is_err());
+        assert!(scoreboard.add_player("玩家123".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_accent_insensitive_duplicates() {
+        // 默认只拒绝完全相同的名称
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.add_player("José".to_string()).is_ok());
+        assert!(scoreboard.add_player("Jose".to_string()).is_ok());
+
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.name_policy.accent_insensitive = true;
+        scoreboard.add_player("José".to_string()).unwrap();
+        let err = scoreboard.add_player("Jose".to_string()).unwrap_err();
+        assert!(err.contains("'José'"));
+        assert!(scoreboard.add_player("JOSÉ".to_string()).is_err());
+        assert!(scoreboard.add_player("Zoë".to_string()).is_ok());
+        assert!(scoreboard.add_player("Zoe".to_string()).is_err());
+        assert!(scoreboard.add_player("张三".to_string()).is_ok());
+        assert!(scoreboard.add_player("José".to_string()).is_err());
+
+        // 保存的名称保持原样
+        assert_eq!(scoreboard.players[&PlayerId(1)], "José");
+        assert_eq!(scoreboard.players.len(), 3);
+    }
+
+    #[test]
+    fn test_update_scores() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        let id4 = scoreboard.add_player("玩家4".to_string()).unwrap();
+        
+        // 测试不存在的玩家ID
+        assert!(scoreboard.update_scores(PlayerId(999)).is_err());
+        
+        // 测试正常更新（4个玩家，1个胜出）
+        assert!(scoreboard.update_scores(id1).is_ok());
+        // 胜出玩家应该得到3分（其他3个玩家的分数）
+        assert_eq!(*scoreboard.scores.get(&id1).unwrap(), 3);
+        // 其他玩家各扣1分
+        assert_eq!(*scoreboard.scores.get(&id2).unwrap(), -1);
+        assert_eq!(*scoreboard.scores.get(&id3).unwrap(), -1);
+        assert_eq!(*scoreboard.scores.get(&id4).unwrap(), -1);
+    }
+    
+    #[test]
+    fn test_input_length_validation() {
+        // 测试输入长度验证逻辑
+        let test_input = "a".repeat(60);
+        assert!(test_input.len() > 50); // 验证测试数据确实超过限制
+        
+        // 测试玩家名称长度限制
+        let mut scoreboard = Scoreboard::new();
+        let long_name = "很长的玩家名称".repeat(5); // 创建超长名称
+        assert!(scoreboard.add_player(long_name).is_err());
+    }
+
+    #[test]
+    fn test_withdrawn_player_score_is_frozen() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id3], -1);
+
+        assert!(scoreboard.withdraw_player(PlayerId(999)).is_err());
+        scoreboard.withdraw_player(id3).unwrap();
+        assert!(scoreboard.withdraw_player(id3).is_err());
+
+        // 退出后积分不再变化，胜者只获得仍在比赛中的其他玩家数量的分数
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        assert_eq!(scoreboard.scores[&id3], -1);
+        assert_eq!(scoreboard.scores[&id1], 2 + 1 - 1);
+        assert_eq!(scoreboard.scores[&id2], -1 - 1 + 1);
+        assert!(scoreboard.anomalies().is_empty());
+
+        // 恢复后重新参与扣分
+        scoreboard.reinstate_player(id3).unwrap();
+        assert!(scoreboard.reinstate_player(id3).is_err());
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id3], -2);
+    }
+
+    #[test]
+    fn test_withdrawn_winner_is_rejected_by_default() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.withdraw_player(c).unwrap();
+
+        let err = scoreboard.update_scores(c).unwrap_err();
+        assert!(err.contains("玩家 'C' 已退出"), "{}", err);
+        let err = scoreboard.update_scores_among(c, &[a, c]).unwrap_err();
+        assert!(err.contains("已退出"), "{}", err);
+        assert!(scoreboard.history.is_empty());
+        assert!(scoreboard.is_withdrawn(c));
+        assert!(scoreboard.take_notices().is_empty());
+        assert_eq!(scoreboard.scores[&b], 0);
+    }
+
+    #[test]
+    fn test_withdrawn_winner_can_be_reinstated() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.withdraw_player(c).unwrap();
+        scoreboard.withdrawn_winner = WithdrawnWinnerPolicy::Reinstate;
+
+        scoreboard.update_scores(c).unwrap();
+        assert!(!scoreboard.is_withdrawn(c));
+        assert_eq!(scoreboard.scores[&c], 2);
+        assert_eq!(scoreboard.scores[&a], -1);
+        let notices = scoreboard.take_notices();
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].contains("自动恢复"), "{}", notices[0]);
+        assert!(scoreboard.take_notices().is_empty());
+    }
+
+    #[test]
+    fn test_round_without_losers_is_rejected() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.withdraw_player(b).unwrap();
+
+        // 另一名玩家已退出，胜者是唯一的参赛玩家
+        let err = scoreboard.update_scores(a).unwrap_err();
+        assert!(err.contains("无人失利"), "{}", err);
+        assert!(scoreboard.history.is_empty());
+        assert_eq!(scoreboard.scores[&a], 0);
+
+        // 恢复后有两名参赛玩家，恰好满足要求
+        scoreboard.reinstate_player(b).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.scores[&a], 1);
+        assert_eq!(scoreboard.scores[&b], -1);
+    }
+
+    #[test]
+    fn test_read_input_limits() {
+        let mut reader = io::Cursor::new("  张三  \n".to_string() + &"a".repeat(60) + "\n");
+        assert_eq!(read_input(&mut reader), Ok("张三".to_string()));
+        assert!(read_input(&mut reader).is_err());
+        assert_eq!(read_input(&mut reader), Err("输入流已结束 (EOF)".to_string()));
+    }
+
+    #[test]
+    fn test_read_invalid_utf8() {
+        let mut bytes = b"ab\xff\xfe\n".to_vec();
+        bytes.extend_from_slice("张三\n".as_bytes());
+        let mut reader = io::Cursor::new(bytes);
+        let err = read_input(&mut reader).unwrap_err();
+        assert!(err.contains("无效的 UTF-8"));
+        // 错误信息不会被 get_input_safe 当作输入流结束而退出
+        assert!(!err.contains("EOF") && !err.contains("输入流"));
+        assert_eq!(read_input(&mut reader), Ok("张三".to_string()));
+    }
+
+    #[test]
+    fn test_read_huge_line_is_discarded() {
+        // 1MB 的超长行被拒绝，且不影响之后的正常输入
+        let huge = "a".repeat(1024 * 1024);
+        let mut reader = io::BufReader::new(io::Cursor::new(format!("{}\nnext\n", huge)));
+        let err = read_line_capped(&mut reader, MAX_LINE_BYTES).unwrap_err();
+        assert!(err.contains("过长"));
+        assert_eq!(read_input(&mut reader), Ok("next".to_string()));
+
+        // 没有换行结尾的超长输入同样被丢弃
+        let mut reader = io::Cursor::new(huge.into_bytes());
+        assert!(read_line_capped(&mut reader, MAX_LINE_BYTES).unwrap_err().contains("过长"));
+        assert!(read_line_capped(&mut reader, MAX_LINE_BYTES).unwrap_err().contains("EOF"));
+
+        // 恰好达到上限的行仍可正常读取
+        let exact = "b".repeat(MAX_LINE_BYTES - 1) + "\n";
+        let mut reader = io::Cursor::new(exact.clone());
+        assert_eq!(read_line_capped(&mut reader, MAX_LINE_BYTES), Ok(exact));
+    }
+
+    #[test]
+    fn test_history_view() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+
+        assert!(scoreboard.history_view().is_empty());
+
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        // 无效的胜者不应产生记录
+        assert!(scoreboard.update_scores(PlayerId(999)).is_err());
+
+        let rounds = scoreboard.history_view();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].round, 1);
+        assert_eq!(rounds[0].winner_id, id1);
+        assert_eq!(rounds[0].winner_name.as_deref(), Some("玩家1"));
+        assert_eq!(rounds[1].round, 2);
+        assert_eq!(rounds[1].winner_name.as_deref(), Some("玩家2"));
+
+        // 玩家被移除后，记录仍保留序号但无法解析名称
+        scoreboard.players.remove(&id2);
+        let rounds = scoreboard.history_view();
+        assert_eq!(rounds[1].winner_id, id2);
+        assert!(rounds[1].winner_name.is_none());
+    }
+
+    #[test]
+    fn test_import_players() {
+        let mut scoreboard = Scoreboard::new();
+        let input = "张三\n\n# 注释\n李四\n张三\n  王五  \n";
+        let mut progress = Vec::new();
+        let summary = scoreboard
+            .import_players_with_progress(input.as_bytes(), |done| progress.push(done))
+            .unwrap();
+
+        assert_eq!(summary.added, 3);
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].0, 5);
+        assert_eq!(progress, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(scoreboard.players.get(&PlayerId(3)).map(String::as_str), Some("王五"));
+
+        // 再导入另一份名单：已存在的名称（按重名规则）跳过，新玩家的序号接着分配
+        scoreboard.name_policy.accent_insensitive = true;
+        scoreboard.add_player("José".to_string()).unwrap();
+        let summary = scoreboard.import_players("李四\njose\n赵六\n".as_bytes()).unwrap();
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.skipped.iter().map(|(line, _)| *line).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(scoreboard.players.get(&PlayerId(5)).map(String::as_str), Some("赵六"));
+
+        let options = parse_args(
+            ["--import-players", "a.txt", "--import-players", "-"].iter().map(|s| s.to_string()),
+        )
+        .unwrap();
+        assert_eq!(options.import_players, vec!["a.txt", "-"]);
+    }
+
+    #[test]
+    fn test_rank_scaled_penalties() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.scoring.rank_penalties = vec![2];
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+
+        // 开局所有人并列第一，未胜出者都按第一名扣 2 分
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 4);
+        assert_eq!(scoreboard.scores[&id2], -2);
+        assert_eq!(scoreboard.scores[&id3], -2);
+
+        // 领先者未胜出时被扣 2 分，其他玩家扣 1 分
+        scoreboard.update_scores(id2).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 2);
+        assert_eq!(scoreboard.scores[&id2], 1);
+        assert_eq!(scoreboard.scores[&id3], -3);
+
+        let total: i32 = scoreboard.scores.values().sum();
+        assert_eq!(total, 0);
+        assert!(scoreboard.anomalies().is_empty());
+    }
+
+    #[test]
+    fn test_custom_loser_delta() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.scoring.loser_delta = 2;
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 4);
+        assert_eq!(scoreboard.scores[&id2], -2);
+        assert_eq!(scoreboard.scores[&id3], -2);
+        assert!(scoreboard.anomalies().is_empty());
+    }
+
+    #[test]
+    fn test_update_scores_among_participants() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        assert!(scoreboard.last_participants().is_none());
+
+        scoreboard.update_scores_among(id2, &[id2, id1]).unwrap();
+        assert_eq!(scoreboard.scores[&id2], 1);
+        assert_eq!(scoreboard.scores[&id1], -1);
+        assert_eq!(scoreboard.scores[&id3], 0);
+        assert_eq!(scoreboard.last_participants(), Some(vec![id1, id2]));
+
+        // 无效的参赛组合不会记录
+        assert!(scoreboard.update_scores_among(id3, &[id1, id2]).is_err());
+        assert!(scoreboard.update_scores_among(id1, &[id1]).is_err());
+        assert!(scoreboard.update_scores_among(id1, &[id1, id1]).is_err());
+        assert!(scoreboard.update_scores_among(id1, &[id1, PlayerId(999)]).is_err());
+        scoreboard.withdraw_player(id3).unwrap();
+        assert!(scoreboard.update_scores_among(id1, &[id1, id3]).is_err());
+        assert_eq!(scoreboard.history.len(), 1);
+
+        // 普通对局记录所有仍在比赛中的玩家
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.last_participants(), Some(vec![id1, id2]));
+        assert!(scoreboard.anomalies().is_empty());
+    }
+
+    #[test]
+    fn test_parse_player_id_trims_whitespace() {
+        assert_eq!(parse_player_id(" 3 "), Ok(PlayerId(3)));
+        assert_eq!(parse_player_id("\t12\r\n"), Ok(PlayerId(12)));
+        assert_eq!(parse_player_id("   "), Err("输入不能为空！".to_string()));
+        // 数字中间的空白仍视为无效输入
+        assert!(parse_player_id(" 1 2 ").is_err());
+    }
+
+    #[test]
+    fn test_parse_player_ids() {
+        assert_eq!(parse_player_ids("1 2"), Ok(vec![PlayerId(1), PlayerId(2)]));
+        assert_eq!(parse_player_ids("3,1， 2"), Ok(vec![PlayerId(3), PlayerId(1), PlayerId(2)]));
+        assert!(parse_player_ids("").is_err());
+        assert!(parse_player_ids("1 a").is_err());
+        assert!(parse_player_ids("0").is_err());
+
+        // 录入结果时空输入表示取消，而不是报错
+        assert_eq!(parse_participants_input(" "), Ok(None));
+        assert_eq!(parse_participants_input("1,2"), Ok(Some(vec![PlayerId(1), PlayerId(2)])));
+        assert!(parse_participants_input(",").is_err());
+        assert_eq!(parse_winner_input(""), Ok(None));
+        assert_eq!(parse_winner_input(" 2 "), Ok(Some(PlayerId(2))));
+        assert!(parse_winner_input("x").is_err());
+    }
+
+    #[test]
+    fn test_streak_bonus_triggers_at_milestone() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.streak_bonus = Some(StreakBonus { milestone: 3, bonus: 1 });
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 2);
+        assert_eq!(scoreboard.current_streak(id1), 2);
+
+        // 第 3 连胜时获得奖励，奖励单独记录在对局记录中
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 4);
+        assert_eq!(scoreboard.history[2].bonus, 1);
+        assert_eq!(scoreboard.history_view()[2].bonus, 1);
+
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.history[3].bonus, 0);
+
+        // 连胜被打断后重新计算
+        scoreboard.update_scores(id2).unwrap();
+        assert_eq!(scoreboard.current_streak(id1), 0);
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.history[6].bonus, 0);
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.history[7].bonus, 1);
+
+        assert!(scoreboard.anomalies().is_empty());
+
+        // 加上奖励后超出积分范围时整局不记录，积分不变
+        scoreboard.streak_bonus = Some(StreakBonus { milestone: 1, bonus: MAX_PENALTY });
+        scoreboard.scores.insert(id1, i32::MAX - MAX_PENALTY);
+        let scores = scoreboard.scores.clone();
+        assert!(scoreboard.update_scores(id1).is_err());
+        assert_eq!(scoreboard.scores, scores);
+        assert_eq!(scoreboard.history.len(), 8);
+    }
+
+    #[test]
+    fn test_parse_streak_bonus() {
+        assert_eq!(parse_streak_bonus("3:1"), Ok(StreakBonus { milestone: 3, bonus: 1 }));
+        assert!(parse_streak_bonus("0:1").is_err());
+        assert!(parse_streak_bonus("1:2147483647").unwrap_err().contains("过大"));
+        assert!(parse_streak_bonus("1:-1001").is_err());
+        assert!(parse_streak_bonus(&format!("1:{}", MAX_PENALTY)).is_ok());
+        assert!(parse_streak_bonus("3").is_err());
+        assert!(parse_streak_bonus("a:1").is_err());
+    }
+
+    #[test]
+    fn test_split_player() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 2);
+
+        // 无效的玩家、名称或积分
+        assert!(scoreboard.split_player(PlayerId(999), "新玩家".to_string(), 1).is_err());
+        assert!(scoreboard.split_player(id1, "玩家2".to_string(), 1).is_err());
+        assert!(scoreboard.split_player(id1, "新玩家".to_string(), -1).is_err());
+        assert_eq!(scoreboard.players.len(), 2);
+
+        let new_id = scoreboard.split_player(id1, "新玩家".to_string(), 1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 1);
+        assert_eq!(scoreboard.scores[&new_id], 1);
+        assert_eq!(scoreboard.scores[&id2], -2);
+
+        // 设置下限后，不能转移超过原玩家可用的积分
+        scoreboard.min_score = Some(0);
+        assert!(scoreboard.split_player(id1, "另一位".to_string(), 2).is_err());
+        assert_eq!(scoreboard.scores[&id1], 1);
+        assert!(scoreboard.split_player(id1, "另一位".to_string(), 1).is_ok());
+        assert_eq!(scoreboard.scores[&id1], 0);
+
+        // 原玩家积分减去转移的积分时溢出，拒绝拆分且不新增玩家
+        scoreboard.min_score = None;
+        scoreboard.scores.insert(id2, i32::MIN);
+        let players_before = scoreboard.players.len();
+        let err = scoreboard.split_player(id2, "溢出".to_string(), 1).unwrap_err();
+        assert!(err.contains("超出可记录的范围"));
+        assert_eq!(scoreboard.scores[&id2], i32::MIN);
+        assert_eq!(scoreboard.players.len(), players_before);
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.strict = true;
+        let id1 = scoreboard.add_player("José".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+
+        // 近似重名被拒绝
+        let err = scoreboard.add_player("jose".to_string()).unwrap_err();
+        assert!(err.contains("严格模式"));
+
+        // 积分将被下限截断时拒绝记录
+        scoreboard.min_score = Some(0);
+        let err = scoreboard.update_scores(id1).unwrap_err();
+        assert!(err.contains("下限"));
+        assert!(scoreboard.history.is_empty());
+        assert_eq!(scoreboard.scores[&id2], 0);
+        scoreboard.min_score = None;
+
+        // 产生新的积分校验警告的操作被撤销
+        scoreboard.update_scores(id1).unwrap();
+        let err = scoreboard.split_player(id2, "新玩家".to_string(), 5).unwrap_err();
+        assert!(err.contains("严格模式"));
+        assert_eq!(scoreboard.players.len(), 2);
+        assert_eq!(scoreboard.scores[&id2], -1);
+
+        // 已有的警告不会阻止后续操作
+        *scoreboard.scores.get_mut(&id1).unwrap() = 10;
+        assert!(!scoreboard.anomalies().is_empty());
+        assert!(scoreboard.update_scores(id2).is_ok());
+
+        // 非严格模式下同样的操作可以执行
+        scoreboard.strict = false;
+        assert!(scoreboard.add_player("jose".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_min_score_floor() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.min_score = Some(-1);
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 2);
+        assert_eq!(scoreboard.scores[&id2], -1);
+    }
+
+    #[test]
+    fn test_find_command() {
+        assert_eq!(find_command("1").map(|c| c.action), Some(MenuAction::RecordWin));
+        assert_eq!(find_command("h").map(|c| c.action), Some(MenuAction::Help));
+        assert_eq!(find_command("H").map(|c| c.action), Some(MenuAction::Help));
+        assert_eq!(find_command("?").map(|c| c.action), Some(MenuAction::Help));
+        // 单键模式的快捷键在按行输入时同样可用
+        assert_eq!(find_command("w").map(|c| c.action), Some(MenuAction::RecordWin));
+        assert_eq!(find_command("Q").map(|c| c.action), Some(MenuAction::Quit));
+        assert!(find_command("").is_none());
+        assert!(find_command("99").is_none());
+
+        // 命令键和别名不能重复
+        let mut keys: Vec<&str> = MENU_COMMANDS
+            .iter()
+            .flat_map(|c| std::iter::once(c.key).chain(c.aliases.iter().copied()))
+            .collect();
+        let total = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), total);
+    }
+
+    #[test]
+    fn test_menu_input_end_quits() {
+        assert_eq!(read_menu_input(&mut "".as_bytes()), Ok(MenuInput::End));
+        let mut input = " 2 \n".as_bytes();
+        assert_eq!(read_menu_input(&mut input), Ok(MenuInput::Line("2".to_string())));
+        assert_eq!(read_menu_input(&mut input), Ok(MenuInput::End));
+        assert_eq!(find_command(quit_key()).map(|c| c.action), Some(MenuAction::Quit));
+    }
+
+    #[test]
+    fn test_recompute_scores() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.scoring.rank_penalties = vec![2];
+        scoreboard.streak_bonus = Some(StreakBonus { milestone: 2, bonus: 1 });
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.update_scores_among(b, &[b, c]).unwrap();
+        scoreboard.update_scores_weighted(c, 2).unwrap();
+        let expected = scoreboard.clone();
+
+        // 与记录一致时没有差异
+        assert!(scoreboard.recompute_scores().unwrap().is_empty());
+        assert_eq!(scoreboard.scores, expected.scores);
+
+        // 手动改坏的积分按记录修正，并报告差异
+        scoreboard.scores.insert(b, 100);
+        let diffs = scoreboard.recompute_scores().unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].name, "B");
+        assert_eq!(diffs[0].before, Some(100));
+        assert_eq!(diffs[0].after, Some(expected.scores[&b]));
+        assert_eq!(scoreboard.scores, expected.scores);
+        assert_eq!(scoreboard.history.last().unwrap().scores_after, expected.scores);
+
+        // 旧存档中没有参赛玩家的记录无法重算
+        scoreboard.history[0].participants.clear();
+        let err = scoreboard.recompute_scores().unwrap_err();
+        assert!(err.contains("第 1 局"), "{}", err);
+    }
+
+    #[test]
+    fn test_ascii_mode_uses_ascii_borders() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.table_style = TableStyle::Unicode;
+        let options = CliOptions { ascii: true, ..CliOptions::default() };
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert_eq!(scoreboard.table_style, TableStyle::Ascii);
+
+        let chars = scoreboard.table_style.box_chars().unwrap();
+        let table = render_box_table(&chars, &["id", "score"], &[vec!["1".to_string(), "+3".to_string()]]);
+        assert!(table.is_ascii(), "{}", table);
+
+        // plain 样式本身只用 '-'，保持不变
+        let mut scoreboard = Scoreboard::new();
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert_eq!(scoreboard.table_style, TableStyle::Plain);
+    }
+
+    #[test]
+    fn test_generate_bracket() {
+        let mut scoreboard = Scoreboard::new();
+        let ids: Vec<PlayerId> = (1..=6)
+            .map(|n| scoreboard.add_player(format!("P{}", n)).unwrap())
+            .collect();
+        // 积分依次递减，排名即种子顺序
+        for (index, id) in ids.iter().enumerate() {
+            scoreboard.scores.insert(*id, 10 - index as i32);
+        }
+        let seed = |slot: Option<(PlayerId, &str)>| slot.map(|(id, _)| id.get());
+
+        let bracket = scoreboard.generate_bracket(4);
+        let pairs: Vec<_> = bracket.into_iter().map(|(a, b)| (seed(a), seed(b))).collect();
+        assert_eq!(pairs, vec![(Some(1), Some(4)), (Some(2), Some(3))]);
+
+        // 6 人补足到 8 人，前两号种子轮空
+        let bracket = scoreboard.generate_bracket(6);
+        let pairs: Vec<_> = bracket.into_iter().map(|(a, b)| (seed(a), seed(b))).collect();
+        assert_eq!(
+            pairs,
+            vec![(Some(1), None), (Some(4), Some(5)), (Some(2), None), (Some(3), Some(6))]
+        );
+
+        // 已退出的玩家不参加淘汰赛，人数不足时不生成对阵
+        scoreboard.withdraw_player(ids[0]).unwrap();
+        let bracket = scoreboard.generate_bracket(2);
+        assert_eq!(seed(bracket[0].0), Some(2));
+        assert_eq!(seed(bracket[0].1), Some(3));
+        assert!(scoreboard.generate_bracket(1).is_empty());
+    }
+
+    #[test]
+    fn test_score_relative_to_mean() {
+        let mut scoreboard = Scoreboard::new();
+        assert_eq!(scoreboard.mean_score(), None);
+        assert_eq!(scoreboard.score_relative_to_mean(PlayerId(1)), None);
+
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.scores.insert(a, 4);
+        scoreboard.scores.insert(b, -1);
+        scoreboard.scores.insert(c, 0);
+        assert_eq!(scoreboard.score_relative_to_mean(a), Some(3.0));
+        assert_eq!(scoreboard.score_relative_to_mean(b), Some(-2.0));
+        assert_eq!(scoreboard.relative_to_mean_text(a), "+3");
+        assert_eq!(scoreboard.relative_to_mean_text(c), "-1");
+        assert_eq!(scoreboard.score_relative_to_mean(PlayerId(9)), None);
+
+        scoreboard.scores.insert(c, 1);
+        assert_eq!(scoreboard.relative_to_mean_text(c), "-0.33");
+    }
+
+    #[test]
+    fn test_clear_history_keeps_scores_and_stats() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.streak_bonus = Some(StreakBonus { milestone: 3, bonus: 1 });
+        scoreboard.update_scores(b).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        let scores = scoreboard.scores.clone();
+
+        assert_eq!(scoreboard.clear_history(), 3);
+        assert!(scoreboard.history.is_empty());
+        assert_eq!(scoreboard.scores, scores);
+        assert_eq!(scoreboard.rounds_played(), 3);
+        assert_eq!(scoreboard.wins(a), 2);
+        assert_eq!(scoreboard.current_streak(a), 2);
+        assert!(scoreboard.anomalies().is_empty());
+
+        // 局数和连胜在清空后继续累计
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.history[0].round, 4);
+        assert_eq!(scoreboard.history[0].bonus, 1);
+        assert!(scoreboard.anomalies().is_empty());
+        assert_eq!(scoreboard.clear_history(), 1);
+        assert_eq!(scoreboard.clear_history(), 0);
+        assert_eq!(scoreboard.wins(a), 3);
+    }
+
+    #[test]
+    fn test_target_score() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.target_score = Some(4);
+
+        // 胜者得 2 分，未达到目标
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.take_target_announcement(), None);
+
+        // 积分恰好等于目标积分时即为达到
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.scores[&a], 4);
+        assert_eq!(scoreboard.players_at_target(), vec![a]);
+        let message = scoreboard.take_target_announcement().unwrap();
+        assert!(message.contains("A 率先达到目标积分 4"), "{}", message);
+        // 每次达到只宣布一次，之后的对局不重复宣布已达到的玩家
+        assert_eq!(scoreboard.take_target_announcement(), None);
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.take_target_announcement(), None);
+
+        // 开启后不再接受新的对局
+        scoreboard.stop_at_target = true;
+        let error = scoreboard.update_scores(b).unwrap_err();
+        assert!(error.contains("比赛已结束"), "{}", error);
+        assert_eq!(scoreboard.history.len(), 3);
+    }
+
+    #[test]
+    fn test_session_deadline() {
+        // 2024-01-01 22:30:00 UTC
+        let now = 1_704_148_200;
+        let end = ClockTime::parse("23:00").unwrap();
+        assert_eq!(end.next_after(now), now + 30 * 60);
+        // 已过今天的该时刻时取次日，跨越午夜
+        let end = ClockTime::parse("01:00").unwrap();
+        assert_eq!(end.next_after(now), now + 150 * 60);
+        // 时区偏移: 22:30 UTC 即 06:30+08:00
+        let end = ClockTime::parse("07:00+08:00").unwrap();
+        assert_eq!(end.next_after(now), now + 30 * 60);
+        assert!(ClockTime::parse("24:00").is_err());
+        assert!(ClockTime::parse("20:60").is_err());
+        assert!(ClockTime::parse("8pm").is_err());
+
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.session_deadline = Some(u64::MAX);
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.take_session_end_announcement(), None);
+
+        // 时间到后记录的一局为最后一局，只宣布一次
+        scoreboard.session_deadline = Some(0);
+        scoreboard.update_scores(b).unwrap();
+        let message = scoreboard.take_session_end_announcement().unwrap();
+        assert!(message.contains("共进行 2 局"), "{}", message);
+        assert_eq!(scoreboard.take_session_end_announcement(), None);
+        let error = scoreboard.update_scores(a).unwrap_err();
+        assert!(error.contains("比赛时间已到"), "{}", error);
+        assert_eq!(scoreboard.history.len(), 2);
+    }
+
+    #[test]
+    fn test_existing_ids_with_gaps() {
+        let mut scoreboard = Scoreboard::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            scoreboard.add_player(name.to_string()).unwrap();
+        }
+        scoreboard.players.remove(&PlayerId(3));
+        scoreboard.players.remove(&PlayerId(4));
+        scoreboard.reconcile();
+
+        assert_eq!(scoreboard.existing_ids(), vec![PlayerId(1), PlayerId(2), PlayerId(5)]);
+        let error = scoreboard.update_scores(PlayerId(3)).unwrap_err();
+        assert_eq!(error, "玩家序号 3 不存在（现有序号: 1, 2, 5）");
+        assert!(scoreboard.update_scores(PlayerId(5)).is_ok());
+        assert_eq!(winner_prompt(&scoreboard), "请输入胜出玩家的序号（1, 2, 5，直接回车取消）: ");
+    }
+
+    #[test]
+    fn test_points_behind_leader() {
+        let mut scoreboard = Scoreboard::new();
+        assert_eq!(scoreboard.points_behind_leader(PlayerId(1)), None);
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        assert_eq!(scoreboard.points_behind_leader(id1), Some(0));
+
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.points_behind_leader(id1), Some(0));
+        assert_eq!(scoreboard.points_behind_leader(id2), Some(3));
+
+        // 并列领先时差距均为 0
+        scoreboard.update_scores(id2).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.scores[&id1], scoreboard.scores[&id2]);
+        assert_eq!(scoreboard.points_behind_leader(id1), Some(0));
+        assert_eq!(scoreboard.points_behind_leader(id2), Some(0));
+        assert_eq!(scoreboard.points_behind_leader(id3), Some(6));
+        assert_eq!(scoreboard.points_behind_leader(PlayerId(99)), None);
+    }
+
+    #[test]
+    fn test_write_report_file() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.add_player("A".to_string()).unwrap();
+        let path = write_report_file(&scoreboard).unwrap();
+        assert!(path.starts_with(std::env::temp_dir()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), scoreboard.generate_report());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_file_lock() {
+        let path = std::env::temp_dir().join(format!("scoreboard_lock_{}.json", std::process::id()));
+        let lock = FileLock::acquire(&path).ok().unwrap();
+        assert!(FileLock::lock_path(&path).exists());
+
+        // 第二次加锁失败，并能读到持有者信息
+        let holder = FileLock::acquire(&path).err().unwrap();
+        assert_eq!(holder.pid, Some(std::process::id()));
+        assert!(holder.locked_at.is_some());
+        if Path::new("/proc").exists() {
+            assert_eq!(holder.is_alive(), Some(true));
+        }
+
+        // 释放后可以重新加锁
+        drop(lock);
+        assert!(!FileLock::lock_path(&path).exists());
+        let lock = FileLock::acquire(&path).ok().unwrap();
+
+        // 残留的锁可以被强制接管
+        std::mem::forget(lock);
+        let lock = FileLock::force_acquire(&path).unwrap();
+        drop(lock);
+        assert!(!FileLock::lock_path(&path).exists());
+    }
+
+    #[test]
+    fn test_autosave_interval() {
+        let mut workspace = Workspace::new(Scoreboard::new());
+        let path = std::env::temp_dir().join(format!("scoreboard_autosave_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut autosave = AutoSave::new(2);
+
+        workspace.active_board_mut().add_player("张三".to_string()).unwrap();
+        autosave.record_operation();
+        assert!(autosave.run_if_due(&workspace, &path).is_none());
+        assert!(!path.exists());
+
+        workspace.active_board_mut().add_player("李四".to_string()).unwrap();
+        autosave.record_operation();
+        assert!(autosave.run_if_due(&workspace, &path).unwrap().is_ok());
+        let (loaded, _) = Workspace::load_from_file(&path).unwrap();
+        assert_eq!(loaded.active_board().players.len(), 2);
+
+        // 保存后重新计数；手动保存同样重新计数
+        autosave.record_operation();
+        assert!(autosave.run_if_due(&workspace, &path).is_none());
+        autosave.reset();
+        autosave.record_operation();
+        assert!(autosave.run_if_due(&workspace, &path).is_none());
+        fs::remove_file(&path).unwrap();
+
+        // 写入失败时返回错误而不是中断
+        let mut autosave = AutoSave::new(1);
+        autosave.record_operation();
+        let bad_path = std::env::temp_dir().join("scoreboard_no_such_dir").join("save.json");
+        assert!(autosave.run_if_due(&workspace, &bad_path).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_input_history() {
+        let path = std::env::temp_dir().join(format!("scoreboard_history_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = InputHistory::load(path.clone());
+        assert!(history.entries.is_empty());
+        history.record("张三").unwrap();
+        history.record("张三").unwrap();
+        history.record("").unwrap();
+        history.record("1").unwrap();
+        assert_eq!(history.entries, vec!["张三", "1"]);
+
+        // 下次会话可以读到之前的输入
+        let mut history = InputHistory::load(path.clone());
+        assert_eq!(history.recent(1), ["1".to_string()]);
+
+        // 超过上限时只保留最近的条目
+        for i in 0..MAX_HISTORY_ENTRIES + 10 {
+            history.record(&i.to_string()).unwrap();
+        }
+        let history = InputHistory::load(path.clone());
+        assert_eq!(history.entries.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(history.entries.last().unwrap(), &(MAX_HISTORY_ENTRIES + 9).to_string());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_scoring_rule_validation() {
+        assert!(ScoringRule::default().validate().is_ok());
+        let rule = |loser_delta, rank_penalties: &[i32]| ScoringRule {
+            loser_delta,
+            rank_penalties: rank_penalties.to_vec(),
+        };
+        // 只对领先者扣分是允许的
+        assert!(rule(0, &[1]).validate().is_ok());
+        assert!(rule(1, &[3, 2]).validate().is_ok());
+
+        assert!(rule(-1, &[]).validate().unwrap_err().contains("负数"));
+        assert!(rule(1, &[2, -1]).validate().unwrap_err().contains("负数"));
+        assert!(rule(0, &[]).validate().unwrap_err().contains("都为 0"));
+        assert!(rule(0, &[0, 0]).validate().unwrap_err().contains("都为 0"));
+        assert!(rule(MAX_PENALTY + 1, &[]).validate().unwrap_err().contains("过大"));
+
+        // 通过命令行设置时被拒绝，原规则保持不变
+        let mut scoreboard = Scoreboard::new();
+        let options = CliOptions { loser_delta: Some(0), ..CliOptions::default() };
+        assert!(apply_options(&options, &mut scoreboard).is_err());
+        assert_eq!(scoreboard.scoring, ScoringRule::default());
+        let options = CliOptions {
+            loser_delta: Some(0),
+            rank_penalties: Some(vec![2]),
+            ..CliOptions::default()
+        };
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert_eq!(scoreboard.scoring, rule(0, &[2]));
+
+        // 加载的存档中规则不合理时给出警告
+        scoreboard.scoring = rule(0, &[]);
+        assert!(scoreboard.reconcile().iter().any(|w| w.contains("积分规则不合理")));
+    }
+
+    #[test]
+    fn test_auto_display_option() {
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.auto_display);
+        let options = CliOptions { auto_display: Some(false), ..CliOptions::default() };
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert!(!scoreboard.auto_display);
+        let options = CliOptions { auto_display: Some(true), ..CliOptions::default() };
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert!(scoreboard.auto_display);
+
+        // 不保存到文件，加载后默认开启
+        scoreboard.auto_display = false;
+        let json = serde_json::to_string(&scoreboard).unwrap();
+        assert!(!json.contains("auto_display"));
+        let loaded: Scoreboard = serde_json::from_str(&json).unwrap();
+        assert!(loaded.auto_display);
+    }
+
+    #[test]
+    fn test_compact_ids() {
+        let mut scoreboard = Scoreboard::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            scoreboard.add_player(name.to_string()).unwrap();
+        }
+        scoreboard.update_scores(PlayerId(3)).unwrap();
+        scoreboard.update_scores(PlayerId(5)).unwrap();
+        scoreboard.pin_player(PlayerId(5), 1).unwrap();
+        scoreboard.withdraw_player(PlayerId(4)).unwrap();
+        scoreboard.freeze("中场".to_string());
+        // 删除玩家 B 和 C，C 仍被对局记录引用
+        scoreboard.players.remove(&PlayerId(2));
+        scoreboard.players.remove(&PlayerId(3));
+        scoreboard.reconcile();
+        let scores_by_name: Vec<(String, i32)> =
+            scoreboard.ranked().into_iter().map(|(_, name, score)| (name.to_string(), score)).collect();
+
+        let mapping = scoreboard.compact_ids();
+        assert_eq!(mapping[&PlayerId(1)], PlayerId(1));
+        assert_eq!(mapping[&PlayerId(4)], PlayerId(2));
+        assert_eq!(mapping[&PlayerId(5)], PlayerId(3));
+        assert_eq!(scoreboard.existing_ids(), vec![PlayerId(1), PlayerId(2), PlayerId(3)]);
+        assert_eq!(scoreboard.players[&PlayerId(3)], "E");
+
+        // 积分、置顶、退出状态和冻结快照都跟随玩家
+        let after: Vec<(String, i32)> =
+            scoreboard.ranked().into_iter().map(|(_, name, score)| (name.to_string(), score)).collect();
+        assert_eq!(after, scores_by_name);
+        assert_eq!(scoreboard.pins.get(&PlayerId(3)), Some(&1));
+        assert!(scoreboard.is_withdrawn(PlayerId(2)));
+        assert!(scoreboard.frozen.as_ref().unwrap().scores.contains_key(&PlayerId(3)));
+
+        // 对局记录中的胜者跟随玩家，已删除的玩家排在现有玩家之后
+        assert_eq!(scoreboard.history[1].winner_id, PlayerId(3));
+        assert!(scoreboard.history[1].participants.contains(&PlayerId(2)));
+        let removed_c = mapping[&PlayerId(3)];
+        assert!(removed_c.get() > 3);
+        assert_eq!(scoreboard.history[0].winner_id, removed_c);
+        assert_eq!(scoreboard.next_id, mapping.len() + 1);
+        assert!(!scoreboard.players.contains_key(&removed_c));
+
+        // 新玩家不会与旧记录中的序号冲突
+        let new_id = scoreboard.add_player("F".to_string()).unwrap();
+        assert!(!mapping.values().any(|id| *id == new_id));
+
+        // 没有引用已删除玩家的记录时，下一个序号为 N+1
+        let mut scoreboard = Scoreboard::new();
+        for name in ["A", "B", "C"] {
+            scoreboard.add_player(name.to_string()).unwrap();
+        }
+        scoreboard.players.remove(&PlayerId(1));
+        scoreboard.reconcile();
+        scoreboard.compact_ids();
+        assert_eq!(scoreboard.players[&PlayerId(1)], "B");
+        assert_eq!(scoreboard.next_id, 3);
+    }
+
+    #[test]
+    fn test_rank_deltas() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        // 尚未显示过积分榜时没有名次变化
+        assert!(scoreboard.rank_deltas().is_empty());
+
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.display_scoreboard();
+        scoreboard.update_scores(b).unwrap();
+        scoreboard.update_scores(b).unwrap();
+        let deltas = scoreboard.rank_deltas();
+        assert_eq!(deltas[&b], 1);
+        assert_eq!(deltas[&a], -1);
+        assert_eq!(deltas[&c], -1);
+        assert_eq!(scoreboard.rank_change_text(1), "▲1");
+        assert_eq!(scoreboard.rank_change_text(-2), "▼2");
+        assert_eq!(scoreboard.rank_change_text(0), "=");
+
+        // 显示后以本次名次为基准
+        scoreboard.display_scoreboard();
+        assert!(scoreboard.rank_deltas().values().all(|delta| *delta == 0));
+    }
+
+    #[test]
+    fn test_size_accessors() {
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.is_empty());
+        assert_eq!(scoreboard.player_count(), 0);
+        assert_eq!(scoreboard.rounds_played(), 0);
+        // 没有玩家时不能记录对局
+        assert!(scoreboard.update_scores(PlayerId(1)).unwrap_err().contains("没有玩家"));
+
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.withdraw_player(b).unwrap();
+        assert!(!scoreboard.is_empty());
+        // 已退出的玩家仍计入人数
+        assert_eq!(scoreboard.player_count(), 3);
+
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.rounds_played(), 2);
+        scoreboard.clear_history();
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.rounds_played(), 3);
+    }
+
+    #[test]
+    fn test_empty_board() {
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.ranked().is_empty());
+        assert!(scoreboard.ranks().is_empty());
+        assert!(scoreboard.leaders().is_empty());
+        assert!(scoreboard.display_order().is_empty());
+        assert!(scoreboard.shuffle_order(Some(1)).is_empty());
+        assert!(scoreboard.anomalies().is_empty());
+        assert_eq!(scoreboard.overlay_text(), "暂无玩家\n");
+        assert_eq!(scoreboard.most_pivotal_round(), None);
+        assert!(scoreboard.generate_report().contains("玩家人数: 0"));
+        assert!(scoreboard.diff(&Scoreboard::new()).is_empty());
+        scoreboard.display_scoreboard();
+        scoreboard.list_players();
+
+        let err = scoreboard.update_scores(PlayerId(1)).unwrap_err();
+        assert!(err.contains("没有玩家"));
+        assert!(scoreboard.update_scores_among(PlayerId(1), &[PlayerId(1), PlayerId(2)]).unwrap_err().contains("没有玩家"));
+
+        let mut svg = Vec::new();
+        scoreboard.export_svg(&mut svg).unwrap();
+        assert!(!String::from_utf8(svg).unwrap().contains("<polyline"));
+        let mut csv = Vec::new();
+        scoreboard.export_csv(&mut csv).unwrap();
+        assert_eq!(csv, b"id,name,score\n");
+
+        scoreboard.freeze("空".to_string());
+        assert!(scoreboard.frozen_comparison().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_weighted_round() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+
+        scoreboard.update_scores_weighted(id1, 2).unwrap();
+        assert_eq!(scoreboard.scores[&id1], 4);
+        assert_eq!(scoreboard.scores[&id2], -2);
+        assert_eq!(scoreboard.scores[&id3], -2);
+        assert_eq!(scoreboard.history[0].weight, 2);
+
+        // 默认权重为 1，行为不变
+        scoreboard.update_scores(id2).unwrap();
+        assert_eq!(scoreboard.history[1].weight, 1);
+        assert_eq!(scoreboard.scores[&id2], 0);
+        assert!(scoreboard.anomalies().is_empty());
+
+        assert!(scoreboard.update_scores_weighted(id1, 0).is_err());
+        assert_eq!(scoreboard.history.len(), 2);
+
+        // 权重过大时拒绝记录，而不是溢出
+        let before = scoreboard.scores.clone();
+        let err = scoreboard.update_scores_weighted(id1, 1_500_000_000).unwrap_err();
+        assert!(err.contains("本局权重过大"));
+        assert!(scoreboard.update_scores_weighted(id1, MAX_WEIGHT + 1).is_err());
+        // 权重在上限内、但累计积分接近 i32 范围时同样报错，积分保持不变
+        scoreboard.scores.insert(id2, i32::MIN + 1);
+        let before_overflow = scoreboard.scores.clone();
+        let err = scoreboard.update_scores_weighted(id1, MAX_WEIGHT).unwrap_err();
+        assert!(err.contains("本局权重过大"));
+        assert_eq!(scoreboard.scores, before_overflow);
+        assert_eq!(scoreboard.history.len(), 2);
+        scoreboard.scores = before;
+        scoreboard.min_score = Some(0);
+        scoreboard.strict = true;
+        scoreboard.scores.insert(id2, 1000);
+        scoreboard.scores.insert(id3, i32::MIN + 1);
+        assert!(scoreboard.update_scores_weighted(id1, MAX_WEIGHT).unwrap_err().contains("本局权重过大"));
+
+        // 与权重无关的溢出（如导入了极端积分）只提示积分超出范围，且不修改积分板
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("a".to_string()).unwrap();
+        scoreboard.add_player("b".to_string()).unwrap();
+        scoreboard.scores.insert(a, i32::MAX);
+        let err = scoreboard.update_scores(a).unwrap_err();
+        assert_eq!(err, "积分超出可记录的范围");
+        assert!(!scoreboard.update_scores_weighted(a, 2).unwrap_err().contains("权重"));
+        // 副积分溢出时主积分同样保持不变
+        scoreboard.scores.insert(a, 0);
+        scoreboard.secondary = Some(SecondaryScore { label: "筹码".to_string(), per_win: 1 });
+        scoreboard.secondary_scores.insert(a, i32::MAX);
+        let scores = scoreboard.scores.clone();
+        assert!(scoreboard.update_scores(a).unwrap_err().contains("筹码超出可记录的范围"));
+        assert_eq!(scoreboard.scores, scores);
+        assert_eq!(scoreboard.secondary_scores[&a], i32::MAX);
+        assert!(scoreboard.history.is_empty());
+    }
+
+    #[test]
+    fn test_round_timing() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        scoreboard.add_player("玩家2".to_string()).unwrap();
+
+        // 未开启计时时不记录用时
+        scoreboard.update_scores(id1).unwrap();
+        assert!(scoreboard.history[0].duration.is_none());
+        assert!(scoreboard.average_round_duration().is_none());
+
+        scoreboard.start_round_timer();
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert!(scoreboard.history[1].duration.is_some());
+        assert!(scoreboard.history[2].duration.is_some());
+        assert!(scoreboard.average_round_duration().is_some());
+    }
+
+    #[test]
+    fn test_round_timer_pause() {
+        let mut timer = RoundTimer::start();
+        timer.pause();
+        assert!(timer.is_paused());
+        let paused_at = timer.elapsed();
+        std::thread::sleep(Duration::from_millis(20));
+        // 暂停期间用时不增长
+        assert_eq!(timer.elapsed(), paused_at);
+
+        timer.resume();
+        assert!(!timer.is_paused());
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(timer.elapsed() > paused_at);
+
+        timer.lap();
+        assert!(!timer.is_paused());
+        assert!(timer.elapsed() < Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_ranks_with_ties() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+
+        let ranks = scoreboard.ranks();
+        assert_eq!(ranks[&id2], 1);
+        assert_eq!(ranks[&id1], 2);
+        assert_eq!(ranks[&id3], 2);
+        let order: Vec<PlayerId> = scoreboard.ranked().iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(order, vec![id2, id1, id3]);
+    }
+
+    #[test]
+    fn test_tie_break_by_wins() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.tie_break = TieBreak::Wins;
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        // 手动调整为同分，玩家2 胜场更多
+        *scoreboard.scores.get_mut(&id1).unwrap() = 2;
+
+        let order: Vec<PlayerId> = scoreboard.ranked().iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(order, vec![id2, id1, id3]);
+        let ranks = scoreboard.ranks();
+        assert_eq!((ranks[&id2], ranks[&id1], ranks[&id3]), (1, 2, 3));
+    }
+
+    #[test]
+    fn test_fully_tied_order_is_deterministic() {
+        let names = ["戊", "乙", "丁", "甲", "丙"];
+        let expected: Vec<PlayerId> = (1..=names.len()).map(PlayerId).collect();
+
+        for tie_break in [TieBreak::None, TieBreak::Wins] {
+            // 每次新建的 HashMap 迭代顺序不同，排序结果必须始终一致
+            for _ in 0..20 {
+                let mut scoreboard = Scoreboard::new();
+                scoreboard.tie_break = tie_break;
+                for name in names {
+                    scoreboard.add_player(name.to_string()).unwrap();
+                }
+                for _ in 0..3 {
+                    let order: Vec<PlayerId> =
+                        scoreboard.ranked().iter().map(|(id, _, _)| *id).collect();
+                    assert_eq!(order, expected);
+                    let display: Vec<PlayerId> =
+                        scoreboard.display_order().iter().map(|(id, _, _)| *id).collect();
+                    assert_eq!(display, expected);
+                }
+                assert!(scoreboard.ranks().values().all(|rank| *rank == 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_overlay_text() {
+        let mut scoreboard = Scoreboard::new();
+        assert_eq!(scoreboard.overlay_text(), "暂无玩家\n");
+
+        let id1 = scoreboard.add_player("张三".to_string()).unwrap();
+        let id2 = scoreboard.add_player("李四".to_string()).unwrap();
+        scoreboard.add_player("王五".to_string()).unwrap();
+        assert_eq!(scoreboard.overlay_text(), "张三 / 李四 / 王五 0分\n");
+
+        scoreboard.update_scores(id2).unwrap();
+        assert_eq!(scoreboard.overlay_text(), "李四 2分\n");
+        scoreboard.update_scores(id1).unwrap();
+        assert_eq!(scoreboard.overlay_text(), "张三 / 李四 1分\n");
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let path = std::env::temp_dir().join(format!("scoreboard_overlay_{}.txt", std::process::id()));
+        write_atomic(&path, "张三 3分\n").unwrap();
+        write_atomic(&path, "李四 4分\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "李四 4分\n");
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        assert!(!Path::new(&tmp).exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pin_player_affects_display_only() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+
+        assert!(scoreboard.pin_player(PlayerId(999), 1).is_err());
+        scoreboard.pin_player(id3, 1).unwrap();
+        scoreboard.pin_player(id1, -1).unwrap();
+
+        let order: Vec<PlayerId> = scoreboard.display_order().iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(order, vec![id3, id2, id1]);
+        // 名次和积分不受影响
+        assert_eq!(scoreboard.ranks()[&id1], 1);
+        assert_eq!(scoreboard.scores[&id1], 2);
+
+        scoreboard.unpin_player(id3).unwrap();
+        scoreboard.pin_player(id1, 0).unwrap();
+        let order: Vec<PlayerId> = scoreboard.display_order().iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(order, vec![id1, id2, id3]);
+    }
+
+    #[test]
+    fn test_anomalies() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        scoreboard.add_player("玩家3".to_string()).unwrap();
+
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        assert!(scoreboard.anomalies().is_empty());
+
+        // 得分超出两局可能的范围，且总和不为 0
+        *scoreboard.scores.get_mut(&id1).unwrap() = 10;
+        let warnings = scoreboard.anomalies();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("玩家1"));
+
+        *scoreboard.scores.get_mut(&id1).unwrap() = 4;
+        *scoreboard.scores.get_mut(&id2).unwrap() = -5;
+        assert!(scoreboard.anomalies()[0].contains("玩家2"));
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("张三".to_string()).unwrap();
+        scoreboard.add_player("Smith, \"J\"".to_string()).unwrap();
+        scoreboard.add_player("李四".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+
+        let mut output = Vec::new();
+        scoreboard.export_csv(&mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        assert!(csv.contains("2,\"Smith, \"\"J\"\"\",-1"));
+
+        let imported = Scoreboard::import_csv(csv.as_bytes(), NamePolicy::default()).unwrap();
+        assert_eq!(imported.players, scoreboard.players);
+        assert_eq!(imported.scores, scoreboard.scores);
+        assert_eq!(imported.next_id, 4);
+    }
+
+    #[test]
+    fn test_import_kv() {
+        let input = "# 第一周积分\n张三=3\n\n 李四 = -1 \n王五=0\n";
+        let imported = Scoreboard::import_kv(input.as_bytes(), NamePolicy::default()).unwrap();
+        assert_eq!(imported.players[&PlayerId(1)], "张三");
+        assert_eq!(imported.scores[&PlayerId(2)], -1);
+        assert_eq!(imported.players[&PlayerId(2)], "李四");
+        assert_eq!(imported.scores[&PlayerId(3)], 0);
+        assert_eq!(imported.next_id, 4);
+
+        let err = Scoreboard::import_kv("张三=3\n李四\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.starts_with("第 2 行"), "{}", err);
+        let err = Scoreboard::import_kv("张三=abc\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.contains("无效的积分"), "{}", err);
+        let err = Scoreboard::import_kv("张三=1\n# 注释\n张三=2\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.starts_with("第 3 行") && err.contains("重复"), "{}", err);
+        assert!(Scoreboard::import_kv("=1\n".as_bytes(), NamePolicy::default()).is_err());
+    }
+
+    #[test]
+    fn test_imported_scores_are_baseline() {
+        // 导入的积分作为起点，没有对局记录也不产生校验警告
+        let mut imported = Scoreboard::import_kv("a=5\nb=-5\n".as_bytes(), NamePolicy::default()).unwrap();
+        assert!(imported.anomalies().is_empty(), "{:?}", imported.anomalies());
+        let csv = Scoreboard::import_csv("id,name,score\n3,a,7\n".as_bytes(), NamePolicy::default()).unwrap();
+        assert!(csv.anomalies().is_empty(), "{:?}", csv.anomalies());
+
+        // 导入后的对局按起点之后的变化校验，重算积分时也从导入的积分开始
+        imported.update_scores(PlayerId(1)).unwrap();
+        assert_eq!(imported.scores[&PlayerId(1)], 6);
+        assert!(imported.anomalies().is_empty(), "{:?}", imported.anomalies());
+        assert!(imported.recompute_scores().unwrap().is_empty());
+        assert_eq!(imported.scores[&PlayerId(2)], -6);
+
+        // 超出起点之后可能范围的积分仍会被发现
+        imported.scores.insert(PlayerId(1), 20);
+        imported.scores.insert(PlayerId(2), -20);
+        assert_eq!(imported.anomalies().len(), 2, "{:?}", imported.anomalies());
+    }
+
+    #[test]
+    fn test_import_csv_errors() {
+        let imported = Scoreboard::import_csv("id,name,score\n5,张三,3\n2,李四,-1\n".as_bytes(), NamePolicy::default()).unwrap();
+        assert_eq!(imported.next_id, 6);
+
+        let err = Scoreboard::import_csv("id,name,score\nx,张三,3\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.contains("第 2 行"));
+        let err = Scoreboard::import_csv("1,张三,3\n2,李四,abc\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.contains("第 2 行"));
+        assert!(Scoreboard::import_csv("1,张三,3\n1,李四,0\n".as_bytes(), NamePolicy::default()).is_err());
+        assert!(Scoreboard::import_csv("1,\"张三,3\n".as_bytes(), NamePolicy::default()).is_err());
+        assert!(Scoreboard::import_csv("1,张三\n".as_bytes(), NamePolicy::default()).is_err());
+
+        // 过大的序号报错而不是溢出
+        let err = Scoreboard::import_csv("18446744073709551615,a,1\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.contains("第 1 行"), "{}", err);
+        let err = Scoreboard::import_csv("1,a,1\n1001,b,0\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.contains("第 2 行") && err.contains("过大"), "{}", err);
+        // 名称按录入玩家的规则校验：控制字符、超长名称和名称策略
+        let err = Scoreboard::import_csv("1,\"a\tb\",1\n".as_bytes(), NamePolicy::default()).err().unwrap();
+        assert!(err.contains("第 1 行") && err.contains("控制字符"), "{}", err);
+        let long = format!("1,{},1\n", "长".repeat(21));
+        assert!(Scoreboard::import_csv(long.as_bytes(), NamePolicy::default()).is_err());
+        let numeric = NamePolicy { reject_numeric: true, accent_insensitive: false };
+        assert!(Scoreboard::import_csv("1,123,1\n".as_bytes(), numeric.clone()).is_err());
+        assert!(Scoreboard::import_kv("123=1\n".as_bytes(), numeric).is_err());
+        assert!(Scoreboard::import_csv("1,123,1\n".as_bytes(), NamePolicy::default()).is_ok());
+    }
+
+    #[test]
+    fn test_workspace_boards() {
+        let mut workspace = Workspace::new(Scoreboard::new());
+        assert_eq!(workspace.active_name(), "默认");
+
+        assert!(workspace.add_board("默认".to_string(), Scoreboard::new()).is_err());
+        assert!(workspace.add_board("".to_string(), Scoreboard::new()).is_err());
+        assert_eq!(workspace.add_board("二号桌".to_string(), Scoreboard::new()), Ok(2));
+        assert_eq!(workspace.active, 1);
+
+        workspace.active_board_mut().add_player("玩家1".to_string()).unwrap();
+        workspace.switch_to(1).unwrap();
+        assert!(workspace.active_board().players.is_empty());
+        assert!(workspace.switch_to(0).is_err());
+        assert!(workspace.switch_to(3).is_err());
+
+        assert!(workspace.rename_active("二号桌".to_string()).is_err());
+        workspace.rename_active("一号桌".to_string()).unwrap();
+        assert_eq!(workspace.active_name(), "一号桌");
+    }
+
+    #[test]
+    fn test_workspace_save_and_load() {
+        let mut workspace = Workspace::new(Scoreboard::new());
+        let board = workspace.active_board_mut();
+        let id1 = board.add_player("张三".to_string()).unwrap();
+        board.add_player("李四".to_string()).unwrap();
+        board.update_scores(id1).unwrap();
+        board.min_score = Some(-5);
+        let mut second = Scoreboard::new();
+        second.add_player("王五".to_string()).unwrap();
+        workspace.add_board("二号桌".to_string(), second).unwrap();
+
+        let path = std::env::temp_dir().join(format!("scoreboard_test_{}.json", std::process::id()));
+        workspace.save_to_file(&path).unwrap();
+        let (loaded, warnings) = Workspace::load_from_file(&path).unwrap();
+        assert!(warnings.is_empty());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.boards.len(), 2);
+        assert_eq!(loaded.active, 1);
+        assert_eq!(loaded.boards[1].name, "二号桌");
+        let first = &loaded.boards[0].board;
+        assert_eq!(first.players, workspace.boards[0].board.players);
+        assert_eq!(first.scores, workspace.boards[0].board.scores);
+        assert_eq!(first.history.len(), 1);
+        assert_eq!(first.next_id, 3);
+        assert_eq!(first.min_score, Some(-5));
+    }
+
+    #[test]
+    fn test_load_format_versions() {
+        // 版本 1 的存档没有 version 字段，也没有之后新增的字段
+        let v1 = r#"{
+            "boards": [{
+                "name": "默认",
+                "board": {
+                    "players": {"1": "张三", "2": "李四"},
+                    "scores": {"1": 1, "2": -1},
+                    "next_id": 3,
+                    "history": [{"round": 1, "timestamp": 1700000000, "winner_id": 1, "duration": null}],
+                    "min_score": null,
+                    "name_policy": {"reject_numeric": false},
+                    "pins": {}
+                }
+            }],
+            "active": 0
+        }"#;
+        let path = std::env::temp_dir().join(format!("scoreboard_v1_{}.json", std::process::id()));
+        fs::write(&path, v1).unwrap();
+        let (workspace, warnings) = Workspace::load_from_file(&path).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(workspace.version, FORMAT_VERSION);
+        let board = workspace.active_board();
+        assert_eq!(board.players[&PlayerId(1)], "张三");
+        assert_eq!(board.scores[&PlayerId(2)], -1);
+        assert_eq!(board.history.len(), 1);
+        assert_eq!(board.scoring, ScoringRule::default());
+
+        // 保存时写入当前版本号
+        workspace.save_to_file(&path).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], FORMAT_VERSION);
+
+        // 更新版本的存档被拒绝
+        fs::write(&path, r#"{"version": 99, "boards": "未来的格式"}"#).unwrap();
+        let err = Workspace::load_from_file(&path).err().unwrap();
+        assert!(err.contains("高于本程序支持的版本"));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(backup_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn test_example_save_json() {
+        let json = example_save_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        // 示例中所有字段都有值，新增的存档字段若忘记填入示例值会在这里失败
+        let board = value["boards"][0]["board"].as_object().unwrap();
+        let empty: Vec<&String> = board.iter().filter(|(_, v)| v.is_null()).map(|(k, _)| k).collect();
+        assert!(empty.is_empty(), "{:?}", empty);
+
+        // 示例可以直接作为存档加载，且没有需要修复的不一致
+        let path = std::env::temp_dir().join(format!("scoreboard_schema_{}.json", std::process::id()));
+        fs::write(&path, &json).unwrap();
+        let (workspace, warnings) = Workspace::load_from_file(&path).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert!(workspace.active_board().anomalies().is_empty());
+        assert_eq!(workspace.active_board().title.as_deref(), Some("周五联赛"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_save_falls_back_to_backup() {
+        let path = std::env::temp_dir().join(format!("scoreboard_backup_{}.json", std::process::id()));
+        let backup = backup_path(&path);
+        let mut workspace = Workspace::new(Scoreboard::new());
+        workspace.active_board_mut().add_player("张三".to_string()).unwrap();
+        workspace.save_to_file(&path).unwrap();
+        // 第一次保存时还没有旧文件，不产生备份
+        assert!(!backup.exists());
+        workspace.active_board_mut().add_player("李四".to_string()).unwrap();
+        workspace.save_to_file(&path).unwrap();
+
+        // 模拟保存到一半时崩溃：文件被截断
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &content[..content.len() / 2]).unwrap();
+        let (loaded, warnings) = Workspace::load_from_file(&path).unwrap();
+        assert_eq!(loaded.active_board().players.len(), 1);
+        assert!(warnings[0].contains("已从备份"), "{}", warnings[0]);
+
+        // 损坏的文件不会覆盖完好的备份
+        workspace.save_to_file(&path).unwrap();
+        let (from_backup, _) = Workspace::load_from_file(&backup).unwrap();
+        assert_eq!(from_backup.active_board().players.len(), 1);
+        let (loaded, warnings) = Workspace::load_from_file(&path).unwrap();
+        assert_eq!(loaded.active_board().players.len(), 2);
+        assert!(warnings.is_empty());
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup).unwrap();
+    }
+
+    #[test]
+    fn test_load_reconciles_hand_edited_file() {
+        let mut workspace = Workspace::new(Scoreboard::new());
+        let board = workspace.active_board_mut();
+        let id1 = board.add_player("张三".to_string()).unwrap();
+        let id2 = board.add_player("李四".to_string()).unwrap();
+        board.update_scores(id1).unwrap();
+        // 模拟手动编辑：删掉一名玩家的积分，加入没有玩家的积分，并改坏 next_id
+        board.scores.remove(&id2);
+        board.scores.insert(PlayerId(9), 5);
+        board.next_id = 1;
+
+        let path = std::env::temp_dir().join(format!("scoreboard_reconcile_{}.json", std::process::id()));
+        workspace.save_to_file(&path).unwrap();
+        let (loaded, warnings) = Workspace::load_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("缺少积分"));
+        assert!(warnings[1].contains("已丢弃"));
+        assert!(warnings[2].contains("下一个玩家序号"));
+        let board = &loaded.boards[0].board;
+        assert_eq!(board.scores[&id1], 1);
+        assert_eq!(board.scores[&id2], 0);
+        assert!(!board.scores.contains_key(&PlayerId(9)));
+        assert_eq!(board.next_id, 3);
+    }
+
+    #[test]
+    fn test_practice_mode_leaves_real_board_untouched() {
+        let mut workspace = Workspace::new(Scoreboard::new());
+        let id1 = workspace.active_board_mut().add_player("玩家1".to_string()).unwrap();
+        workspace.active_board_mut().add_player("玩家2".to_string()).unwrap();
+        workspace.active_board_mut().update_scores(id1).unwrap();
+
+        workspace.enter_practice();
+        assert!(workspace.in_practice());
+        assert!(workspace.active_board().practice);
+        let practice = workspace.active_board_mut();
+        practice.update_scores(id1).unwrap();
+        practice.add_player("临时玩家".to_string()).unwrap();
+        assert_eq!(workspace.active_board().scores[&id1], 2);
+        assert!(workspace.add_board("二号桌".to_string(), Scoreboard::new()).is_err());
+
+        // 练习模式的数据不会被保存
+        let json = serde_json::to_string(&workspace).unwrap();
+        assert!(!json.contains("临时玩家"));
+
+        workspace.exit_practice();
+        let board = workspace.active_board();
+        assert!(!board.practice);
+        assert_eq!(board.scores[&id1], 1);
+        assert_eq!(board.players.len(), 2);
+        assert_eq!(board.history.len(), 1);
+    }
+
+    #[test]
+    fn test_session_stats() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        scoreboard.add_player("玩家2".to_string()).unwrap();
+        // 模拟会话已开始 10 分钟
+        scoreboard.started_at -= 600;
+        for _ in 0..5 {
+            scoreboard.update_scores(id1).unwrap();
+        }
+
+        let stats = scoreboard.session_stats();
+        assert_eq!(stats.rounds, 5);
+        assert!(stats.elapsed >= Duration::from_secs(600));
+        let rate = stats.rounds_per_minute.unwrap();
+        assert!((rate - 0.5).abs() < 0.01);
+        assert!(scoreboard.generate_report().contains("每分钟局数: 0.50"));
+
+        // 从文件加载时保留原开始时间
+        let json = serde_json::to_string(&scoreboard).unwrap();
+        let loaded: Scoreboard = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.started_at, scoreboard.started_at);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(1_500)), "1.5秒");
+        assert_eq!(format_duration(Duration::from_secs(83)), "1分23秒");
+        assert_eq!(format_duration(Duration::from_secs(3_723)), "1小时02分03秒");
+    }
+
+    #[test]
+    fn test_player_id() {
+        let id = PlayerId::from(7);
+        assert_eq!(usize::from(id), 7);
+        assert_eq!(format!("{:<3}|", id), "7  |");
+        // 存档格式不变：序号仍保存为数字，积分表的键仍为 "7"
+        assert_eq!(serde_json::to_string(&id).unwrap(), "7");
+        let scores: HashMap<PlayerId, i32> = serde_json::from_str(r#"{"7": 3}"#).unwrap();
+        assert_eq!(scores[&id], 3);
+    }
+
+    #[test]
+    fn test_most_pivotal_round() {
+        let mut scoreboard = Scoreboard::new();
+        let ids: Vec<PlayerId> = (1..=4)
+            .map(|i| scoreboard.add_player(format!("玩家{}", i)).unwrap())
+            .collect();
+        assert_eq!(scoreboard.most_pivotal_round(), None);
+
+        scoreboard.update_scores(ids[0]).unwrap();
+        assert_eq!(scoreboard.most_pivotal_round(), None);
+
+        // 玩家4 在第 2 局从并列第 2 升到与玩家1 并列第 1，之后一直领先
+        scoreboard.update_scores(ids[3]).unwrap();
+        scoreboard.update_scores(ids[3]).unwrap();
+        scoreboard.update_scores(ids[3]).unwrap();
+        assert_eq!(scoreboard.ranked()[0].0, ids[3]);
+        assert_eq!(scoreboard.most_pivotal_round(), Some(2));
+        assert!(scoreboard.generate_report().contains("关键一局: 第 2 局"));
+    }
+
+    #[test]
+    fn test_player_colors() {
+        let scoreboard = Scoreboard::new();
+        assert_eq!(scoreboard.player_color(PlayerId(1)), scoreboard.player_color(PlayerId(1)));
+        assert_ne!(scoreboard.player_color(PlayerId(1)), scoreboard.player_color(PlayerId(2)));
+        assert_eq!(scoreboard.player_color(PlayerId(1)), scoreboard.player_color(PlayerId(1 + PLAYER_COLORS.len())));
+        assert_eq!(scoreboard.player_color(PlayerId(1)).hex(), "#e6194b");
+
+        // 默认不上色；上色后转义序列不计入显示宽度
+        assert_eq!(scoreboard.colored_name(PlayerId(1), "张三"), "张三");
+        let painted = scoreboard.player_color(PlayerId(1)).paint("张三");
+        assert_ne!(painted, "张三");
+        assert_eq!(strip_ansi(&painted), "张三");
+        assert_eq!(visible_width(&painted), 4);
+
+        assert!(!ColorChoice::Auto.enabled(true, true));
+        assert!(ColorChoice::Auto.enabled(false, true));
+        assert!(!ColorChoice::Auto.enabled(false, false));
+        assert!(ColorChoice::Always.enabled(true, false));
+        assert!(!ColorChoice::Never.enabled(false, true));
+    }
+
+    #[test]
+    fn test_confirmation_word() {
+        assert!(is_confirmed("确认"));
+        assert!(is_confirmed(" 确认 "));
+        assert!(!is_confirmed("y"));
+        assert!(!is_confirmed("yes"));
+        assert!(!is_confirmed(""));
+        assert!(confirm_destructive("测试", true));
+    }
+
+    #[test]
+    fn test_box_table_aligns_cjk() {
+        let rows = vec![
+            vec!["1".to_string(), "张三".to_string(), "3".to_string()],
+            vec!["2".to_string(), "Bob".to_string(), "-10".to_string()],
+        ];
+        let chars = TableStyle::Unicode.box_chars().unwrap();
+        let table = render_box_table(&chars, &["序号", "玩家名称", "积分"], &rows);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "┌──────┬──────────┬──────┐");
+        assert_eq!(lines[1], "│ 序号 │ 玩家名称 │ 积分 │");
+        assert_eq!(lines[3], "│ 1    │ 张三     │ 3    │");
+        assert_eq!(lines[5], "└──────┴──────────┴──────┘");
+        // 每行的显示宽度相同
+        assert!(lines.iter().all(|line| line.width() == lines[0].width()));
+
+        let ascii = render_box_table(&TableStyle::Ascii.box_chars().unwrap(), &["积分"], &[vec!["1".to_string()]]);
+        assert!(ascii.starts_with("+------+\n| 积分 |\n"));
+        assert!(TableStyle::Plain.box_chars().is_none());
+    }
+
+    #[test]
+    fn test_freeze_comparison() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        assert!(scoreboard.frozen_comparison().is_none());
+
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.freeze("中场休息".to_string());
+        scoreboard.update_scores(id2).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+
+        let frozen = scoreboard.frozen.as_ref().unwrap();
+        assert_eq!(frozen.label, "中场休息");
+        assert_eq!(frozen.round, 1);
+        let rows = scoreboard.frozen_comparison().unwrap();
+        assert_eq!(rows[0], (id2, "玩家2", Some(-1), 1));
+        assert_eq!(rows[1], (id3, "玩家3", None, 0));
+        assert_eq!(rows[2], (id1, "玩家1", Some(1), -1));
+
+        // 积分变化与其他地方一样由 delta_text 格式化：增加带 "+"，没有变化时显示 0 而不是 +0
+        let text = scoreboard.frozen_comparison_text();
+        let row_of = |name: &str| text.lines().find(|line| line.contains(name)).unwrap().to_string();
+        assert!(row_of("玩家2").trim_end().ends_with("+2"));
+        assert!(row_of("玩家3").trim_end().ends_with("新加入"));
+        // 中文名称和表头按显示宽度对齐，分隔线与表头等宽
+        let table: Vec<&str> = text.lines().skip_while(|line| !line.starts_with("序号")).filter(|line| !line.is_empty()).collect();
+        assert_eq!(table.len(), 5);
+        for line in &table {
+            assert_eq!(visible_width(line), visible_width(table[0]), "{}", line);
+        }
+        scoreboard.freeze("第二节".to_string());
+        let text = scoreboard.frozen_comparison_text();
+        for line in text.lines().filter(|line| line.contains("玩家")).skip(1) {
+            assert!(line.trim_end().ends_with(" 0"), "{}", line);
+        }
+        assert!(!text.contains("+0"));
+    }
+
+    #[test]
+    fn test_score_of_name() {
+        let mut scoreboard = Scoreboard::new();
+        let alice = scoreboard.add_player("Alice".to_string()).unwrap();
+        scoreboard.add_player("李四".to_string()).unwrap();
+        scoreboard.update_scores(alice).unwrap();
+
+        assert_eq!(scoreboard.score_of_name("alice"), Some(1));
+        assert_eq!(scoreboard.score_of_name("李四"), Some(-1));
+        assert_eq!(scoreboard.score_of_name("Bob"), None);
+
+        scoreboard.add_player("ALICE".to_string()).unwrap();
+        assert_eq!(scoreboard.score_of_name("alice"), None);
+        assert!(scoreboard.find_by_name("alice").unwrap_err().contains("多名玩家"));
+        assert_eq!(scoreboard.score_of_name("ALICE"), Some(0));
+    }
+
+    #[test]
+    fn test_rename_by_name() {
+        let mut scoreboard = Scoreboard::new();
+        let id1 = scoreboard.add_player("zhang".to_string()).unwrap();
+        scoreboard.add_player("李四".to_string()).unwrap();
+
+        // 不区分大小写查找，且允许只改大小写
+        scoreboard.rename_by_name("ZHANG", "Zhang".to_string()).unwrap();
+        assert_eq!(scoreboard.players[&id1], "Zhang");
+
+        assert!(scoreboard.rename_by_name("王五", "赵六".to_string()).unwrap_err().contains("找不到"));
+        assert!(scoreboard.rename_by_name("Zhang", "李四".to_string()).unwrap_err().contains("已存在"));
+        assert!(scoreboard.rename_by_name("Zhang", "".to_string()).is_err());
+
+        // 仅大小写不同的多名玩家：大小写完全一致者优先，否则报告歧义
+        let id3 = scoreboard.add_player("ZHANG".to_string()).unwrap();
+        let err = scoreboard.rename_by_name("zhang", "张三".to_string()).unwrap_err();
+        assert!(err.contains("多名玩家"));
+        assert_eq!(scoreboard.players[&id1], "Zhang");
+        scoreboard.rename_by_name("ZHANG", "张三".to_string()).unwrap();
+        assert_eq!(scoreboard.players[&id3], "张三");
+    }
+
+    #[test]
+    fn test_scoring_config() {
+        let mut scoreboard = Scoreboard::new();
+        let config = scoreboard.scoring_config();
+        assert_eq!(config.mode, ScoringMode::Standard);
+        assert_eq!(config.loser_delta, 1);
+        assert_eq!(config.min_score, None);
+        assert!(config.describe()[0].contains("-1 分"));
+
+        scoreboard.scoring.rank_penalties = vec![3, 2];
+        scoreboard.min_score = Some(-5);
+        scoreboard.streak_bonus = Some(StreakBonus { milestone: 3, bonus: 1 });
+        let config = scoreboard.scoring_config();
+        assert_eq!(config.mode, ScoringMode::RankScaled);
+        assert_eq!(config.rank_penalties, vec![3, 2]);
+        let lines = config.describe();
+        assert!(lines[0].contains("第1名 -3"));
+        assert_eq!(lines[1], "积分下限: -5");
+        assert!(lines[2].contains("每连胜 3 局额外 +1 分"));
+        assert!(scoreboard.generate_report().contains("积分下限: -5"));
+    }
+
+    #[test]
+    fn test_shuffle_order() {
+        let mut scoreboard = Scoreboard::new();
+        for i in 1..=6 {
+            scoreboard.add_player(format!("玩家{}", i)).unwrap();
+        }
+        scoreboard.withdraw_player(PlayerId(6)).unwrap();
+
+        let first = scoreboard.shuffle_order(Some(42));
+        assert_eq!(first, scoreboard.shuffle_order(Some(42)));
+        assert_eq!(first.len(), 5);
+        let mut ids: Vec<usize> = first.iter().map(|(id, _)| id.get()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+        // 不同种子通常得到不同顺序
+        assert!((0..10).any(|seed| scoreboard.shuffle_order(Some(seed)) != first));
+        assert!(scoreboard.scores.values().all(|score| *score == 0));
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut before = Scoreboard::new();
+        before.add_player("玩家1".to_string()).unwrap();
+        before.add_player("玩家2".to_string()).unwrap();
+        before.add_player("旧名".to_string()).unwrap();
+        before.add_player("离开".to_string()).unwrap();
+
+        // 之后的存档中序号不同，但名称相同的玩家仍能匹配；序号 3 改名
+        let mut after = Scoreboard::new();
+        after.add_player("玩家2".to_string()).unwrap();
+        after.add_player("玩家1".to_string()).unwrap();
+        after.add_player("新名".to_string()).unwrap();
+        after.next_id = 5;
+        let new_id = after.add_player("新人".to_string()).unwrap();
+        after.scores.insert(PlayerId(2), 3);
+        after.scores.insert(PlayerId(1), -1);
+        after.scores.insert(new_id, 2);
+
+        let diffs = before.diff(&after);
+        assert_eq!(diffs.len(), 5);
+        assert_eq!(
+            diffs[0],
+            ScoreDiff { name: "玩家1".to_string(), previous_name: None, before: Some(0), after: Some(3) }
+        );
+        assert_eq!(diffs[1].name, "玩家2");
+        assert_eq!(diffs[1].after, Some(-1));
+        assert_eq!(diffs[2].name, "新名");
+        assert_eq!(diffs[2].previous_name.as_deref(), Some("旧名"));
+        assert_eq!((diffs[3].name.as_str(), diffs[3].after), ("离开", None));
+        assert_eq!((diffs[4].name.as_str(), diffs[4].before), ("新人", None));
+
+        assert!(before.diff(&before.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_lock_roster_after_first_round() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.lock_roster_after_first_round = true;
+        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
+        scoreboard.add_player("玩家2".to_string()).unwrap();
+        assert!(!scoreboard.roster_locked());
+
+        scoreboard.update_scores(id1).unwrap();
+        assert!(scoreboard.roster_locked());
+        let err = scoreboard.add_player("玩家3".to_string()).unwrap_err();
+        assert!(err.contains("锁定"));
+        assert!(scoreboard.split_player(id1, "玩家4".to_string(), 0).is_err());
+
+        scoreboard.unlock_roster();
+        assert!(!scoreboard.roster_locked());
+        assert!(scoreboard.add_player("玩家3".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let cases = [
+            (RoundingMode::Floor, "1", "-2"),
+            (RoundingMode::Ceil, "2", "-1"),
+            (RoundingMode::Round, "2", "-2"),
+            (RoundingMode::Truncate, "1", "-1"),
+        ];
+        for (mode, positive, negative) in cases {
+            assert_eq!(format_score_rounded(1.5, 0, mode), positive, "{:?}", mode);
+            assert_eq!(format_score_rounded(-1.5, 0, mode), negative, "{:?}", mode);
+        }
+
+        // 按小数位数舍入，且不会出现 "-0"
+        assert_eq!(format_score_rounded(1.25, 1, RoundingMode::Round), "1.3");
+        assert_eq!(format_score_rounded(1.25, 1, RoundingMode::Floor), "1.2");
+        assert_eq!(format_score_rounded(-0.4, 0, RoundingMode::Ceil), "0");
+        assert_eq!(format_score_rounded(-0.4, 0, RoundingMode::Truncate), "0");
+        assert_eq!(format_score_rounded(3.0, 2, RoundingMode::Floor), "3");
+    }
+
+    #[test]
+    fn test_rounding_applies_to_mean_values() {
+        // 积分都是整数，舍入方式实际影响的是平均分和相对平均分
+        let cases = [("floor", "+2", "-1"), ("ceil", "+3", "0"), ("round", "+3", "0"), ("truncate", "+2", "0")];
+        for (mode, above, below) in cases {
+            let mut scoreboard = Scoreboard::new();
+            let a = scoreboard.add_player("A".to_string()).unwrap();
+            let b = scoreboard.add_player("B".to_string()).unwrap();
+            let c = scoreboard.add_player("C".to_string()).unwrap();
+            scoreboard.scores.extend([(a, 4), (b, -1), (c, 1)]);
+            let args = ["--rounding", mode, "--score-precision", "0"];
+            let options = parse_args(args.iter().map(|s| s.to_string())).unwrap();
+            apply_options(&options, &mut scoreboard).unwrap();
+
+            // 平均分 4/3，A 高出 2.67 分，C 低 0.33 分
+            assert_eq!(scoreboard.relative_to_mean_text(a), above, "{}", mode);
+            assert_eq!(scoreboard.relative_to_mean_text(c), below, "{}", mode);
+            // 整数积分不受舍入方式影响
+            assert_eq!(scoreboard.score_text(4), "4");
+            assert_eq!(scoreboard.delta_text(-1), "-1");
+        }
+    }
+
+    #[test]
+    fn test_format_score() {
+        assert_eq!(format_score(1.0, 2), "1");
+        assert_eq!(format_score(1.5, 2), "1.5");
+        assert_eq!(format_score(-0.5, 2), "-0.5");
+        assert_eq!(format_score(1.25, 1), "1.2");
+        assert_eq!(format_score(-0.0, 2), "0");
+        assert_eq!(format_score(-0.4, 0), "0");
+        assert_eq!(format_score(10.0, 0), "10");
+        // 舍入后为零的负数不显示负号
+        for precision in 0..=6 {
+            assert_eq!(format_score(-0.0, precision), "0");
+            assert_eq!(format_score(-0.0000001, precision), "0");
+        }
+        assert_eq!(format_score(0.0, 3), "0");
+        assert_eq!(format_score(i32::MIN as f64, 2), "-2147483648");
+        assert_eq!(format_score_rounded(-0.4, 0, RoundingMode::Ceil), "0");
+
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.score_precision = 3;
+        assert_eq!(scoreboard.score_text(-2), "-2");
+    }
+
+    #[test]
+    fn test_positive_sign() {
+        let mut scoreboard = Scoreboard::new();
+        assert_eq!(scoreboard.score_text(3), "3");
+
+        scoreboard.show_positive_sign = PositiveSign::NonZero;
+        assert_eq!(scoreboard.score_text(3), "+3");
+        assert_eq!(scoreboard.score_text(0), "0");
+        assert_eq!(scoreboard.score_text(-3), "-3");
+        // 分差不加正号
+        assert_eq!(scoreboard.amount_text(3), "3");
+        // 积分变化非零时总是带符号，零不带符号
+        assert_eq!(scoreboard.delta_text(3), "+3");
+        assert_eq!(scoreboard.delta_text(0), "0");
+        assert_eq!(scoreboard.delta_text(i32::MIN), "-2147483648");
+
+        scoreboard.show_positive_sign = PositiveSign::All;
+        assert_eq!(scoreboard.score_text(0), "+0");
+        assert_eq!(scoreboard.score_text(-1), "-1");
+
+        // 导出的 CSV 与积分榜一致
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        let mut csv = Vec::new();
+        scoreboard.export_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "id,name,score\n1,A,+1\n2,B,-1\n");
+
+        assert_eq!(score_column_width(["+1", "-1"].into_iter()), 6);
+        assert_eq!(score_column_width(["+1234.56"].into_iter()), 8);
+    }
+
+    #[test]
+    fn test_export_svg() {
         let mut scoreboard = Scoreboard::new();
-        
-        // 测试空名称
-        assert!(scoreboard.add_player("".to_string()).is_err());
-        
-        // 测试过长名称
-        let long_name = "a".repeat(25);
-        assert!(scoreboard.add_player(long_name).is_err());
-        
-        // 测试包含控制字符的名称
-        assert!(scoreboard.add_player("test\n".to_string()).is_err());
-        assert!(scoreboard.add_player("test\t".to_string()).is_err());
-        
-        // 测试正常名称
-        assert!(scoreboard.add_player("张三".to_string()).is_ok());
-        
-        // 测试重复名称
-        assert!(scoreboard.add_player("张三".to_string()).is_err());
+        let id1 = scoreboard.add_player("A&B".to_string()).unwrap();
+        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        // 中途加入的玩家只有之后的积分点
+        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        assert_eq!(scoreboard.history[2].scores_after[&id3], -1);
+
+        let mut output = Vec::new();
+        scoreboard.export_svg(&mut output).unwrap();
+        let svg = String::from_utf8(output).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert!(svg.contains("A&amp;B"));
+        assert!(svg.contains("玩家3"));
+        let polylines: Vec<&str> = svg.lines().filter(|line| line.starts_with("<polyline")).collect();
+        assert_eq!(polylines[0].matches(',').count(), 3);
+        assert_eq!(polylines[2].matches(',').count(), 1);
     }
-    
+
     #[test]
-    fn test_update_scores() {
+    fn test_export_history_csv() {
         let mut scoreboard = Scoreboard::new();
-        let id1 = scoreboard.add_player("玩家1".to_string()).unwrap();
-        let id2 = scoreboard.add_player("玩家2".to_string()).unwrap();
-        let id3 = scoreboard.add_player("玩家3".to_string()).unwrap();
-        let id4 = scoreboard.add_player("玩家4".to_string()).unwrap();
-        
-        // 测试不存在的玩家ID
-        assert!(scoreboard.update_scores(999).is_err());
-        
-        // 测试正常更新（4个玩家，1个胜出）
-        assert!(scoreboard.update_scores(id1).is_ok());
-        // 胜出玩家应该得到3分（其他3个玩家的分数）
-        assert_eq!(*scoreboard.scores.get(&id1).unwrap(), 3);
-        // 其他玩家各扣1分
-        assert_eq!(*scoreboard.scores.get(&id2).unwrap(), -1);
-        assert_eq!(*scoreboard.scores.get(&id3).unwrap(), -1);
-        assert_eq!(*scoreboard.scores.get(&id4).unwrap(), -1);
+        let id1 = scoreboard.add_player("张三".to_string()).unwrap();
+        let id2 = scoreboard.add_player("李, 四".to_string()).unwrap();
+        scoreboard.update_scores(id1).unwrap();
+        scoreboard.update_scores(id2).unwrap();
+        for record in &mut scoreboard.history {
+            record.timestamp = 1_700_000_000;
+        }
+        scoreboard.players.remove(&id1);
+
+        let mut output = Vec::new();
+        scoreboard.export_history_csv(&mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "round,timestamp,winner_id,winner_name,event_type");
+        assert_eq!(lines[1], "1,2023-11-14T22:13:20Z,1,,win");
+        assert_eq!(lines[2], "2,2023-11-14T22:13:20Z,2,\"李, 四\",win");
+        assert_eq!(lines.len(), 3);
     }
-    
+
     #[test]
-    fn test_input_length_validation() {
-        // 测试输入长度验证逻辑
-        let test_input = "a".repeat(60);
-        assert!(test_input.len() > 50); // 验证测试数据确实超过限制
-        
-        // 测试玩家名称长度限制
+    fn test_secondary_score() {
         let mut scoreboard = Scoreboard::new();
-        let long_name = "很长的玩家名称".repeat(5); // 创建超长名称
-        assert!(scoreboard.add_player(long_name).is_err());
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+
+        // 未启用时不显示也不导出副积分
+        assert_eq!(scoreboard.secondary_score(a), None);
+        assert!(scoreboard.adjust_secondary(a, 1).unwrap_err().contains("未启用副积分"));
+        let mut output = Vec::new();
+        scoreboard.export_csv(&mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("id,name,score\n"));
+
+        scoreboard.secondary = Some(SecondaryScore { label: "筹码".to_string(), per_win: 2 });
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(scoreboard.secondary_score(a), Some(2));
+        assert_eq!(scoreboard.secondary_score(b), Some(0));
+        // 单独调整副积分不影响主积分
+        assert_eq!(scoreboard.adjust_secondary(b, -3), Ok(-3));
+        assert_eq!(scoreboard.scores[&b], -1);
+        assert!(scoreboard.adjust_secondary(PlayerId(9), 1).is_err());
+
+        let mut output = Vec::new();
+        scoreboard.export_csv(&mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        assert_eq!(csv, "id,name,score,secondary\n1,A,1,2\n2,B,-1,-3\n");
+        let imported = Scoreboard::import_csv(csv.as_bytes(), NamePolicy::default()).unwrap();
+        assert_eq!(imported.secondary_score(b), Some(-3));
+        assert_eq!(imported.scores[&b], -1);
     }
-}
 
-fn main() {
-    println!("欢迎使用游戏积分板系统！");
-    println!("首先，请录入所有参与游戏的玩家名称。");
-    
-    let mut scoreboard = Scoreboard::new();
-    
-    // 录入玩家
-    loop {
-        let name = get_input_safe("请输入玩家名称（输入 'done' 完成录入）: ");
-        
-        if name.to_lowercase() == "done" {
-            if scoreboard.players.is_empty() {
-                println!("至少需要录入一个玩家！");
-                continue;
-            }
-            break;
+    #[test]
+    fn test_set_title() {
+        let mut scoreboard = Scoreboard::new();
+        assert!(!scoreboard.generate_report().contains("比赛:"));
+        scoreboard.set_title("  周五联赛 ").unwrap();
+        assert_eq!(scoreboard.title.as_deref(), Some("周五联赛"));
+        assert!(scoreboard.generate_report().contains("比赛: 周五联赛\n"));
+        let mut output = Vec::new();
+        scoreboard.export_svg(&mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().contains("<title>周五联赛</title>"));
+
+        assert!(scoreboard.set_title("联\n赛").is_err());
+        assert!(scoreboard.set_title(&"赛".repeat(41)).is_err());
+        assert_eq!(scoreboard.title.as_deref(), Some("周五联赛"));
+        scoreboard.set_title("").unwrap();
+        assert_eq!(scoreboard.title, None);
+    }
+
+    #[test]
+    fn test_anonymized() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("张三".to_string()).unwrap();
+        let b = scoreboard.add_player("李四".to_string()).unwrap();
+        let c = scoreboard.add_player("王五".to_string()).unwrap();
+        scoreboard.update_scores(b).unwrap();
+
+        // 按排名命名，并列时按序号
+        let anonymous = scoreboard.anonymized();
+        assert_eq!(anonymous.players[&b], "玩家A");
+        assert_eq!(anonymous.players[&a], "玩家B");
+        assert_eq!(anonymous.players[&c], "玩家C");
+        assert_eq!(anonymous.scores, scoreboard.scores);
+        assert_eq!(anonymous.ranks(), scoreboard.ranks());
+        assert_eq!(scoreboard.players[&a], "张三");
+        let mut output = Vec::new();
+        anonymous.export_csv(&mut output).unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains("李四"));
+
+        assert_eq!(alphabetic_label(0), "A");
+        assert_eq!(alphabetic_label(25), "Z");
+        assert_eq!(alphabetic_label(26), "AA");
+        assert_eq!(alphabetic_label(27), "AB");
+        assert_eq!(alphabetic_label(701), "ZZ");
+        assert_eq!(alphabetic_label(702), "AAA");
+    }
+
+    #[test]
+    fn test_sorted_players() {
+        let mut scoreboard = Scoreboard::new();
+        let bob = scoreboard.add_player("bob".to_string()).unwrap();
+        let alice = scoreboard.add_player("Alice".to_string()).unwrap();
+        let zhang = scoreboard.add_player("张三".to_string()).unwrap();
+        let li = scoreboard.add_player("李四".to_string()).unwrap();
+        scoreboard.update_scores(li).unwrap();
+
+        let ids = |by| -> Vec<PlayerId> { scoreboard.sorted_players(by).into_iter().map(|(id, _)| id).collect() };
+        assert_eq!(ids(PlayerSort::Id), vec![bob, alice, zhang, li]);
+        // 英文不区分大小写；中文按码位，"张"(U+5F20) 在 "李"(U+674E) 之前
+        assert_eq!(ids(PlayerSort::Name), vec![alice, bob, zhang, li]);
+        assert_eq!(ids(PlayerSort::Score), vec![li, bob, alice, zhang]);
+    }
+
+    #[test]
+    fn test_most_improved() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.add_player("C".to_string()).unwrap();
+        assert_eq!(scoreboard.most_improved(3), None);
+        for winner in [a, a, b, b] {
+            scoreboard.update_scores(winner).unwrap();
         }
-        
-        match scoreboard.add_player(name.clone()) {
-            Ok(id) => {
-                println!("玩家 '{}' 已添加，序号为: {}", name, id);
+
+        // 最近 2 局 B 从 -2 升到 2
+        assert_eq!(scoreboard.most_improved(2), Some((b, "B", 4)));
+        // 窗口超过记录长度时使用全部记录：A 与 B 都从 0 升到 2，取排名靠前的 A
+        assert_eq!(scoreboard.most_improved(10), Some((a, "A", 2)));
+        assert_eq!(scoreboard.most_improved(0), None);
+        assert!(scoreboard.generate_report().contains("进步最快: A（最近 4 局 +2 分）"));
+    }
+
+    #[test]
+    fn test_quick_repeat_winner() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        let recorded_at = scoreboard.history[0].timestamp;
+
+        // 默认关闭
+        assert!(!scoreboard.is_quick_repeat(a, recorded_at));
+        scoreboard.repeat_window = Some(3);
+        assert!(scoreboard.is_quick_repeat(a, recorded_at));
+        assert!(scoreboard.is_quick_repeat(a, recorded_at + 3));
+        assert!(!scoreboard.is_quick_repeat(a, recorded_at + 4));
+        // 不同的胜者不受影响
+        assert!(!scoreboard.is_quick_repeat(b, recorded_at));
+        // --yes 时不提示，直接记录
+        assert!(confirm_repeat_win(&scoreboard, a, true));
+    }
+
+    #[test]
+    fn test_id_name_map() {
+        let mut scoreboard = Scoreboard::new();
+        for name in ["A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K"] {
+            scoreboard.add_player(name.to_string()).unwrap();
+        }
+        scoreboard.players.remove(&PlayerId(2));
+        let map = scoreboard.id_name_map();
+        assert_eq!(map.len(), 10);
+        assert_eq!(map[&PlayerId(11)], "K");
+        // 按序号的数值排列（10 在 9 之后，而不是按字符串排在 1 之后）
+        let json = serde_json::to_string(&map).unwrap();
+        assert!(json.starts_with(r#"{"1":"A","3":"C","#), "{}", json);
+        assert!(json.ends_with(r#""9":"I","10":"J","11":"K"}"#), "{}", json);
+    }
+
+    #[test]
+    fn test_score_extremes() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        assert_eq!(scoreboard.score_extremes(a), Some((0, 0)));
+        assert_eq!(scoreboard.score_extremes(PlayerId(9)), None);
+        for winner in [a, a, b, b, b] {
+            scoreboard.update_scores(winner).unwrap();
+        }
+        // A: 1, 2, 1, 0, -1；B: -1, -2, -1, 0, 1
+        assert_eq!(scoreboard.score_extremes(a), Some((-1, 2)));
+        assert_eq!(scoreboard.score_extremes(b), Some((-2, 1)));
+        assert!(scoreboard.generate_report().contains("A: 当前 -1，最高 2，最低 -1"));
+
+        // 清空对局记录后仍保留
+        scoreboard.clear_history();
+        assert_eq!(scoreboard.score_extremes(a), Some((-1, 2)));
+
+        // 旧存档没有保存时从对局记录恢复
+        let mut restored = Scoreboard::new();
+        let a = restored.add_player("A".to_string()).unwrap();
+        restored.add_player("B".to_string()).unwrap();
+        restored.update_scores(a).unwrap();
+        restored.update_scores(a).unwrap();
+        restored.scores.insert(a, 0);
+        restored.score_extremes.clear();
+        restored.reconcile();
+        assert_eq!(restored.score_extremes(a), Some((0, 2)));
+    }
+
+    #[test]
+    fn test_standings_text() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("张三".to_string()).unwrap();
+        scoreboard.add_player("李|四".to_string()).unwrap();
+        scoreboard.add_player("王五".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        assert_eq!(
+            scoreboard.standings_text(StandingsFormat::Plain),
+            "1. 张三 2分\n2. 李|四 -1分\n2. 王五 -1分\n"
+        );
+
+        scoreboard.set_title("周五联赛").unwrap();
+        let markdown = scoreboard.standings_text(StandingsFormat::Markdown);
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0], "**周五联赛 积分榜**");
+        assert_eq!(lines[2], "| 排名 | 玩家 | 积分 |");
+        assert_eq!(lines[5], "| 2 | 李\\|四 | -1 |");
+
+        assert_eq!(StandingsFormat::parse("MD"), Ok(StandingsFormat::Markdown));
+        assert!(StandingsFormat::parse("html").is_err());
+    }
+
+    #[test]
+    fn test_add_players_loops_end_on_done() {
+        fn feed<'a>(inputs: &'a [&'a str]) -> impl FnMut() -> String + 'a {
+            let mut inputs = inputs.iter();
+            move || inputs.next().expect("录入循环读取了多余的输入").to_string()
+        }
+
+        // 开局录入：没有玩家时输入 done 不会结束
+        let mut scoreboard = Scoreboard::new();
+        add_players_from(&mut scoreboard, 1, feed(&["done", "张三", "张三", " DONE "]));
+        assert_eq!(scoreboard.names_of(&scoreboard.existing_ids()), vec!["张三"]);
+
+        // 中途添加：直接输入 done 即结束，不添加任何玩家
+        add_players_from(&mut scoreboard, 0, feed(&["Done"]));
+        assert_eq!(scoreboard.player_count(), 1);
+        add_players_from(&mut scoreboard, 0, feed(&["李四", "done"]));
+        assert_eq!(scoreboard.player_count(), 2);
+
+        assert!(is_done_keyword("done"));
+        assert!(!is_done_keyword("done2"));
+    }
+
+    #[test]
+    fn test_simulate() {
+        let board = || {
+            let mut scoreboard = Scoreboard::new();
+            for name in ["A", "B", "C"] {
+                scoreboard.add_player(name.to_string()).unwrap();
             }
-            Err(e) => {
-                println!("添加玩家失败: {}", e);
-                continue;
+            scoreboard
+        };
+        let mut first = board();
+        first.simulate(20, 42).unwrap();
+        assert_eq!(first.history.len(), 20);
+        assert_eq!(first.scores.values().sum::<i32>(), 0);
+        // 同一种子结果相同
+        let mut second = board();
+        second.simulate(20, 42).unwrap();
+        assert_eq!(first.scores, second.scores);
+
+        // 遵守当前的积分规则和比赛设置
+        let mut scaled = board();
+        scaled.set_scoring(ScoringRule { loser_delta: 1, rank_penalties: vec![3] }).unwrap();
+        scaled.simulate(20, 42).unwrap();
+        assert_ne!(scaled.scores, first.scores);
+        let mut capped = board();
+        capped.target_score = Some(4);
+        capped.stop_at_target = true;
+        let error = capped.simulate(100, 42).unwrap_err();
+        assert!(error.contains("比赛已结束"), "{}", error);
+
+        let mut lonely = Scoreboard::new();
+        lonely.add_player("A".to_string()).unwrap();
+        assert!(lonely.simulate(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_rank_gaps() {
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.rank_gaps().is_empty());
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        assert!(scoreboard.rank_gaps().is_empty());
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        // A 4 分，B、C 并列 -2 分
+        assert_eq!(scoreboard.rank_gaps(), vec![(a, b, 6), (b, c, 0)]);
+    }
+
+    #[test]
+    fn test_watch_options() {
+        let options = parse_args(
+            ["--file", "a.json", "--watch", "--watch-interval", "10"].iter().map(|s| s.to_string()),
+        )
+        .unwrap();
+        assert!(options.watch);
+        assert_eq!(options.watch_interval, Some(10));
+        assert!(parse_args(["--watch-interval", "0"].iter().map(|s| s.to_string())).is_err());
+        assert!(parse_args(["--watch-interval", "x"].iter().map(|s| s.to_string())).is_err());
+        assert!(run_watch(None, 1).is_err());
+    }
+
+    #[test]
+    fn test_sort_note() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.add_player("张三".to_string()).unwrap();
+        let b = scoreboard.add_player("李四".to_string()).unwrap();
+        scoreboard.update_scores(b).unwrap();
+        let csv = |board: &Scoreboard| {
+            let mut out = Vec::new();
+            board.export_csv(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // 默认不写排序说明
+        assert!(csv(&scoreboard).starts_with("id,name,score\n"));
+        assert!(!scoreboard.standings_text(StandingsFormat::Markdown).contains("排序"));
+
+        scoreboard.sort_note = true;
+        let exported = csv(&scoreboard);
+        assert!(exported.starts_with("# 排序: 按序号\nid,name,score\n"), "{}", exported);
+        // 带排序说明的 CSV 仍然可以导入
+        let imported = Scoreboard::import_csv(exported.as_bytes(), NamePolicy::default()).unwrap();
+        assert_eq!(imported.scores[&b], 1);
+        assert!(
+            scoreboard
+                .standings_text(StandingsFormat::Markdown)
+                .contains("_排序: 按名次（名次相同时按序号）_")
+        );
+        assert!(scoreboard.generate_report().contains("积分起伏（排序: 按名次"));
+    }
+
+    #[test]
+    fn test_player_record() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("张三".to_string()).unwrap();
+        let b = scoreboard.add_player("李四".to_string()).unwrap();
+        let c = scoreboard.add_player("王五".to_string()).unwrap();
+        for winner in [a, a, b, a, c] {
+            scoreboard.update_scores(winner).unwrap();
+        }
+        scoreboard.update_scores_among(b, &[b, c]).unwrap();
+        scoreboard.withdraw_player(c).unwrap();
+
+        let record = scoreboard.player_record(a).unwrap();
+        assert_eq!(record.name, "张三");
+        assert_eq!(record.score, scoreboard.scores[&a]);
+        assert_eq!(record.rank, scoreboard.ranks()[&a]);
+        assert_eq!((record.wins, record.losses), (3, 2));
+        assert_eq!((record.current_streak, record.best_streak), (0, 2));
+        assert!(!record.withdrawn);
+        let line = format!("{} 第{}名 {}胜{}负", record.name, record.rank, record.wins, record.losses);
+        println!("{}", line);
+        assert_eq!(line, "张三 第1名 3胜2负");
+
+        let record = scoreboard.player_record(b).unwrap();
+        assert_eq!((record.wins, record.losses, record.current_streak), (2, 4, 1));
+        assert!(scoreboard.player_record(c).unwrap().withdrawn);
+        assert_eq!(scoreboard.player_record(PlayerId(9)), None);
+    }
+
+    #[test]
+    fn test_elimination_round() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        let d = scoreboard.add_player("D".to_string()).unwrap();
+        scoreboard.elimination = true;
+        scoreboard.tie_break = TieBreak::Wins;
+
+        // 第一局后 B、C、D 同为 -1 分、0 胜，序号最大的 D 被淘汰
+        assert_eq!(scoreboard.elimination_round(a), Ok(Some(d)));
+        // 第二局 B 胜出，A 和 C 积分相同时按胜场 A 排名靠前，C 被淘汰
+        scoreboard.scores.insert(a, -1);
+        assert_eq!(scoreboard.elimination_round(b), Ok(Some(c)));
+        assert_eq!(scoreboard.elimination_survivor(), None);
+        // 胜者即使积分最低也不会被淘汰
+        scoreboard.scores.insert(a, -10);
+        assert_eq!(scoreboard.elimination_round(a), Ok(Some(b)));
+        assert_eq!(scoreboard.elimination_survivor(), Some(a));
+
+        // 只剩最终胜者后返回 None，不再记录对局，积分不变
+        let scores = scoreboard.scores.clone();
+        assert_eq!(scoreboard.elimination_round(a), Ok(None));
+        assert_eq!(scoreboard.elimination_round(b), Ok(None));
+        assert_eq!(scoreboard.rounds_played(), 3);
+        assert_eq!(scoreboard.scores, scores);
+        assert!(scoreboard.elimination_over_error().contains("最终胜者: A"));
+    }
+
+    #[test]
+    fn test_record_wins_batch() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        scoreboard.add_player("C".to_string()).unwrap();
+
+        let summary = scoreboard.record_wins_batch("1 2，1\n x 9\n\n2,");
+        assert_eq!(summary.added, 4);
+        let skipped: Vec<usize> = summary.skipped.iter().map(|(entry, _)| *entry).collect();
+        assert_eq!(skipped, vec![4, 5]);
+        assert!(summary.skipped[0].1.starts_with("'x': "), "{}", summary.skipped[0].1);
+        assert!(summary.skipped[1].1.contains("'9'"), "{}", summary.skipped[1].1);
+        let winners: Vec<PlayerId> = scoreboard.history.iter().map(|record| record.winner_id).collect();
+        assert_eq!(winners, vec![a, b, a, b]);
+        assert!(scoreboard.record_wins_batch("  ").skipped.is_empty());
+
+        // 逐轮淘汰决出最终胜者后，其余序号跳过
+        let mut scoreboard = Scoreboard::new();
+        for name in ["A", "B", "C"] {
+            scoreboard.add_player(name.to_string()).unwrap();
+        }
+        scoreboard.elimination = true;
+        let summary = scoreboard.record_wins_batch("1 1 1");
+        assert_eq!(summary.added, 2);
+        assert_eq!(summary.skipped.len(), 1);
+        assert!(summary.skipped[0].1.contains("最终胜者: A"), "{}", summary.skipped[0].1);
+    }
+
+    #[test]
+    fn test_season_aggregate() {
+        let dir = std::env::temp_dir().join(format!("scoreboard_season_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut first = Scoreboard::new();
+        let a = first.add_player("张三".to_string()).unwrap();
+        first.add_player("李四".to_string()).unwrap();
+        first.update_scores(a).unwrap();
+        let mut second = Scoreboard::new();
+        let c = second.add_player("王五".to_string()).unwrap();
+        second.add_player("张三".to_string()).unwrap();
+        second.update_scores(c).unwrap();
+        second.update_scores(c).unwrap();
+        Workspace::new(first).save_to_file(&dir.join("day1.json")).unwrap();
+        Workspace::new(second).save_to_file(&dir.join("day2.json")).unwrap();
+
+        let (season, files, warnings) = Season::load(&dir).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(season.sessions.iter().map(|(label, _)| label.as_str()).collect::<Vec<_>>(), ["day1", "day2"]);
+        let total = season.aggregate().unwrap();
+        let scores: Vec<(&str, i32)> = total.ranked().into_iter().map(|(_, name, score)| (name, score)).collect();
+        assert_eq!(scores, vec![("王五", 2), ("张三", -1), ("李四", -1)]);
+        assert_eq!(total.player_count(), 3);
+        assert_eq!(total.wins(total.find_by_name("张三").unwrap()), 1);
+        assert_eq!(total.rounds_played(), 3);
+        assert!(total.anomalies().is_empty(), "{:?}", total.anomalies());
+
+        // 赛季文件只记录文件名；扫描目录时不会把赛季文件当作比赛日
+        let season_file = dir.join("season.json");
+        Season::save(&season_file, &files[..1]).unwrap();
+        assert!(fs::read_to_string(&season_file).unwrap().contains("\"day1.json\""));
+        let (from_file, _, _) = Season::load(&season_file).unwrap();
+        assert_eq!(from_file.sessions.len(), 1);
+        assert_eq!(Season::load(&dir).unwrap().0.sessions.len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // 累计积分溢出时报错并指出玩家，而不是溢出
+        let mut extreme = Scoreboard::new();
+        let a = extreme.add_player("a".to_string()).unwrap();
+        extreme.scores.insert(a, i32::MAX);
+        let season = Season { sessions: vec![("day1".to_string(), extreme.clone()), ("day2".to_string(), extreme)] };
+        let err = season.aggregate().err().unwrap();
+        assert!(err.contains("'a'") && err.contains("day2"), "{}", err);
+    }
+
+    #[test]
+    fn test_season_player_mapping() {
+        let board = |names: &[(usize, &str, i32)]| {
+            let mut board = Scoreboard::new();
+            for (id, name, score) in names {
+                board.players.insert(PlayerId(*id), name.to_string());
+                board.scores.insert(PlayerId(*id), *score);
             }
+            board
+        };
+        let season = Season {
+            sessions: vec![
+                ("第一天".to_string(), board(&[(1, "张三", 2), (2, "Alice", -1), (3, "José", -1)])),
+                // 同一人序号不同、全角字母和多余空白不影响合并；大小写或重音不同的名称不合并
+                ("第二天".to_string(), board(&[(1, "Ａlice", 3), (5, "张三", -3), (7, "jose", 0)])),
+                // 同一比赛日中规范化后相同的两名玩家分开统计
+                ("第三天".to_string(), board(&[(1, "Bob  Li", 1), (2, "Bob Li", -1)])),
+                // 之后写法完全相同的玩家合并到对应的那一位
+                ("第四天".to_string(), board(&[(1, "Bob Li", 2)])),
+            ],
+        };
+
+        let mapping = season.player_mapping();
+        // 每项为 "赛季序号 名称 <- 比赛日位置:序号 ..."
+        let players: Vec<String> = mapping
+            .players
+            .iter()
+            .map(|(id, (name, sources))| {
+                let sources: Vec<String> = sources.iter().map(|(day, id)| format!("{}:{}", day, id)).collect();
+                format!("{} {} <- {}", id, name, sources.join(" "))
+            })
+            .collect();
+        assert_eq!(
+            players,
+            vec![
+                "1 张三 <- 0:1 1:5",
+                "2 Alice <- 0:2 1:1",
+                "3 José <- 0:3",
+                "4 jose <- 1:7",
+                "5 Bob  Li <- 2:1",
+                "6 Bob Li <- 2:2 3:1",
+            ]
+        );
+        assert_eq!(mapping.warnings.len(), 2, "{:?}", mapping.warnings);
+        assert!(mapping.warnings.iter().any(|w| w.starts_with("第三天: 'Bob  Li'、'Bob Li' 规范化后")));
+        assert!(mapping.warnings.iter().any(|w| w.contains("'José'、'jose' 名称相近")));
+
+        let total = season.aggregate().unwrap();
+        assert_eq!(total.scores[&PlayerId(1)], -1);
+        assert_eq!(total.scores[&PlayerId(2)], 2);
+        assert_eq!(total.scores[&PlayerId(6)], 1);
+        assert_eq!(total.next_id, 7);
+    }
+
+    #[test]
+    fn test_ranks_follow_score_edits() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.display_scoreboard();
+        assert_eq!(scoreboard.ranks()[&a], 1);
+
+        // 直接修改积分（如手动编辑存档后加载）后，名次和所有依赖名次的查询立即更新
+        scoreboard.scores.insert(c, 5);
+        assert_eq!(scoreboard.ranks()[&c], 1);
+        assert_eq!(scoreboard.ranks()[&a], 2);
+        assert_eq!(scoreboard.player_record(c).unwrap().rank, 1);
+        assert_eq!(scoreboard.rank_gaps()[0], (c, a, 3));
+        assert!(scoreboard.standings_text(StandingsFormat::Plain).starts_with("1. C 5分\n"));
+        // 上次显示时 C 与 B 并列第 2 名
+        assert_eq!(scoreboard.rank_deltas()[&c], 1);
+
+        // 转移积分后同样立即生效
+        scoreboard.split_player(c, "D".to_string(), 4).unwrap();
+        let d = scoreboard.find_by_name("D").unwrap();
+        assert_eq!(scoreboard.ranks()[&d], 1);
+        assert_eq!(scoreboard.ranks()[&a], 2);
+        assert_eq!(scoreboard.ranks()[&c], 3);
+        assert_eq!(scoreboard.ranks()[&b], 4);
+    }
+
+    #[test]
+    fn test_score_histogram() {
+        let mut scoreboard = Scoreboard::new();
+        assert!(scoreboard.score_histogram(5).is_empty());
+
+        for (name, score) in [("A", 7), ("B", -1), ("C", -5), ("D", -6), ("E", 0), ("F", 14)] {
+            let id = scoreboard.add_player(name.to_string()).unwrap();
+            scoreboard.scores.insert(id, score);
         }
+        // 负积分向下取整分组，中间没有玩家的区间人数为 0
+        let histogram: Vec<(i32, usize)> = scoreboard.score_histogram(5).into_iter().collect();
+        assert_eq!(histogram, vec![(-10, 1), (-5, 2), (0, 1), (5, 1), (10, 1)]);
+        let histogram: Vec<(i32, usize)> = scoreboard.score_histogram(10).into_iter().collect();
+        assert_eq!(histogram, vec![(-10, 3), (0, 2), (10, 1)]);
+        // 区间大小小于 1 时按 1 计算
+        assert_eq!(scoreboard.score_histogram(0).len(), 21);
+        assert_eq!(scoreboard.score_histogram(0).values().sum::<usize>(), 6);
+
+        scoreboard.scores.insert(PlayerId(1), i32::MIN);
+        scoreboard.scores.insert(PlayerId(6), i32::MAX);
+        let histogram = scoreboard.score_histogram(i32::MAX);
+        assert_eq!(histogram.values().sum::<usize>(), 6);
+        // 区间过多时不补齐空区间
+        let histogram = scoreboard.score_histogram(1);
+        assert_eq!(histogram.len(), 6);
     }
-    
-    println!("\n玩家录入完成！");
-    scoreboard.list_players();
-    scoreboard.display_scoreboard();
-    
-    // 游戏循环
-    loop {
-        println!("请选择操作:");
-        println!("1. 记录游戏结果（输入胜出玩家序号）");
-        println!("2. 查看积分榜");
-        println!("3. 查看玩家列表");
-        println!("4. 退出程序");
-        
-        let choice = get_input_safe("请输入选择 (1-4): ");
-        
-        match choice.as_str() {
-            "1" => {
-                scoreboard.list_players();
-                let winner_input = get_input_safe("请输入胜出玩家的序号: ");
-                
-                // 增强数字输入验证
-                if winner_input.is_empty() {
-                    println!("输入不能为空！");
-                    continue;
-                }
-                
-                // 检查是否包含非数字字符
-                if !winner_input.chars().all(|c| c.is_ascii_digit()) {
-                    println!("请输入有效的正整数！");
-                    continue;
-                }
-                
-                match winner_input.parse::<usize>() {
-                    Ok(winner_id) => {
-                        // 检查数字范围
-                        if winner_id == 0 {
-                            println!("玩家序号必须大于0！");
-                            continue;
-                        }
-                        
-                        if winner_id > 1000 {
-                            println!("玩家序号过大，请输入合理的序号！");
-                            continue;
-                        }
-                        
-                        match scoreboard.update_scores(winner_id) {
-                            Ok(()) => {
-                                println!("积分已更新！");
-                                scoreboard.display_scoreboard();
-                            }
-                            Err(e) => println!("错误: {}", e),
-                        }
-                    }
-                    Err(_) => println!("数字解析失败，请输入有效的数字！"),
+
+    #[test]
+    fn test_player_tags() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("张三".to_string()).unwrap();
+        let b = scoreboard.add_player("Bob".to_string()).unwrap();
+        assert_eq!(scoreboard.tag(a), None);
+
+        scoreboard.set_tag(a, " 👑 ").unwrap();
+        assert_eq!(scoreboard.tag(a), Some("👑"));
+        assert_eq!(scoreboard.tagged_name(a, "张三"), "👑 张三");
+        assert!(scoreboard.set_tag(b, "👑👑").is_ok());
+        // emoji 占两个显示宽度
+        assert!(scoreboard.set_tag(b, "👑👑👑").unwrap_err().contains("标记过长"));
+        assert!(scoreboard.set_tag(b, "abcde").is_err());
+        assert!(scoreboard.set_tag(b, "a\u{7}").is_err());
+        assert!(scoreboard.set_tag(PlayerId(9), "x").is_err());
+        scoreboard.set_tag(b, "").unwrap();
+        assert_eq!(scoreboard.tag(b), None);
+
+        assert!(scoreboard.standings_text(StandingsFormat::Plain).contains("1. 👑 张三 0分"));
+        // 标记写入 CSV 的 tag 列，导入后保留
+        let mut out = Vec::new();
+        scoreboard.export_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv.starts_with("id,name,score,tag\n1,张三,0,👑\n2,Bob,0,\n"), "{}", csv);
+        let imported = Scoreboard::import_csv(csv.as_bytes(), NamePolicy::default()).unwrap();
+        assert_eq!(imported.tag(a), Some("👑"));
+        assert_eq!(imported.tag(b), None);
+        assert!(Scoreboard::import_csv("id,name,score,tag\n1,张三,0\n".as_bytes(), NamePolicy::default()).is_err());
+
+        // 宽字符标记不影响表格对齐：名称列按显示宽度补齐
+        assert_eq!(visible_width(&pad_display(&scoreboard.tagged_name(a, "张三"), 15)), 15);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_export_png() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("Alice".to_string()).unwrap();
+        let b = scoreboard.add_player("张三".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+
+        let mut png = Vec::new();
+        let missing = scoreboard.export_png(&mut png).unwrap();
+        // 内置字体没有中文字形，按出现顺序报告
+        assert_eq!(missing, vec!['张', '三']);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        // 逐块检查长度和 CRC，并取出 IHDR 中的尺寸和 IDAT 数据
+        let mut offset = 8;
+        let mut kinds = Vec::new();
+        let (mut width, mut height, mut idat) = (0, 0, Vec::new());
+        while offset < png.len() {
+            let len = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+            let kind = &png[offset + 4..offset + 8];
+            let data = &png[offset + 8..offset + 8 + len];
+            let crc = u32::from_be_bytes(png[offset + 8 + len..offset + 12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(kind.iter().chain(data)));
+            match kind {
+                b"IHDR" => {
+                    width = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+                    height = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
                 }
+                b"IDAT" => idat = data.to_vec(),
+                _ => {}
             }
-            "2" => {
-                scoreboard.display_scoreboard();
-            }
-            "3" => {
-                scoreboard.list_players();
-            }
-            "4" => {
-                println!("感谢使用游戏积分板系统！再见！");
+            kinds.push(String::from_utf8(kind.to_vec()).unwrap());
+            offset += 12 + len;
+        }
+        assert_eq!(kinds, ["IHDR", "PLTE", "IDAT", "IEND"]);
+        assert!(width > 0 && height > 0);
+
+        // 解出不压缩的 deflate 块，每行为过滤类型 0 加 width 个像素
+        let mut raw = Vec::new();
+        let mut pos = 2;
+        loop {
+            let last = idat[pos] & 1 == 1;
+            let len = u16::from_le_bytes([idat[pos + 1], idat[pos + 2]]) as usize;
+            raw.extend_from_slice(&idat[pos + 5..pos + 5 + len]);
+            pos += 5 + len;
+            if last {
                 break;
             }
-            _ => {
-                println!("无效选择，请输入 1-4 之间的数字。");
-            }
         }
+        assert_eq!(u32::from_be_bytes(idat[pos..].try_into().unwrap()), adler32(&raw));
+        assert_eq!(raw.len(), height * (width + 1));
+        assert!(raw.chunks(width + 1).all(|row| row[0] == 0));
+        assert!(raw.contains(&PNG_INK));
+
+        // 纯英文名称不报告缺少字形；标题加宽作图区域
+        scoreboard.rename_player(b, "Bob".to_string()).unwrap();
+        let mut plain = Vec::new();
+        assert!(scoreboard.export_png(&mut plain).unwrap().is_empty());
+        scoreboard.set_title("Spring Cup Grand Final 2026 Opener").unwrap();
+        let mut titled = Vec::new();
+        scoreboard.export_png(&mut titled).unwrap();
+        let titled_width = u32::from_be_bytes(titled[16..20].try_into().unwrap()) as usize;
+        assert!(titled_width > width);
+    }
+
+    #[test]
+    fn test_confirm_winner() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("张三".to_string()).unwrap();
+        scoreboard.add_player("李四".to_string()).unwrap();
+
+        // 未开启时不询问
+        assert!(!scoreboard.confirm_winner);
+        assert!(confirm_winner(&scoreboard, a, |_| panic!("不应询问")));
+
+        scoreboard.confirm_winner = true;
+        let before = scoreboard.scores.clone();
+        let mut prompt = String::new();
+        let confirmed = confirm_winner(&scoreboard, a, |text| {
+            prompt = text.to_string();
+            "n".to_string()
+        });
+        assert!(prompt.contains("确认记录 '张三' 胜出？"));
+        // 回答"否"时与菜单中一样不记录，积分不变
+        assert!(!confirmed);
+        assert_eq!(scoreboard.scores, before);
+        assert_eq!(scoreboard.history.len(), 0);
+
+        for reply in ["", "y", " Yes ", "是"] {
+            assert!(confirm_winner(&scoreboard, a, |_| reply.to_string()));
+        }
+        // 序号不存在时交给记录方法报错
+        assert!(confirm_winner(&scoreboard, PlayerId(99), |_| panic!("不应询问")));
+
+        let options = parse_args(["--no-confirm-winner"].iter().map(|s| s.to_string())).unwrap();
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert!(!scoreboard.confirm_winner);
+        let options = parse_args(["--confirm-winner"].iter().map(|s| s.to_string())).unwrap();
+        apply_options(&options, &mut scoreboard).unwrap();
+        assert!(scoreboard.confirm_winner);
+    }
+
+    #[test]
+    fn test_help_text() {
+        let mut scoreboard = Scoreboard::new();
+        scoreboard.add_player("A".to_string()).unwrap();
+        scoreboard.add_player("B".to_string()).unwrap();
+        let help = help_text(&scoreboard, false, false);
+        // 每条命令都出现在帮助中
+        assert!(MENU_COMMANDS.iter().all(|command| help.contains(command.label)));
+        assert!(help.contains("逐轮淘汰: 关闭"));
+        assert!(help.contains("目标积分: 无"));
+        assert!(!help.contains("练习模式: 开启"));
+
+        scoreboard.elimination = true;
+        scoreboard.target_score = Some(10);
+        scoreboard.stop_at_target = true;
+        let help = help_text(&scoreboard, true, true);
+        assert!(help.contains("逐轮淘汰: 开启（剩余 2 人）"));
+        assert!(help.contains("目标积分: 10（有玩家达到后不再记录对局）"));
+        assert!(help.contains("只读模式: 开启"));
+        assert!(help.contains("练习模式: 开启"));
+        // 只读模式下隐藏修改类命令
+        assert!(!help.contains(find_command("1").unwrap().description));
+    }
+
+    #[test]
+    fn test_matchup_matrix() {
+        let mut scoreboard = Scoreboard::new();
+        let a = scoreboard.add_player("A".to_string()).unwrap();
+        let b = scoreboard.add_player("B".to_string()).unwrap();
+        let c = scoreboard.add_player("C".to_string()).unwrap();
+        scoreboard.add_player("D".to_string()).unwrap();
+        scoreboard.update_scores(a).unwrap();
+        scoreboard.update_scores_among(b, &[a, b]).unwrap();
+        scoreboard.update_scores_among(a, &[a, c]).unwrap();
+        // 删除的玩家不再出现在矩阵中
+        scoreboard.players.remove(&c);
+
+        // D 没有赢过任何对局
+        assert_eq!(
+            scoreboard.matchup_matrix(),
+            vec![vec![0, 1, 1], vec![1, 0, 0], vec![0, 0, 0]]
+        );
+
+        let mut output = Vec::new();
+        scoreboard.export_matchups_csv(&mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        assert_eq!(csv, "winner\\opponent,A,B,D\nA,,1,1\nB,1,,0\nD,0,0,\n");
+
+        let mut output = Vec::new();
+        scoreboard.export_matchups_markdown(&mut output).unwrap();
+        let markdown = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0], "| 胜者 \\ 对手 | A | B | D |");
+        assert_eq!(lines[1], "|---|---|---|---|");
+        assert_eq!(lines[2], "| A | - | 1 | 1 |");
+        assert_eq!(lines[4], "| D | 0 | 0 | - |");
+    }
+
+    #[test]
+    fn test_read_only_commands() {
+        let allowed: Vec<MenuAction> = MENU_COMMANDS
+            .iter()
+            .filter(|c| c.read_only)
+            .map(|c| c.action)
+            .collect();
+        assert!(allowed.contains(&MenuAction::ShowScoreboard));
+        assert!(allowed.contains(&MenuAction::Quit));
+        assert!(allowed.contains(&MenuAction::Help));
+        assert!(!allowed.contains(&MenuAction::RecordWin));
+        assert!(!allowed.contains(&MenuAction::SplitPlayer));
+        assert!(!allowed.contains(&MenuAction::Save));
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13:20 UTC");
     }
 }